@@ -0,0 +1,334 @@
+//! Criterion benchmarks of parsing, rendering, reading font faces and the
+//! font scan, to catch performance regressions.
+//!
+//! cargo bench --features bench --bench conversion
+//!
+//! The documents and the font directory are generated here rather than kept
+//! in the repository.
+
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use docxside_pdf::bench;
+
+const WML_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const WORDS: &[&str] = &[
+    "the",
+    "layout",
+    "of",
+    "a",
+    "paragraph",
+    "depends",
+    "on",
+    "every",
+    "word",
+    "in",
+    "it",
+    "and",
+    "tables",
+    "measure",
+    "their",
+    "cells",
+    "before",
+    "the",
+    "columns",
+    "settle",
+    "on",
+    "widths",
+    "that",
+    "fit",
+    "pages",
+    "break",
+    "where",
+    "lines",
+    "no",
+    "longer",
+    "fit",
+    "below",
+    "margin",
+];
+
+/// `count` words picked from `WORDS` by a fixed sequence, so that every run
+/// generates the same text.
+fn words(seed: &mut u32, count: usize) -> String {
+    let mut text = Vec::with_capacity(count);
+    for _ in 0..count {
+        *seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        text.push(WORDS[(*seed >> 16) as usize % WORDS.len()]);
+    }
+    text.join(" ")
+}
+
+/// A DOCX with `body` as the content of its body, on Letter pages.
+fn docx(body: &str) -> Vec<u8> {
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="{WML_NS}"><w:body>{body}<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/></w:sectPr></w:body></w:document>"#
+    );
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in [
+        ("[Content_Types].xml", CONTENT_TYPES),
+        ("_rels/.rels", PACKAGE_RELS),
+        ("word/document.xml", &document),
+    ] {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(data.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Paragraphs of about 60 words, each with a bold run in the middle.
+fn text_body(paragraphs: usize) -> String {
+    let mut seed = 1;
+    let mut body = String::new();
+    for _ in 0..paragraphs {
+        body += &format!(
+            r#"<w:p><w:r><w:t xml:space="preserve">{} </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t xml:space="preserve">{} </w:t></w:r><w:r><w:t>{}</w:t></w:r></w:p>"#,
+            words(&mut seed, 30),
+            words(&mut seed, 5),
+            words(&mut seed, 25),
+        );
+    }
+    body
+}
+
+/// Tables of 20 rows and 5 columns, separated by a paragraph.
+fn tables_body(tables: usize) -> String {
+    let mut seed = 2;
+    let mut body = String::new();
+    for _ in 0..tables {
+        body += r#"<w:tbl><w:tblGrid>"#;
+        body += &r#"<w:gridCol w:w="1872"/>"#.repeat(5);
+        body += "</w:tblGrid>";
+        for _ in 0..20 {
+            body += "<w:tr>";
+            for _ in 0..5 {
+                body += &format!(
+                    r#"<w:tc><w:tcPr><w:tcW w:w="1872" w:type="dxa"/></w:tcPr><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:tc>"#,
+                    words(&mut seed, 4)
+                );
+            }
+            body += "</w:tr>";
+        }
+        body += "</w:tbl>";
+        body += &format!("<w:p><w:r><w:t>{}</w:t></w:r></w:p>", words(&mut seed, 12));
+    }
+    body
+}
+
+/// A TrueType file with only the tables the font index reads, and `outlines`
+/// bytes standing in for the glyphs it skips.
+fn font_file(family: &str, bold: bool, italic: bool, outlines: usize) -> Vec<u8> {
+    let mut head = vec![0; 54];
+    head[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+    let mut hhea = vec![0; 36];
+    hhea[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    hhea[34..36].copy_from_slice(&1u16.to_be_bytes());
+    let maxp = vec![0, 0, 0x50, 0, 0, 1];
+
+    let mut os2 = vec![0; 78];
+    os2[4..6].copy_from_slice(&(if bold { 700u16 } else { 400 }).to_be_bytes());
+    let fs_selection = italic as u16 | (bold as u16) << 5 | ((!bold && !italic) as u16) << 6;
+    os2[62..64].copy_from_slice(&fs_selection.to_be_bytes());
+
+    let style = match (bold, italic) {
+        (false, false) => "Regular",
+        (true, false) => "Bold",
+        (false, true) => "Italic",
+        (true, true) => "Bold Italic",
+    };
+    let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(u16::to_be_bytes).collect() };
+    let strings = [(1u16, utf16(family)), (2, utf16(style))];
+    let mut name = Vec::new();
+    name.extend(0u16.to_be_bytes());
+    name.extend((strings.len() as u16).to_be_bytes());
+    name.extend((6 + 12 * strings.len() as u16).to_be_bytes());
+    let mut offset = 0u16;
+    for (name_id, text) in &strings {
+        for field in [3, 1, 0x0409, *name_id, text.len() as u16, offset] {
+            name.extend(field.to_be_bytes());
+        }
+        offset += text.len() as u16;
+    }
+    for (_, text) in &strings {
+        name.extend(text);
+    }
+
+    // Tables in tag order, each starting on a 4-byte boundary
+    let tables = [
+        (b"OS/2", os2),
+        (b"glyf", vec![0; outlines]),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"maxp", maxp),
+        (b"name", name),
+    ];
+    let mut font = Vec::new();
+    font.extend(0x0001_0000u32.to_be_bytes());
+    font.extend((tables.len() as u16).to_be_bytes());
+    font.extend([0; 6]);
+    let mut data_offset = 12 + 16 * tables.len();
+    let mut data = Vec::new();
+    for (tag, table) in &tables {
+        font.extend(*tag);
+        font.extend(0u32.to_be_bytes());
+        font.extend((data_offset as u32).to_be_bytes());
+        font.extend((table.len() as u32).to_be_bytes());
+        data.extend(table);
+        data.resize(data.len().next_multiple_of(4), 0);
+        data_offset = 12 + 16 * tables.len() + data.len();
+    }
+    font.extend(data);
+    font
+}
+
+/// A font directory of `families` subdirectories, each with the four styles
+/// of a family. Returns the directory and the number of faces in it.
+fn font_dir(families: usize) -> (PathBuf, usize) {
+    let dir = std::env::temp_dir().join("docxside-pdf-bench-fonts");
+    let _ = std::fs::remove_dir_all(&dir);
+    for i in 0..families {
+        let family = format!("Bench Sans {i}");
+        let family_dir = dir.join(&family);
+        std::fs::create_dir_all(&family_dir).unwrap();
+        for (bold, italic, style) in [
+            (false, false, "Regular"),
+            (true, false, "Bold"),
+            (false, true, "Italic"),
+            (true, true, "BoldItalic"),
+        ] {
+            let path = family_dir.join(format!("BenchSans{i}-{style}.ttf"));
+            std::fs::write(path, font_file(&family, bold, italic, 256 * 1024)).unwrap();
+        }
+    }
+    (dir, families * 4)
+}
+
+fn parse_text(c: &mut Criterion) {
+    let input = docx(&text_body(2000));
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("text, 2000 paragraphs", |b| {
+        b.iter(|| bench::parse(&input).unwrap())
+    });
+    group.finish();
+}
+
+fn parse_tables(c: &mut Criterion) {
+    let input = docx(&tables_body(100));
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("tables, 100 of 20x5", |b| {
+        b.iter(|| bench::parse(&input).unwrap())
+    });
+    group.finish();
+}
+
+fn render_pages(c: &mut Criterion) {
+    let doc = bench::parse(&docx(&text_body(435))).unwrap();
+    let pages = bench::render(doc.clone()).unwrap();
+    let pages = pages
+        .windows(b"/Type /Page\n".len())
+        .filter(|w| w == b"/Type /Page\n")
+        .count();
+    let mut group = c.benchmark_group("render");
+    group.sample_size(20);
+    group.bench_function(format!("text, {pages} pages"), |b| {
+        b.iter_batched(
+            || doc.clone(),
+            |doc| bench::render(doc).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn encode_words(c: &mut Criterion) {
+    let doc = bench::parse(&docx(&text_body(435))).unwrap();
+    assert_eq!(
+        bench::encode_words(&doc, false).unwrap(),
+        bench::encode_words(&doc, true).unwrap(),
+        "reusing encoded words changed their width"
+    );
+    let mut group = c.benchmark_group("layout");
+    group.sample_size(20);
+    for (name, reuse) in [("encoding every word", false), ("reusing encodings", true)] {
+        group.bench_function(name, |b| {
+            b.iter(|| bench::encode_words(&doc, reuse).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn font_faces(c: &mut Criterion) {
+    let (dir, faces) = font_dir(50);
+    let files: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .unwrap()
+        .flat_map(|family| std::fs::read_dir(family.unwrap().path()).unwrap())
+        .map(|file| file.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), faces);
+    for file in &files {
+        assert_eq!(
+            bench::faces_from_whole_file(file),
+            bench::faces_from_tables(file),
+            "{}: the two readers disagree",
+            file.display()
+        );
+    }
+    let mut group = c.benchmark_group("font faces");
+    group.bench_function("whole files, 200", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .map(|f| bench::faces_from_whole_file(f))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("tables only, 200", |b| {
+        b.iter(|| {
+            files
+                .iter()
+                .map(|f| bench::faces_from_tables(f))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+fn font_scan(c: &mut Criterion) {
+    let (dir, faces) = font_dir(50);
+    let dirs = [dir];
+    assert_eq!(
+        bench::index_font_dirs(&dirs),
+        faces,
+        "generated fonts were not indexed"
+    );
+    c.bench_function("font scan, 200 files", |b| {
+        b.iter(|| bench::index_font_dirs(&dirs))
+    });
+    let _ = std::fs::remove_dir_all(&dirs[0]);
+}
+
+criterion_group!(
+    benches,
+    parse_text,
+    parse_tables,
+    render_pages,
+    encode_words,
+    font_faces,
+    font_scan
+);
+criterion_main!(benches);
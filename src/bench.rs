@@ -0,0 +1,113 @@
+//! Internals exposed to the benchmarks in `benches/`, behind the `bench`
+//! feature. Not part of the API.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use pdf_writer::{Pdf, Ref};
+
+use crate::error::Error;
+use crate::fonts::FontEntry;
+use crate::model::{Block, Document};
+use crate::{ConversionOptions, docx, fonts, pdf};
+
+/// Parse a DOCX held in memory.
+pub fn parse(docx: &[u8]) -> Result<Document, Error> {
+    docx::parse_reader(Cursor::new(docx), String::new(), &mut Vec::new())
+}
+
+/// Render a parsed document with the default options, without the copy
+/// `render_pdf` makes to split runs for fallback fonts. Rendering moves the
+/// bytes of pictures out of the document, so it is taken by value.
+pub fn render(mut doc: Document) -> Result<Vec<u8>, Error> {
+    pdf::render(&mut doc, &ConversionOptions::default(), &mut Vec::new())
+}
+
+/// Index the fonts in `dirs` and their subdirectories, bypassing the font
+/// cache. Returns the number of faces found.
+pub fn index_font_dirs(dirs: &[PathBuf]) -> usize {
+    fonts::index_font_dirs(dirs.to_vec(), None).len()
+}
+
+/// Index of a face in its file, family, bold and italic.
+pub type FaceStyle = (u32, String, bool, bool);
+
+/// Faces of a font file read the way the font index once did: the whole
+/// file, parsed in full.
+pub fn faces_from_whole_file(path: &Path) -> Vec<FaceStyle> {
+    let Ok(data) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    let is_collection = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("ttc"));
+    let face_count = if is_collection {
+        ttf_parser::fonts_in_collection(&data).unwrap_or(1)
+    } else {
+        1
+    };
+    (0..face_count)
+        .filter_map(|index| {
+            let (family, bold, italic) = fonts::read_font_style(&data, index)?;
+            Some((index, family, bold, italic))
+        })
+        .collect()
+}
+
+/// Faces of a font file read the way the font index does: only the tables
+/// holding its names and style.
+pub fn faces_from_tables(path: &Path) -> Vec<FaceStyle> {
+    fonts::read_file_faces(path)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|face| (face.index, face.family, face.bold, face.italic))
+        .collect()
+}
+
+/// Encode every word of the body's paragraphs in the font of its run, twice
+/// over as layout measures words more than once, either through the font's
+/// memo, as layout does, or encoding each word afresh, as it once did.
+/// Returns the total width of the words in units of 1000 per em.
+pub fn encode_words(doc: &Document, reuse: bool) -> Result<f32, Error> {
+    let mut pdf = Pdf::new();
+    let mut next_id = 1i32;
+    let mut alloc = || {
+        let r = Ref::new(next_id);
+        next_id += 1;
+        r
+    };
+    let mut entries: HashMap<String, FontEntry> = HashMap::new();
+    let mut width = 0.0;
+    for _ in 0..2 {
+        for block in &doc.blocks {
+            let Block::Paragraph(para) = block else {
+                continue;
+            };
+            for run in &para.runs {
+                let key = fonts::font_key(run);
+                if !entries.contains_key(&key) {
+                    let entry = fonts::register_font(
+                        &mut pdf,
+                        fonts::primary_font_name(&run.font_name),
+                        (run.bold, run.italic),
+                        format!("F{}", entries.len() + 1),
+                        &mut alloc,
+                        &doc.embedded_fonts,
+                        false,
+                    )?;
+                    entries.insert(key.clone(), entry);
+                }
+                let entry = &entries[&key];
+                for word in run.text.split_whitespace() {
+                    width += if reuse {
+                        entry.encode(word).width_1000
+                    } else {
+                        entry.encode_text(word).width_1000
+                    };
+                }
+            }
+        }
+    }
+    Ok(width)
+}
@@ -1,28 +1,110 @@
-use std::collections::HashMap;
-use std::io::Read;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Read, Seek};
 use std::path::Path;
+use std::sync::Arc;
 
-use crate::error::Error;
+use crate::error::{Error, Warning};
+use crate::fonts::EmbeddedFonts;
 use crate::model::{
-    Alignment, Block, Document, EmbeddedImage, FieldCode, HeaderFooter, Paragraph, Run,
-    TabAlignment, TabStop, Table, TableCell, TableRow, VertAlign,
+    Alignment, Block, BorderStyle, CellBorder, CellBorders, CellMargins, CellVAlign, Document,
+    EmbeddedImage, FieldCode, FloatPosition, FloatRelative, HeaderFooter, ImageFormat, LabelSuffix,
+    LineSpacing, LinkTarget, ListLabel, Metadata, PageBorder, PageBorders, PageVAlign, Paragraph,
+    RowHeightRule, Run, Section, SectionBreak, TabAlignment, TabStop, Table, TableCell, TableRow,
+    TextBox, UnderlineStyle, VMerge, VertAlign,
 };
+use crate::png;
 
 struct LevelDef {
     num_fmt: String,
     lvl_text: String,
+    jc: Alignment, // w:lvlJc, label alignment at the number position
+    label_props: LabelProps,
+    suffix: LabelSuffix,
+    start: u32,
+    restart: Option<u8>, // w:lvlRestart: 1-based level whose use restarts this one, 0 = never
+    is_legal: bool,      // w:isLgl shows all referenced levels as decimal
+    p_style: Option<String>, // w:pStyle: paragraphs of this style use this level
     indent_left: f32,
     indent_hanging: f32,
 }
 
+/// Numbering state of the document body. Counters belong to the abstractNum,
+/// so lists sharing one continue each other's numbering unless a `w:num`
+/// restarts a level with an override when it is first used.
+#[derive(Default)]
+struct ListCounters {
+    // abstractNumId -> current counter of each level, None before it is used
+    by_abstract: HashMap<String, [Option<u32>; 9]>,
+    seen_nums: HashSet<String>,
+}
+
+/// Run properties from a level's `w:rPr`; unset values follow the paragraph text.
+struct LabelProps {
+    font_name: Option<String>,
+    font_size: Option<f32>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    color: Option<[u8; 3]>,
+}
+
+/// A `w:lvlOverride` of one `w:num`, layered over its abstractNum level.
+struct LevelOverride {
+    start: Option<u32>,      // w:startOverride
+    level: Option<LevelDef>, // a complete replacement w:lvl
+}
+
 struct NumberingInfo {
     abstract_nums: HashMap<String, HashMap<u8, LevelDef>>,
     num_to_abstract: HashMap<String, String>,
+    overrides: HashMap<String, HashMap<u8, LevelOverride>>, // numId -> ilvl -> override
+}
+
+impl NumberingInfo {
+    /// The effective definition of a level of a numId, and its start value.
+    fn level(&self, num_id: &str, ilvl: u8) -> Option<(&LevelDef, u32)> {
+        let ov = self.overrides.get(num_id).and_then(|o| o.get(&ilvl));
+        let def = ov.and_then(|o| o.level.as_ref()).or_else(|| {
+            self.num_to_abstract
+                .get(num_id)
+                .and_then(|abs_id| self.abstract_nums.get(abs_id))
+                .and_then(|levels| levels.get(&ilvl))
+        })?;
+        let start = ov.and_then(|o| o.start).unwrap_or(def.start);
+        Some((def, start))
+    }
+
+    /// The level of a numId that is linked to a paragraph style via `lvl/pStyle`.
+    fn style_level(&self, num_id: &str, style_id: &str) -> Option<u8> {
+        let linked = |levels: &HashMap<u8, LevelDef>| {
+            levels
+                .iter()
+                .find(|(_, def)| def.p_style.as_deref() == Some(style_id))
+                .map(|(&ilvl, _)| ilvl)
+        };
+        let overridden = self.overrides.get(num_id).and_then(|o| {
+            o.iter()
+                .find(|(_, ov)| {
+                    ov.level
+                        .as_ref()
+                        .is_some_and(|def| def.p_style.as_deref() == Some(style_id))
+                })
+                .map(|(&ilvl, _)| ilvl)
+        });
+        overridden.or_else(|| {
+            self.num_to_abstract
+                .get(num_id)
+                .and_then(|abs_id| self.abstract_nums.get(abs_id))
+                .and_then(linked)
+        })
+    }
 }
 
 const WML_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
 const DML_NS: &str = "http://schemas.openxmlformats.org/drawingml/2006/main";
 const WPD_NS: &str = "http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing";
+const MC_NS: &str = "http://schemas.openxmlformats.org/markup-compatibility/2006";
+const VML_NS: &str = "urn:schemas-microsoft-com:vml";
+const MATH_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/math";
 
 fn twips_to_pts(twips: f32) -> f32 {
     twips / 20.0
@@ -38,6 +120,60 @@ fn parse_hex_color(val: &str) -> Option<[u8; 3]> {
     Some([r, g, b])
 }
 
+/// Width of a `w:tblW` of type pct as a fraction: fiftieths of a percent, or
+/// an explicit percentage like "100%".
+fn pct_width(node: roxmltree::Node) -> Option<f32> {
+    if node.attribute((WML_NS, "type")) != Some("pct") {
+        return None;
+    }
+    let val = node.attribute((WML_NS, "w"))?;
+    let fraction = match val.strip_suffix('%') {
+        Some(pct) => pct.parse::<f32>().ok()? / 100.0,
+        None => val.parse::<f32>().ok()? / 5000.0,
+    };
+    (fraction > 0.0).then_some(fraction)
+}
+
+/// Color of an element that names a theme color or holds a hex value in
+/// `attr` ("val", "color" or "fill"). Theme colors take precedence and get
+/// their tint (towards white) and shade (towards black) applied.
+fn element_color(node: roxmltree::Node, attr: &str, theme: &Theme) -> Option<[u8; 3]> {
+    let (theme_attr, tint_attr, shade_attr) = if attr == "fill" {
+        ("themeFill", "themeFillTint", "themeFillShade")
+    } else {
+        ("themeColor", "themeTint", "themeShade")
+    };
+    let factor = |name: &str| {
+        node.attribute((WML_NS, name))
+            .and_then(|v| u8::from_str_radix(v, 16).ok())
+            .map(|v| v as f32 / 255.0)
+    };
+    let themed = node
+        .attribute((WML_NS, theme_attr))
+        .and_then(|name| theme.color(name))
+        .map(|rgb| {
+            let mut rgb = rgb.map(|c| c as f32);
+            if let Some(tint) = factor(tint_attr) {
+                rgb = rgb.map(|c| c * tint + 255.0 * (1.0 - tint));
+            }
+            if let Some(shade) = factor(shade_attr) {
+                rgb = rgb.map(|c| c * shade);
+            }
+            rgb.map(|c| c.round().clamp(0.0, 255.0) as u8)
+        });
+    themed.or_else(|| node.attribute((WML_NS, attr)).and_then(parse_hex_color))
+}
+
+/// `w:color` child of a run property node.
+fn run_color(rpr: roxmltree::Node, theme: &Theme) -> Option<[u8; 3]> {
+    wml(rpr, "color").and_then(|c| element_color(c, "val", theme))
+}
+
+/// Fill color of a `w:shd` element.
+fn shading_fill(shd: roxmltree::Node, theme: &Theme) -> Option<[u8; 3]> {
+    element_color(shd, "fill", theme)
+}
+
 fn wml<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<roxmltree::Node<'a, 'a>> {
     node.children()
         .find(|n| n.tag_name().name() == name && n.tag_name().namespace() == Some(WML_NS))
@@ -47,13 +183,220 @@ fn wml_attr<'a>(node: roxmltree::Node<'a, 'a>, child: &str) -> Option<&'a str> {
     wml(node, child).and_then(|n| n.attribute((WML_NS, "val")))
 }
 
+/// Read an on/off run property like `<w:b/>` or `<w:caps w:val="0"/>`.
+/// Returns None when the element is absent so callers can fall back to the style.
+fn wml_toggle(node: roxmltree::Node, child: &str) -> Option<bool> {
+    wml(node, child).map(|n| {
+        n.attribute((WML_NS, "val"))
+            .is_none_or(|v| v != "0" && v != "false")
+    })
+}
+
 fn twips_attr(node: roxmltree::Node, attr: &str) -> Option<f32> {
     node.attribute((WML_NS, attr))
         .and_then(|v| v.parse::<f32>().ok())
         .map(twips_to_pts)
 }
 
-fn parse_border_bottom(ppr: roxmltree::Node) -> Option<crate::model::BorderBottom> {
+/// `w:spacing @line`, read as twips for exact and atLeast rules and as
+/// 240ths of a line otherwise.
+fn parse_line_spacing(spacing: roxmltree::Node) -> Option<LineSpacing> {
+    let line = spacing.attribute((WML_NS, "line"))?.parse::<f32>().ok()?;
+    Some(match spacing.attribute((WML_NS, "lineRule")) {
+        Some("exact") => LineSpacing::Exact(twips_to_pts(line)),
+        Some("atLeast") => LineSpacing::AtLeast(twips_to_pts(line)),
+        _ => LineSpacing::Auto(line / 240.0),
+    })
+}
+
+/// Space Word gives paragraphs with `w:beforeAutospacing`/`w:afterAutospacing`,
+/// as set on paragraphs that came from HTML.
+const AUTO_PARAGRAPH_SPACING: f32 = 14.0;
+
+/// Height of one line of text in `font_size` under `spacing`, the unit of
+/// `w:beforeLines`/`w:afterLines`.
+fn nominal_line_height(font_size: f32, spacing: LineSpacing) -> f32 {
+    match spacing {
+        LineSpacing::Auto(factor) => font_size * 1.2 * factor,
+        LineSpacing::Exact(pt) | LineSpacing::AtLeast(pt) => pt,
+    }
+}
+
+/// Space before or after a paragraph (`side` is "before" or "after") from its
+/// `w:spacing`. Autospacing overrides the explicit values, and a value in
+/// hundredths of a line (`w:beforeLines`) overrides the one in twips.
+fn paragraph_spacing(spacing: roxmltree::Node, side: &str, line_h: f32) -> Option<f32> {
+    let auto = spacing
+        .attribute((WML_NS, format!("{side}Autospacing").as_str()))
+        .is_some_and(|v| matches!(v, "1" | "true" | "on"));
+    if auto {
+        return Some(AUTO_PARAGRAPH_SPACING);
+    }
+    spacing
+        .attribute((WML_NS, format!("{side}Lines").as_str()))
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|lines| lines / 100.0 * line_h)
+        .or_else(|| twips_attr(spacing, side))
+}
+
+/// Decimal separator of a language tag such as "de-DE": a comma for most of
+/// continental Europe and a few others, a point otherwise.
+fn decimal_separator(lang: &str) -> char {
+    let primary = lang
+        .split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match primary.as_str() {
+        "af" | "az" | "be" | "bg" | "bs" | "ca" | "cs" | "da" | "de" | "el" | "es" | "et"
+        | "eu" | "fi" | "fo" | "fr" | "gl" | "hr" | "hu" | "hy" | "id" | "is" | "it" | "ka"
+        | "kk" | "lt" | "lv" | "mk" | "nb" | "nl" | "nn" | "no" | "pl" | "pt" | "ro" | "ru"
+        | "sk" | "sl" | "sq" | "sr" | "sv" | "tr" | "uk" | "uz" | "vi" => ',',
+        _ => '.',
+    }
+}
+
+/// Right indent of a `w:ind`, also accepting the bidi-neutral `w:end`.
+fn indent_right_attr(ind: roxmltree::Node) -> Option<f32> {
+    twips_attr(ind, "right").or_else(|| twips_attr(ind, "end"))
+}
+
+/// First-line indent of a `w:ind`. A hanging indent replaces any first-line
+/// indent inherited from the style, so it counts as an explicit zero.
+fn indent_first_line_attr(ind: roxmltree::Node) -> Option<f32> {
+    twips_attr(ind, "firstLine").or_else(|| twips_attr(ind, "hanging").map(|_| 0.0))
+}
+
+/// Format a 1-based note, list or page number in a `w:numFmt` style.
+pub(crate) fn format_number(n: usize, fmt: &str) -> String {
+    match fmt {
+        "lowerRoman" => to_roman(n).to_lowercase(),
+        "upperRoman" => to_roman(n),
+        "lowerLetter" => to_letters(n).to_lowercase(),
+        "upperLetter" => to_letters(n),
+        "decimalZero" => format!("{n:02}"),
+        "ordinal" => format!("{n}{}", ordinal_suffix(n)),
+        "cardinalText" => capitalize(&to_words(n)),
+        "ordinalText" => capitalize(&to_ordinal_words(n)),
+        "none" => String::new(),
+        _ => n.to_string(),
+    }
+}
+
+fn ordinal_suffix(n: usize) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// English cardinal number words, e.g. 42 -> "forty-two".
+fn to_words(n: usize) -> String {
+    const ONES: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+    match n {
+        0..=19 => ONES[n].to_string(),
+        20..=99 if n.is_multiple_of(10) => TENS[n / 10].to_string(),
+        20..=99 => format!("{}-{}", TENS[n / 10], ONES[n % 10]),
+        100..=999 if n.is_multiple_of(100) => format!("{} hundred", ONES[n / 100]),
+        100..=999 => format!("{} hundred {}", ONES[n / 100], to_words(n % 100)),
+        _ if n.is_multiple_of(1000) => format!("{} thousand", to_words(n / 1000)),
+        _ => format!("{} thousand {}", to_words(n / 1000), to_words(n % 1000)),
+    }
+}
+
+/// English ordinal number words, e.g. 42 -> "forty-second".
+fn to_ordinal_words(n: usize) -> String {
+    let words = to_words(n);
+    // Only the last word becomes ordinal: "twenty-one" -> "twenty-first"
+    let split = words.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = words.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        _ => match last.strip_suffix('y') {
+            Some(stem) => format!("{stem}ieth"),
+            None => format!("{last}th"),
+        },
+    };
+    format!("{head}{last}")
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn to_roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for &(value, numeral) in &NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+/// Word's letter numbering repeats the letter: A..Z, AA..ZZ, AAA..
+fn to_letters(n: usize) -> String {
+    if n == 0 {
+        return String::new();
+    }
+    let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+    letter.to_string().repeat((n - 1) / 26 + 1)
+}
+
+fn parse_border_bottom(ppr: roxmltree::Node, theme: &Theme) -> Option<crate::model::BorderBottom> {
     let bottom = wml(ppr, "pBdr").and_then(|pbdr| wml(pbdr, "bottom"))?;
     let val = bottom.attribute((WML_NS, "val")).unwrap_or("none");
     if val == "none" || val == "nil" {
@@ -69,10 +412,7 @@ fn parse_border_bottom(ppr: roxmltree::Node) -> Option<crate::model::BorderBotto
         .attribute((WML_NS, "space"))
         .and_then(|v| v.parse::<f32>().ok())
         .unwrap_or(0.0);
-    let color = bottom
-        .attribute((WML_NS, "color"))
-        .and_then(parse_hex_color)
-        .unwrap_or([0, 0, 0]);
+    let color = element_color(bottom, "color", theme).unwrap_or([0, 0, 0]);
     Some(crate::model::BorderBottom {
         width_pt,
         space_pt,
@@ -80,8 +420,153 @@ fn parse_border_bottom(ppr: roxmltree::Node) -> Option<crate::model::BorderBotto
     })
 }
 
-fn border_bottom_extra(ppr: roxmltree::Node) -> f32 {
-    parse_border_bottom(ppr)
+fn parse_page_border(node: roxmltree::Node, theme: &Theme) -> Option<PageBorder> {
+    let style = match node.attribute((WML_NS, "val")).unwrap_or("none") {
+        "none" | "nil" => return None,
+        "double" => BorderStyle::Double,
+        _ => BorderStyle::Single,
+    };
+    // sz is in 1/8 of a point, space in points
+    let width_pt = node
+        .attribute((WML_NS, "sz"))
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|v| v / 8.0)
+        .unwrap_or(0.5);
+    let space_pt = node
+        .attribute((WML_NS, "space"))
+        .and_then(|v| v.parse::<f32>().ok())
+        .unwrap_or(0.0);
+    let color = element_color(node, "color", theme).unwrap_or([0, 0, 0]);
+    Some(PageBorder {
+        style,
+        width_pt,
+        space_pt,
+        color,
+    })
+}
+
+/// `w:tblBorders` or `w:tcBorders` edges. The outer `Option` is `None` when an
+/// edge isn't specified, the inner one when it is explicitly none/nil.
+#[derive(Clone, Copy, Default)]
+struct BorderSet {
+    top: Option<Option<CellBorder>>,
+    left: Option<Option<CellBorder>>,
+    bottom: Option<Option<CellBorder>>,
+    right: Option<Option<CellBorder>>,
+    inside_h: Option<Option<CellBorder>>,
+    inside_v: Option<Option<CellBorder>>,
+}
+
+impl BorderSet {
+    fn parse(borders: Option<roxmltree::Node>, theme: &Theme) -> Self {
+        let Some(borders) = borders else {
+            return Self::default();
+        };
+        let edge = |name: &str| wml(borders, name).map(|b| parse_cell_border(b, theme));
+        BorderSet {
+            top: edge("top"),
+            left: edge("left").or_else(|| edge("start")),
+            bottom: edge("bottom"),
+            right: edge("right").or_else(|| edge("end")),
+            inside_h: edge("insideH"),
+            inside_v: edge("insideV"),
+        }
+    }
+
+    /// Fill in edges not specified here from `fallback`.
+    fn or(self, fallback: BorderSet) -> Self {
+        BorderSet {
+            top: self.top.or(fallback.top),
+            left: self.left.or(fallback.left),
+            bottom: self.bottom.or(fallback.bottom),
+            right: self.right.or(fallback.right),
+            inside_h: self.inside_h.or(fallback.inside_h),
+            inside_v: self.inside_v.or(fallback.inside_v),
+        }
+    }
+}
+
+/// `w:tblCellMar` or `w:tcMar` margins; `None` when not specified.
+#[derive(Clone, Copy, Default)]
+struct MarginSet {
+    top: Option<f32>,
+    left: Option<f32>,
+    bottom: Option<f32>,
+    right: Option<f32>,
+}
+
+impl MarginSet {
+    fn parse(margins: Option<roxmltree::Node>) -> Self {
+        let Some(margins) = margins else {
+            return Self::default();
+        };
+        let edge = |name: &str| {
+            let node = wml(margins, name)?;
+            match node.attribute((WML_NS, "type")) {
+                Some("nil") => Some(0.0),
+                _ => twips_attr(node, "w"),
+            }
+        };
+        MarginSet {
+            top: edge("top"),
+            left: edge("left").or_else(|| edge("start")),
+            bottom: edge("bottom"),
+            right: edge("right").or_else(|| edge("end")),
+        }
+    }
+
+    fn or(self, fallback: MarginSet) -> Self {
+        MarginSet {
+            top: self.top.or(fallback.top),
+            left: self.left.or(fallback.left),
+            bottom: self.bottom.or(fallback.bottom),
+            right: self.right.or(fallback.right),
+        }
+    }
+
+    /// Word's defaults are 0.08" left and right, nothing above and below.
+    fn resolve(self) -> CellMargins {
+        CellMargins {
+            top: self.top.unwrap_or(0.0),
+            left: self.left.unwrap_or(5.4),
+            bottom: self.bottom.unwrap_or(0.0),
+            right: self.right.unwrap_or(5.4),
+        }
+    }
+}
+
+fn parse_cell_border(node: roxmltree::Node, theme: &Theme) -> Option<CellBorder> {
+    if matches!(node.attribute((WML_NS, "val")), None | Some("none" | "nil")) {
+        return None;
+    }
+    // sz is in 1/8 of a point
+    let width_pt = node
+        .attribute((WML_NS, "sz"))
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|v| v / 8.0)
+        .unwrap_or(0.5);
+    let color = element_color(node, "color", theme).unwrap_or([0, 0, 0]);
+    Some(CellBorder { width_pt, color })
+}
+
+fn parse_page_borders(pg_borders: roxmltree::Node, theme: &Theme) -> Option<PageBorders> {
+    let edge = |name: &str| wml(pg_borders, name).and_then(|b| parse_page_border(b, theme));
+    let borders = PageBorders {
+        offset_from_page: pg_borders.attribute((WML_NS, "offsetFrom")) == Some("page"),
+        top: edge("top"),
+        left: edge("left"),
+        bottom: edge("bottom"),
+        right: edge("right"),
+    };
+    let any = borders.top.is_some()
+        || borders.left.is_some()
+        || borders.bottom.is_some()
+        || borders.right.is_some();
+    any.then_some(borders)
+}
+
+fn border_bottom_extra(ppr: roxmltree::Node, theme: &Theme) -> f32 {
+    parse_border_bottom(ppr, theme)
         .map(|b| b.space_pt + b.width_pt)
         .unwrap_or(0.0)
 }
@@ -91,44 +576,294 @@ fn dml<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<roxmltree::Node<
         .find(|n| n.tag_name().name() == name && n.tag_name().namespace() == Some(DML_NS))
 }
 
-fn latin_typeface<'a>(node: roxmltree::Node<'a, 'a>) -> Option<&'a str> {
-    dml(node, "latin")
+/// Typeface of the `a:latin`, `a:ea` or `a:cs` child of a theme font.
+fn theme_typeface<'a>(node: roxmltree::Node<'a, 'a>, script: &str) -> Option<&'a str> {
+    dml(node, script)
         .and_then(|n| n.attribute("typeface"))
         .filter(|tf| !tf.is_empty())
 }
 
-struct ThemeFonts {
+struct Theme {
     major: String,
     minor: String,
+    // East Asian and complex script typefaces, when the theme names them
+    major_east_asia: Option<String>,
+    minor_east_asia: Option<String>,
+    major_cs: Option<String>,
+    minor_cs: Option<String>,
+    colors: HashMap<String, [u8; 3]>, // a:clrScheme entries by element name, e.g. "accent1"
+}
+
+impl Theme {
+    /// Font named by a `w:asciiTheme`, `w:hAnsiTheme`, `w:eastAsiaTheme` or
+    /// `w:cstheme` value. Script slots the theme leaves empty use its Latin font.
+    fn font(&self, name: &str) -> Option<String> {
+        let (latin, script) = match name {
+            "majorAscii" | "majorHAnsi" => (&self.major, None),
+            "minorAscii" | "minorHAnsi" => (&self.minor, None),
+            "majorEastAsia" => (&self.major, self.major_east_asia.as_ref()),
+            "minorEastAsia" => (&self.minor, self.minor_east_asia.as_ref()),
+            "majorBidi" => (&self.major, self.major_cs.as_ref()),
+            "minorBidi" => (&self.minor, self.minor_cs.as_ref()),
+            _ => return None,
+        };
+        Some(script.unwrap_or(latin).clone())
+    }
+
+    /// Color named by a `w:themeColor` or `w:themeFill` value such as "accent1" or "text1".
+    fn color(&self, name: &str) -> Option<[u8; 3]> {
+        let key = match name {
+            "dark1" | "text1" => "dk1",
+            "light1" | "background1" => "lt1",
+            "dark2" | "text2" => "dk2",
+            "light2" | "background2" => "lt2",
+            "hyperlink" => "hlink",
+            "followedHyperlink" => "folHlink",
+            other => other,
+        };
+        self.colors.get(key).copied()
+    }
 }
 
 struct StyleDefaults {
     font_size: f32,
+    font_size_cs: Option<f32>, // w:szCs, for complex script text
     font_name: String,
+    script_fonts: ScriptFonts,
     space_after: f32,
-    line_spacing: f32, // multiplier from w:spacing @line / 240
+    line_spacing: LineSpacing,
+    color: Option<[u8; 3]>,
+    bold: bool,
+    italic: bool,
+    kern: Option<f32>,    // w:kern, smallest font size in points that is kerned
+    widow_control: bool,  // Word applies it unless turned off
+    lang: Option<String>, // w:lang of rPrDefault
 }
 
 struct ParagraphStyle {
     font_size: Option<f32>,
+    font_size_cs: Option<f32>,
     font_name: Option<String>,
+    script_fonts: ScriptFonts,
     bold: Option<bool>,
     italic: Option<bool>,
     color: Option<[u8; 3]>,
+    underline: Option<UnderlineStyle>,
+    underline_color: Option<[u8; 3]>,
+    caps: Option<bool>,
+    small_caps: Option<bool>,
+    hidden: Option<bool>,
+    char_spacing: Option<f32>,
+    scale: Option<f32>,
+    kern: Option<f32>,
     space_before: f32,
     space_after: Option<f32>,
     alignment: Option<Alignment>,
     contextual_spacing: bool,
     keep_next: bool,
-    line_spacing: Option<f32>, // auto line spacing factor override
+    keep_lines: bool,
+    widow_control: Option<bool>,
+    line_spacing: Option<LineSpacing>,
+    indent_right: Option<f32>,
+    indent_first_line: Option<f32>,
     border_bottom_extra: f32,
     border_bottom: Option<crate::model::BorderBottom>,
+    num_id: Option<String>, // pPr/numPr of list and numbered heading styles
+    num_ilvl: Option<u8>,
+    outline_level: Option<u8>, // w:outlineLvl, or the level of a "heading N" style; 9 is body text
+    link: Option<String>,      // w:link to the linked character or paragraph style
+    tab_stops: Vec<TabStop>,   // merged with those of the basedOn chain
     based_on: Option<String>,
 }
 
+/// Formatting a table style gives to part of a table: the whole table, or
+/// the cells matching one `w:tblStylePr` condition such as firstRow.
+#[derive(Clone, Copy, Default)]
+struct TableCellFormat {
+    shading: Option<Option<[u8; 3]>>, // outer None when not specified
+    borders: BorderSet,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    color: Option<[u8; 3]>,
+}
+
+impl TableCellFormat {
+    fn parse(node: roxmltree::Node, theme: &Theme) -> Self {
+        let tc_pr = wml(node, "tcPr");
+        let rpr = wml(node, "rPr");
+        TableCellFormat {
+            shading: tc_pr
+                .and_then(|pr| wml(pr, "shd"))
+                .map(|shd| shading_fill(shd, theme)),
+            borders: BorderSet::parse(tc_pr.and_then(|pr| wml(pr, "tcBorders")), theme),
+            bold: rpr.and_then(|pr| wml_toggle(pr, "b")),
+            italic: rpr.and_then(|pr| wml_toggle(pr, "i")),
+            color: rpr.and_then(|pr| run_color(pr, theme)),
+        }
+    }
+
+    fn or(self, fallback: TableCellFormat) -> Self {
+        TableCellFormat {
+            shading: self.shading.or(fallback.shading),
+            borders: self.borders.or(fallback.borders),
+            bold: self.bold.or(fallback.bold),
+            italic: self.italic.or(fallback.italic),
+            color: self.color.or(fallback.color),
+        }
+    }
+}
+
+#[derive(Default)]
+struct TableStyle {
+    borders: BorderSet,
+    cell_margins: MarginSet,
+    whole_table: TableCellFormat,
+    conditions: HashMap<String, TableCellFormat>, // w:tblStylePr by w:type
+    based_on: Option<String>,
+}
+
+impl TableStyle {
+    /// Conditional formatting of the cell at (`ri`, `ci`), layered from the
+    /// bands up to the first/last row as Word does.
+    fn cell_format(
+        &self,
+        look: TableLook,
+        ri: usize,
+        n_rows: usize,
+        ci: usize,
+        n_cells: usize,
+    ) -> TableCellFormat {
+        let mut format = self.whole_table;
+        let mut apply = |name: &str| {
+            if let Some(cond) = self.conditions.get(name) {
+                format = cond.or(format);
+            }
+        };
+        if !look.no_v_band {
+            let band = ci.saturating_sub(look.first_col as usize);
+            apply(if band.is_multiple_of(2) {
+                "band1Vert"
+            } else {
+                "band2Vert"
+            });
+        }
+        if !look.no_h_band {
+            let band = ri.saturating_sub(look.first_row as usize);
+            apply(if band.is_multiple_of(2) {
+                "band1Horz"
+            } else {
+                "band2Horz"
+            });
+        }
+        if look.first_col && ci == 0 {
+            apply("firstCol");
+        }
+        if look.last_col && ci + 1 == n_cells {
+            apply("lastCol");
+        }
+        if look.first_row && ri == 0 {
+            apply("firstRow");
+        }
+        if look.last_row && ri + 1 == n_rows {
+            apply("lastRow");
+        }
+        format
+    }
+}
+
+/// `w:tblLook`: which conditional formats of the table style are in effect.
+#[derive(Clone, Copy)]
+struct TableLook {
+    first_row: bool,
+    last_row: bool,
+    first_col: bool,
+    last_col: bool,
+    no_h_band: bool,
+    no_v_band: bool,
+}
+
+impl TableLook {
+    fn parse(look: Option<roxmltree::Node>) -> Self {
+        // Word's default look is 04A0: first row, first column, no vertical bands
+        let Some(look) = look else {
+            return TableLook {
+                first_row: true,
+                last_row: false,
+                first_col: true,
+                last_col: false,
+                no_h_band: false,
+                no_v_band: true,
+            };
+        };
+        // Older documents only write the hex bitmask in w:val
+        let mask = look
+            .attribute((WML_NS, "val"))
+            .and_then(|v| u32::from_str_radix(v, 16).ok())
+            .unwrap_or(0);
+        let flag = |name: &str, bit: u32| match look.attribute((WML_NS, name)) {
+            Some(v) => v == "1" || v == "true",
+            None => mask & bit != 0,
+        };
+        TableLook {
+            first_row: flag("firstRow", 0x0020),
+            last_row: flag("lastRow", 0x0040),
+            first_col: flag("firstColumn", 0x0080),
+            last_col: flag("lastColumn", 0x0100),
+            no_h_band: flag("noHBand", 0x0200),
+            no_v_band: flag("noVBand", 0x0400),
+        }
+    }
+}
+
 struct StylesInfo {
     defaults: StyleDefaults,
     paragraph_styles: HashMap<String, ParagraphStyle>,
+    // Character styles only use the run properties of ParagraphStyle
+    character_styles: HashMap<String, ParagraphStyle>,
+    table_styles: HashMap<String, TableStyle>,
+    default_table_style: Option<String>,
+}
+
+impl StylesInfo {
+    /// The style referenced by a run's `w:rStyle`. Paragraph style ids are
+    /// resolved through their linked character style.
+    fn character_style(&self, id: &str) -> Option<&ParagraphStyle> {
+        self.character_styles.get(id).or_else(|| {
+            let para = self.paragraph_styles.get(id)?;
+            let linked = para
+                .link
+                .as_deref()
+                .and_then(|l| self.character_styles.get(l));
+            linked.or(Some(para))
+        })
+    }
+
+    /// A table style merged with the styles it is based on.
+    fn table_style(&self, style_id: Option<&str>) -> TableStyle {
+        let mut resolved = TableStyle::default();
+        let mut next = style_id.or(self.default_table_style.as_deref());
+        let mut depth = 0;
+        while let Some(id) = next
+            && depth < 10
+        {
+            let Some(style) = self.table_styles.get(id) else {
+                break;
+            };
+            resolved.borders = resolved.borders.or(style.borders);
+            resolved.cell_margins = resolved.cell_margins.or(style.cell_margins);
+            resolved.whole_table = resolved.whole_table.or(style.whole_table);
+            for (name, cond) in &style.conditions {
+                let merged = match resolved.conditions.get(name) {
+                    Some(own) => own.or(*cond),
+                    None => *cond,
+                };
+                resolved.conditions.insert(name.clone(), merged);
+            }
+            next = style.based_on.as_deref();
+            depth += 1;
+        }
+        resolved
+    }
 }
 
 fn parse_alignment(val: &str) -> Alignment {
@@ -140,28 +875,128 @@ fn parse_alignment(val: &str) -> Alignment {
     }
 }
 
-fn parse_theme(zip: &mut zip::ZipArchive<std::fs::File>) -> ThemeFonts {
-    let mut major = String::from("Aptos Display");
-    let mut minor = String::from("Aptos");
+fn parse_underline_style(val: &str) -> UnderlineStyle {
+    match val {
+        "none" => UnderlineStyle::None,
+        "words" => UnderlineStyle::Words,
+        "double" | "wavyDouble" => UnderlineStyle::Double,
+        "thick" => UnderlineStyle::Thick,
+        "dotted" | "dottedHeavy" => UnderlineStyle::Dotted,
+        "dash" | "dashedHeavy" | "dashLong" | "dashLongHeavy" => UnderlineStyle::Dash,
+        "dotDash" | "dashDotHeavy" | "dotDotDash" | "dashDotDotHeavy" => UnderlineStyle::DotDash,
+        "wave" | "wavyHeavy" => UnderlineStyle::Wave,
+        _ => UnderlineStyle::Single,
+    }
+}
+
+/// Parse `w:u` from a run property node. Returns None when the element is absent,
+/// so an explicit `w:val="none"` can still override an inherited underline.
+fn parse_underline(
+    rpr: roxmltree::Node,
+    theme: &Theme,
+) -> Option<(UnderlineStyle, Option<[u8; 3]>)> {
+    let u = wml(rpr, "u")?;
+    let style = u
+        .attribute((WML_NS, "val"))
+        .map(parse_underline_style)
+        .unwrap_or(UnderlineStyle::Single);
+    let color = element_color(u, "color", theme);
+    Some((style, color))
+}
+
+/// Parse `w:kern` (in half-points) into the smallest font size, in points,
+/// whose text is kerned; 0 turns kerning off.
+fn parse_kern(rpr: roxmltree::Node) -> Option<f32> {
+    wml_attr(rpr, "kern")
+        .and_then(|v| v.parse::<f32>().ok())
+        .map(|hp| hp / 2.0)
+}
+
+/// Parse `w:w` (horizontal text scale in percent) into a factor.
+fn parse_text_scale(rpr: roxmltree::Node) -> Option<f32> {
+    wml_attr(rpr, "w")
+        .and_then(|v| v.trim_end_matches('%').parse::<f32>().ok())
+        .filter(|&pct| pct > 0.0)
+        .map(|pct| pct / 100.0)
+}
+
+/// `w:outlineLvl` of a paragraph's properties: 0 for a top-level heading,
+/// 9 for body text.
+fn parse_outline_level(ppr: roxmltree::Node) -> Option<u8> {
+    wml_attr(ppr, "outlineLvl")?
+        .parse::<u8>()
+        .ok()
+        .map(|level| level.min(9))
+}
+
+struct Settings {
+    even_and_odd_headers: bool,
+    lang: Option<String>, // w:themeFontLang w:val
+}
+
+fn parse_settings<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<Settings, Error> {
+    let mut settings = Settings {
+        even_and_odd_headers: false,
+        lang: None,
+    };
+    let Some(xml_content) = read_zip_text(zip, "word/settings.xml")? else {
+        return Ok(settings);
+    };
+    let xml = parse_part("word/settings.xml", &xml_content)?;
+    let root = xml.root_element();
+    settings.even_and_odd_headers = wml_toggle(root, "evenAndOddHeaders").unwrap_or(false);
+    settings.lang = wml_attr(root, "themeFontLang").map(str::to_string);
+    Ok(settings)
+}
+
+/// Title, author and dates from `docProps/core.xml`. Elements are matched by
+/// local name, as the Dublin Core and core-properties namespaces are fixed.
+fn parse_core_properties<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<Metadata, Error> {
+    let mut metadata = Metadata::default();
+    let Some(xml_content) = read_zip_text(zip, "docProps/core.xml")? else {
+        return Ok(metadata);
+    };
+    let xml = parse_part("docProps/core.xml", &xml_content)?;
+    for node in xml.root_element().children().filter(|n| n.is_element()) {
+        let Some(text) = node.text().map(str::trim).filter(|t| !t.is_empty()) else {
+            continue;
+        };
+        match node.tag_name().name() {
+            "title" => metadata.title = Some(text.to_string()),
+            "creator" => metadata.author = Some(text.to_string()),
+            "subject" => metadata.subject = Some(text.to_string()),
+            "keywords" => metadata.keywords = Some(text.to_string()),
+            "created" => metadata.created = text.parse().ok(),
+            "modified" => metadata.modified = text.parse().ok(),
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+fn parse_theme<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<Theme, Error> {
+    let mut theme = Theme {
+        major: String::from("Aptos Display"),
+        minor: String::from("Aptos"),
+        major_east_asia: None,
+        minor_east_asia: None,
+        major_cs: None,
+        minor_cs: None,
+        colors: HashMap::new(),
+    };
 
-    let mut xml_content = String::new();
     let names: Vec<String> = zip.file_names().map(|s| s.to_string()).collect();
     let Some(theme_name) = names
         .iter()
         .find(|n| n.starts_with("word/theme/") && n.ends_with(".xml"))
     else {
-        return ThemeFonts { major, minor };
+        return Ok(theme);
     };
     let theme_name = theme_name.clone();
-    let Ok(mut file) = zip.by_name(&theme_name) else {
-        return ThemeFonts { major, minor };
-    };
-    if file.read_to_string(&mut xml_content).is_err() {
-        return ThemeFonts { major, minor };
-    }
-    let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-        return ThemeFonts { major, minor };
+    let Some(xml_content) = read_zip_text(zip, &theme_name)? else {
+        return Ok(theme);
     };
+    let xml = parse_part(&theme_name, &xml_content)?;
 
     for node in xml.descendants() {
         if node.tag_name().namespace() != Some(DML_NS) {
@@ -169,79 +1004,159 @@ fn parse_theme(zip: &mut zip::ZipArchive<std::fs::File>) -> ThemeFonts {
         }
         match node.tag_name().name() {
             "majorFont" => {
-                if let Some(tf) = latin_typeface(node) {
-                    major = tf.to_string();
+                if let Some(tf) = theme_typeface(node, "latin") {
+                    theme.major = tf.to_string();
                 }
+                theme.major_east_asia = theme_typeface(node, "ea").map(str::to_string);
+                theme.major_cs = theme_typeface(node, "cs").map(str::to_string);
             }
             "minorFont" => {
-                if let Some(tf) = latin_typeface(node) {
-                    minor = tf.to_string();
+                if let Some(tf) = theme_typeface(node, "latin") {
+                    theme.minor = tf.to_string();
+                }
+                theme.minor_east_asia = theme_typeface(node, "ea").map(str::to_string);
+                theme.minor_cs = theme_typeface(node, "cs").map(str::to_string);
+            }
+            "clrScheme" => {
+                for entry in node.children().filter(|n| n.is_element()) {
+                    // Each entry holds an a:srgbClr, or an a:sysClr with its last value
+                    let value = entry.children().find(|n| n.is_element()).and_then(|c| {
+                        c.attribute("val")
+                            .filter(|_| c.tag_name().name() == "srgbClr")
+                            .or_else(|| c.attribute("lastClr"))
+                    });
+                    if let Some(rgb) = value.and_then(parse_hex_color) {
+                        theme
+                            .colors
+                            .insert(entry.tag_name().name().to_string(), rgb);
+                    }
                 }
             }
             _ => {}
         }
     }
 
-    ThemeFonts { major, minor }
+    Ok(theme)
 }
 
-fn resolve_font(
-    ascii: Option<&str>,
-    ascii_theme: Option<&str>,
-    theme: &ThemeFonts,
-    default_font: &str,
-) -> String {
-    if let Some(f) = ascii {
-        return f.to_string();
+/// Font of one `w:rFonts` slot such as "hAnsi", or of its theme attribute.
+fn rfonts_slot(rfonts: roxmltree::Node, slot: &str, theme: &Theme) -> Option<String> {
+    let theme_attr = match slot {
+        "cs" => "cstheme".to_string(),
+        _ => format!("{slot}Theme"),
+    };
+    rfonts
+        .attribute((WML_NS, slot))
+        .map(str::to_string)
+        .or_else(|| {
+            rfonts
+                .attribute((WML_NS, theme_attr.as_str()))
+                .and_then(|name| theme.font(name))
+        })
+}
+
+fn resolve_font_from_node(rfonts: roxmltree::Node, theme: &Theme, default_font: &str) -> String {
+    rfonts_slot(rfonts, "ascii", theme).unwrap_or_else(|| default_font.to_string())
+}
+
+/// The `w:rFonts` slots besides `w:ascii`, which is carried as the font name.
+/// Unset slots are inherited, and fall back to the ASCII font in the end.
+#[derive(Clone, Default)]
+struct ScriptFonts {
+    h_ansi: Option<String>,
+    east_asia: Option<String>,
+    cs: Option<String>,
+}
+
+impl ScriptFonts {
+    fn from_node(rfonts: roxmltree::Node, theme: &Theme) -> Self {
+        Self {
+            h_ansi: rfonts_slot(rfonts, "hAnsi", theme),
+            east_asia: rfonts_slot(rfonts, "eastAsia", theme),
+            cs: rfonts_slot(rfonts, "cs", theme),
+        }
     }
-    match ascii_theme {
-        Some("majorHAnsi") => theme.major.clone(),
-        Some("minorHAnsi") => theme.minor.clone(),
-        _ => default_font.to_string(),
+
+    /// These fonts, with the slots left unset taken from `base`.
+    fn or(self, base: &ScriptFonts) -> Self {
+        Self {
+            h_ansi: self.h_ansi.or_else(|| base.h_ansi.clone()),
+            east_asia: self.east_asia.or_else(|| base.east_asia.clone()),
+            cs: self.cs.or_else(|| base.cs.clone()),
+        }
     }
 }
 
-fn resolve_font_from_node(
-    rfonts: roxmltree::Node,
-    theme: &ThemeFonts,
-    default_font: &str,
-) -> String {
-    resolve_font(
-        rfonts.attribute((WML_NS, "ascii")),
-        rfonts.attribute((WML_NS, "asciiTheme")),
-        theme,
-        default_font,
-    )
+/// Which `w:rFonts` slot formats a character.
+#[derive(Clone, Copy, PartialEq)]
+enum FontSlot {
+    Ascii,
+    HAnsi,
+    EastAsia,
+    ComplexScript,
+}
+
+/// Slot of a character by its Unicode block, after §17.3.2.26 of ECMA-376.
+/// With `w:hint="eastAsia"`, characters shared between Latin and East Asian
+/// text use the East Asian font. Combining marks and joiners return None, as
+/// they stay with the character before them.
+fn font_slot(c: char, east_asia_hint: bool) -> Option<FontSlot> {
+    let slot = match c as u32 {
+        0x0000..=0x007F => FontSlot::Ascii,
+        0x0300..=0x036F | 0x200C..=0x200D | 0xFE00..=0xFE0F => return None,
+        // Hebrew, Arabic, Syriac, Thaana, Indic scripts, Thai, Lao, Tibetan
+        0x0590..=0x07BF
+        | 0x0900..=0x0FFF
+        | 0x1780..=0x17FF // Khmer
+        | 0xFB1D..=0xFDFF // Hebrew and Arabic presentation forms
+        | 0xFE70..=0xFEFF => FontSlot::ComplexScript,
+        0x1100..=0x11FF // Hangul Jamo
+        | 0x2E80..=0x9FFF // CJK radicals, punctuation, kana, ideographs
+        | 0xA000..=0xA4CF // Yi
+        | 0xAC00..=0xD7AF // Hangul syllables
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFFEF // half- and full-width forms
+        | 0x20000..=0x2FFFF => FontSlot::EastAsia,
+        _ if east_asia_hint => FontSlot::EastAsia,
+        _ => FontSlot::HAnsi,
+    };
+    Some(slot)
 }
 
-fn parse_styles(zip: &mut zip::ZipArchive<std::fs::File>, theme: &ThemeFonts) -> StylesInfo {
+fn parse_styles<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    theme: &Theme,
+) -> Result<StylesInfo, Error> {
     let mut defaults = StyleDefaults {
         font_size: 12.0,
+        font_size_cs: None,
         font_name: theme.minor.clone(),
+        script_fonts: ScriptFonts::default(),
         space_after: 8.0,
-        line_spacing: 1.2,
+        line_spacing: LineSpacing::Auto(1.2),
+        color: None,
+        bold: false,
+        italic: false,
+        kern: None,
+        widow_control: true,
+        lang: None,
     };
     let mut paragraph_styles = HashMap::new();
+    let mut character_styles = HashMap::new();
+    let mut table_styles = HashMap::new();
+    let mut default_table_style = None;
 
-    let mut xml_content = String::new();
-    let Ok(mut file) = zip.by_name("word/styles.xml") else {
-        return StylesInfo {
+    let Some(xml_content) = read_zip_text(zip, "word/styles.xml")? else {
+        return Ok(StylesInfo {
             defaults,
             paragraph_styles,
-        };
-    };
-    if file.read_to_string(&mut xml_content).is_err() {
-        return StylesInfo {
-            defaults,
-            paragraph_styles,
-        };
-    }
-    let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-        return StylesInfo {
-            defaults,
-            paragraph_styles,
-        };
+            character_styles,
+            table_styles,
+            default_table_style,
+        });
     };
+    let xml = parse_part("word/styles.xml", &xml_content)?;
 
     let root = xml.root_element();
 
@@ -250,22 +1165,30 @@ fn parse_styles(zip: &mut zip::ZipArchive<std::fs::File>, theme: &ThemeFonts) ->
             if let Some(sz_val) = wml_attr(rpr, "sz").and_then(|v| v.parse::<f32>().ok()) {
                 defaults.font_size = sz_val / 2.0;
             }
+            defaults.font_size_cs = wml_attr(rpr, "szCs")
+                .and_then(|v| v.parse::<f32>().ok())
+                .map(|hp| hp / 2.0);
             if let Some(rfonts) = wml(rpr, "rFonts") {
                 defaults.font_name = resolve_font_from_node(rfonts, theme, &theme.minor);
+                defaults.script_fonts = ScriptFonts::from_node(rfonts, theme);
             }
+            defaults.color = run_color(rpr, theme);
+            defaults.bold = wml_toggle(rpr, "b").unwrap_or(false);
+            defaults.italic = wml_toggle(rpr, "i").unwrap_or(false);
+            defaults.kern = parse_kern(rpr);
+            defaults.lang = wml_attr(rpr, "lang").map(str::to_string);
+        }
+        let default_ppr = wml(doc_defaults, "pPrDefault").and_then(|n| wml(n, "pPr"));
+        if let Some(v) = default_ppr.and_then(|n| wml_toggle(n, "widowControl")) {
+            defaults.widow_control = v;
         }
-        let default_spacing = wml(doc_defaults, "pPrDefault")
-            .and_then(|n| wml(n, "pPr"))
-            .and_then(|n| wml(n, "spacing"));
+        let default_spacing = default_ppr.and_then(|n| wml(n, "spacing"));
         if let Some(spacing) = default_spacing {
             if let Some(after_val) = twips_attr(spacing, "after") {
                 defaults.space_after = after_val;
             }
-            if let Some(line_val) = spacing
-                .attribute((WML_NS, "line"))
-                .and_then(|v| v.parse::<f32>().ok())
-            {
-                defaults.line_spacing = line_val / 240.0;
+            if let Some(line_spacing) = parse_line_spacing(spacing) {
+                defaults.line_spacing = line_spacing;
             }
         }
     }
@@ -276,19 +1199,45 @@ fn parse_styles(zip: &mut zip::ZipArchive<std::fs::File>, theme: &ThemeFonts) ->
         {
             continue;
         }
-        if style_node.attribute((WML_NS, "type")) != Some("paragraph") {
-            continue;
-        }
         let Some(style_id) = style_node.attribute((WML_NS, "styleId")) else {
             continue;
         };
+        if style_node.attribute((WML_NS, "type")) == Some("table") {
+            if style_node.attribute((WML_NS, "default")) == Some("1") {
+                default_table_style = Some(style_id.to_string());
+            }
+            let tbl_pr = wml(style_node, "tblPr");
+            table_styles.insert(
+                style_id.to_string(),
+                TableStyle {
+                    borders: BorderSet::parse(tbl_pr.and_then(|pr| wml(pr, "tblBorders")), theme),
+                    cell_margins: MarginSet::parse(tbl_pr.and_then(|pr| wml(pr, "tblCellMar"))),
+                    whole_table: TableCellFormat::parse(style_node, theme),
+                    conditions: style_node
+                        .children()
+                        .filter(|n| is_wml_element(n, "tblStylePr"))
+                        .filter_map(|n| {
+                            let kind = n.attribute((WML_NS, "type"))?;
+                            Some((kind.to_string(), TableCellFormat::parse(n, theme)))
+                        })
+                        .collect(),
+                    based_on: wml_attr(style_node, "basedOn").map(|s| s.to_string()),
+                },
+            );
+            continue;
+        }
+        let is_character = style_node.attribute((WML_NS, "type")) == Some("character");
+        if !is_character && style_node.attribute((WML_NS, "type")) != Some("paragraph") {
+            continue;
+        }
 
         let ppr = wml(style_node, "pPr");
         let spacing = ppr.and_then(|n| wml(n, "spacing"));
-        let space_before = spacing.and_then(|n| twips_attr(n, "before")).unwrap_or(0.0);
-        let space_after = spacing.and_then(|n| twips_attr(n, "after"));
-        let bdr_extra = ppr.map(border_bottom_extra).unwrap_or(0.0);
-        let border_bottom = ppr.and_then(parse_border_bottom);
+        let line_spacing = spacing.and_then(parse_line_spacing);
+        let bdr_extra = ppr
+            .map(|ppr| border_bottom_extra(ppr, theme))
+            .unwrap_or(0.0);
+        let border_bottom = ppr.and_then(|ppr| parse_border_bottom(ppr, theme));
 
         let rpr = wml(style_node, "rPr");
 
@@ -297,9 +1246,29 @@ fn parse_styles(zip: &mut zip::ZipArchive<std::fs::File>, theme: &ThemeFonts) ->
             .and_then(|v| v.parse::<f32>().ok())
             .map(|hp| hp / 2.0);
 
+        // Lines-based spacing is measured before basedOn is resolved, so an
+        // inherited font size is not taken into account
+        let line_h = nominal_line_height(
+            font_size.unwrap_or(defaults.font_size),
+            line_spacing.unwrap_or(defaults.line_spacing),
+        );
+        let space_before = spacing
+            .and_then(|n| paragraph_spacing(n, "before", line_h))
+            .unwrap_or(0.0);
+        let space_after = spacing.and_then(|n| paragraph_spacing(n, "after", line_h));
+
+        let font_size_cs = rpr
+            .and_then(|n| wml_attr(n, "szCs"))
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|hp| hp / 2.0);
+
         let font_name = rpr
             .and_then(|n| wml(n, "rFonts"))
             .map(|rfonts| resolve_font_from_node(rfonts, theme, &defaults.font_name));
+        let script_fonts = rpr
+            .and_then(|n| wml(n, "rFonts"))
+            .map(|rfonts| ScriptFonts::from_node(rfonts, theme))
+            .unwrap_or_default();
 
         let bold = rpr.and_then(|n| wml(n, "b")).map(|n| {
             n.attribute((WML_NS, "val"))
@@ -310,56 +1279,115 @@ fn parse_styles(zip: &mut zip::ZipArchive<std::fs::File>, theme: &ThemeFonts) ->
                 .is_none_or(|v| v != "0" && v != "false")
         });
 
-        let color = rpr
-            .and_then(|n| wml_attr(n, "color"))
-            .and_then(parse_hex_color);
+        let color = rpr.and_then(|n| run_color(n, theme));
+
+        let caps = rpr.and_then(|n| wml_toggle(n, "caps"));
+        let small_caps = rpr.and_then(|n| wml_toggle(n, "smallCaps"));
+        let hidden = rpr.and_then(|n| wml_toggle(n, "vanish"));
+        let char_spacing = rpr
+            .and_then(|n| wml(n, "spacing"))
+            .and_then(|n| twips_attr(n, "val"));
+        let scale = rpr.and_then(parse_text_scale);
+        let kern = rpr.and_then(parse_kern);
+
+        let underline_info = rpr.and_then(|n| parse_underline(n, theme));
+        let underline = underline_info.map(|(style, _)| style);
+        let underline_color = underline_info.and_then(|(_, color)| color);
 
         let alignment = ppr.and_then(|ppr| wml_attr(ppr, "jc")).map(parse_alignment);
 
         let contextual_spacing = ppr.and_then(|ppr| wml(ppr, "contextualSpacing")).is_some();
 
         let keep_next = ppr.and_then(|ppr| wml(ppr, "keepNext")).is_some();
-
-        let line_spacing = spacing
-            .and_then(|n| n.attribute((WML_NS, "line")))
-            .and_then(|v| v.parse::<f32>().ok())
-            .map(|val| val / 240.0);
+        let keep_lines = ppr
+            .and_then(|ppr| wml_toggle(ppr, "keepLines"))
+            .unwrap_or(false);
+        let widow_control = ppr.and_then(|ppr| wml_toggle(ppr, "widowControl"));
+
+        let ind = ppr.and_then(|ppr| wml(ppr, "ind"));
+        let indent_right = ind.and_then(indent_right_attr);
+        let indent_first_line = ind.and_then(indent_first_line_attr);
+
+        let num_pr = ppr.and_then(|ppr| wml(ppr, "numPr"));
+        let num_id = num_pr
+            .and_then(|n| wml_attr(n, "numId"))
+            .map(|s| s.to_string());
+        let num_ilvl = num_pr
+            .and_then(|n| wml_attr(n, "ilvl"))
+            .and_then(|v| v.parse::<u8>().ok());
+        let outline_level = ppr.and_then(parse_outline_level).or_else(|| {
+            let name = wml_attr(style_node, "name")?.to_ascii_lowercase();
+            let level = name.strip_prefix("heading ")?.parse::<u8>().ok()?;
+            (1..=9).contains(&level).then(|| level - 1)
+        });
 
         let based_on = wml(style_node, "basedOn")
             .and_then(|n| n.attribute((WML_NS, "val")))
             .map(|s| s.to_string());
 
-        paragraph_styles.insert(
+        let link = wml_attr(style_node, "link").map(|s| s.to_string());
+        let tab_stops = ppr.map(parse_tab_stops).unwrap_or_default();
+
+        let target = if is_character {
+            &mut character_styles
+        } else {
+            &mut paragraph_styles
+        };
+        target.insert(
             style_id.to_string(),
             ParagraphStyle {
                 font_size,
+                font_size_cs,
                 font_name,
+                script_fonts,
                 bold,
                 italic,
                 color,
+                underline,
+                underline_color,
+                caps,
+                small_caps,
+                hidden,
+                char_spacing,
+                scale,
+                kern,
                 space_before,
                 space_after,
                 alignment,
                 contextual_spacing,
                 keep_next,
+                keep_lines,
+                widow_control,
                 line_spacing,
+                indent_right,
+                indent_first_line,
                 border_bottom_extra: bdr_extra,
                 border_bottom,
+                num_id,
+                num_ilvl,
+                outline_level,
+                link,
+                tab_stops,
                 based_on,
             },
         );
     }
 
     resolve_based_on(&mut paragraph_styles);
+    resolve_based_on(&mut character_styles);
 
-    StylesInfo {
+    Ok(StylesInfo {
         defaults,
         paragraph_styles,
-    }
+        character_styles,
+        table_styles,
+        default_table_style,
+    })
 }
 
 fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
-    let ids: Vec<String> = styles.keys().cloned().collect();
+    let mut ids: Vec<String> = styles.keys().cloned().collect();
+    ids.sort();
     for id in ids {
         let mut chain: Vec<String> = Vec::new();
         let mut current = id.clone();
@@ -377,22 +1405,43 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
         }
         // Apply inheritance from furthest ancestor down to the style itself
         let mut inherited_font_name: Option<String> = None;
+        let mut inherited_script_fonts = ScriptFonts::default();
         let mut inherited_font_size: Option<f32> = None;
+        let mut inherited_font_size_cs: Option<f32> = None;
         let mut inherited_bold: Option<bool> = None;
         let mut inherited_italic: Option<bool> = None;
         let mut inherited_color: Option<[u8; 3]> = None;
+        let mut inherited_underline: Option<UnderlineStyle> = None;
+        let mut inherited_underline_color: Option<[u8; 3]> = None;
+        let mut inherited_caps: Option<bool> = None;
+        let mut inherited_small_caps: Option<bool> = None;
+        let mut inherited_hidden: Option<bool> = None;
+        let mut inherited_char_spacing: Option<f32> = None;
+        let mut inherited_scale: Option<f32> = None;
+        let mut inherited_kern: Option<f32> = None;
         let mut inherited_alignment: Option<Alignment> = None;
         let mut inherited_space_after: Option<f32> = None;
-        let mut inherited_line_spacing: Option<f32> = None;
+        let mut inherited_line_spacing: Option<LineSpacing> = None;
+        let mut inherited_widow_control: Option<bool> = None;
+        let mut inherited_indent_right: Option<f32> = None;
+        let mut inherited_indent_first_line: Option<f32> = None;
+        let mut inherited_num_id: Option<String> = None;
+        let mut inherited_num_ilvl: Option<u8> = None;
+        let mut inherited_outline_level: Option<u8> = None;
+        let mut inherited_tab_stops: Vec<TabStop> = Vec::new();
 
         for ancestor_id in chain.iter().rev() {
             if let Some(s) = styles.get(ancestor_id) {
                 if s.font_name.is_some() {
                     inherited_font_name = s.font_name.clone();
                 }
+                inherited_script_fonts = s.script_fonts.clone().or(&inherited_script_fonts);
                 if s.font_size.is_some() {
                     inherited_font_size = s.font_size;
                 }
+                if s.font_size_cs.is_some() {
+                    inherited_font_size_cs = s.font_size_cs;
+                }
                 if s.bold.is_some() {
                     inherited_bold = s.bold;
                 }
@@ -402,6 +1451,28 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
                 if s.color.is_some() {
                     inherited_color = s.color;
                 }
+                if s.underline.is_some() {
+                    inherited_underline = s.underline;
+                    inherited_underline_color = s.underline_color;
+                }
+                if s.caps.is_some() {
+                    inherited_caps = s.caps;
+                }
+                if s.small_caps.is_some() {
+                    inherited_small_caps = s.small_caps;
+                }
+                if s.hidden.is_some() {
+                    inherited_hidden = s.hidden;
+                }
+                if s.char_spacing.is_some() {
+                    inherited_char_spacing = s.char_spacing;
+                }
+                if s.scale.is_some() {
+                    inherited_scale = s.scale;
+                }
+                if s.kern.is_some() {
+                    inherited_kern = s.kern;
+                }
                 if s.alignment.is_some() {
                     inherited_alignment = s.alignment;
                 }
@@ -411,6 +1482,25 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
                 if s.line_spacing.is_some() {
                     inherited_line_spacing = s.line_spacing;
                 }
+                if s.widow_control.is_some() {
+                    inherited_widow_control = s.widow_control;
+                }
+                if s.indent_right.is_some() {
+                    inherited_indent_right = s.indent_right;
+                }
+                if s.indent_first_line.is_some() {
+                    inherited_indent_first_line = s.indent_first_line;
+                }
+                if s.num_id.is_some() {
+                    inherited_num_id = s.num_id.clone();
+                }
+                if s.num_ilvl.is_some() {
+                    inherited_num_ilvl = s.num_ilvl;
+                }
+                if s.outline_level.is_some() {
+                    inherited_outline_level = s.outline_level;
+                }
+                inherited_tab_stops = merge_tab_stops(&inherited_tab_stops, &s.tab_stops);
             }
         }
 
@@ -418,9 +1508,13 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
             if s.font_name.is_none() {
                 s.font_name = inherited_font_name;
             }
+            s.script_fonts = inherited_script_fonts;
             if s.font_size.is_none() {
                 s.font_size = inherited_font_size;
             }
+            if s.font_size_cs.is_none() {
+                s.font_size_cs = inherited_font_size_cs;
+            }
             if s.bold.is_none() {
                 s.bold = inherited_bold;
             }
@@ -430,6 +1524,28 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
             if s.color.is_none() {
                 s.color = inherited_color;
             }
+            if s.underline.is_none() {
+                s.underline = inherited_underline;
+                s.underline_color = inherited_underline_color;
+            }
+            if s.caps.is_none() {
+                s.caps = inherited_caps;
+            }
+            if s.small_caps.is_none() {
+                s.small_caps = inherited_small_caps;
+            }
+            if s.hidden.is_none() {
+                s.hidden = inherited_hidden;
+            }
+            if s.char_spacing.is_none() {
+                s.char_spacing = inherited_char_spacing;
+            }
+            if s.scale.is_none() {
+                s.scale = inherited_scale;
+            }
+            if s.kern.is_none() {
+                s.kern = inherited_kern;
+            }
             if s.alignment.is_none() {
                 s.alignment = inherited_alignment;
             }
@@ -439,6 +1555,25 @@ fn resolve_based_on(styles: &mut HashMap<String, ParagraphStyle>) {
             if s.line_spacing.is_none() {
                 s.line_spacing = inherited_line_spacing;
             }
+            if s.widow_control.is_none() {
+                s.widow_control = inherited_widow_control;
+            }
+            if s.indent_right.is_none() {
+                s.indent_right = inherited_indent_right;
+            }
+            if s.indent_first_line.is_none() {
+                s.indent_first_line = inherited_indent_first_line;
+            }
+            if s.num_id.is_none() {
+                s.num_id = inherited_num_id;
+            }
+            if s.num_ilvl.is_none() {
+                s.num_ilvl = inherited_num_ilvl;
+            }
+            if s.outline_level.is_none() {
+                s.outline_level = inherited_outline_level;
+            }
+            s.tab_stops = inherited_tab_stops;
         }
     }
 }
@@ -478,29 +1613,6 @@ fn deobfuscate_font(data: &mut [u8], key: &[u8; 16]) {
     }
 }
 
-/// Parse word/_rels/fontTable.xml.rels to get relationship ID → target path mapping.
-fn parse_font_table_rels(zip: &mut zip::ZipArchive<std::fs::File>) -> HashMap<String, String> {
-    let mut rels = HashMap::new();
-    let mut xml_content = String::new();
-    let Ok(mut file) = zip.by_name("word/_rels/fontTable.xml.rels") else {
-        return rels;
-    };
-    if file.read_to_string(&mut xml_content).is_err() {
-        return rels;
-    }
-    let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-        return rels;
-    };
-    for node in xml.root_element().children() {
-        if node.tag_name().name() == "Relationship"
-            && let (Some(id), Some(target)) = (node.attribute("Id"), node.attribute("Target"))
-        {
-            rels.insert(id.to_string(), target.to_string());
-        }
-    }
-    rels
-}
-
 struct EmbedInfo {
     font_name: String,
     bold: bool,
@@ -510,25 +1622,19 @@ struct EmbedInfo {
 }
 
 /// Parse word/fontTable.xml for embedded fonts, extract and deobfuscate them.
-fn parse_font_table(
-    zip: &mut zip::ZipArchive<std::fs::File>,
-) -> HashMap<(String, bool, bool), Vec<u8>> {
-    let mut result = HashMap::new();
+fn parse_font_table<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    package: &Package,
+) -> Result<EmbeddedFonts, Error> {
+    let mut result = BTreeMap::new();
 
-    // Phase 1: parse XML to collect embed info (drops borrow on zip before phase 2)
+    // Phase 1: parse XML to collect embed info
     let embeds = {
-        let mut xml_content = String::new();
-        let Ok(mut file) = zip.by_name("word/fontTable.xml") else {
-            return result;
+        let Some(xml_content) = read_zip_text(zip, "word/fontTable.xml")? else {
+            return Ok(result);
         };
-        if file.read_to_string(&mut xml_content).is_err() {
-            return result;
-        }
-        drop(file);
 
-        let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-            return result;
-        };
+        let xml = parse_part("word/fontTable.xml", &xml_content)?;
 
         let embed_variants: &[(&str, bool, bool)] = &[
             ("embedRegular", false, false),
@@ -572,30 +1678,20 @@ fn parse_font_table(
     };
 
     if embeds.is_empty() {
-        return result;
+        return Ok(result);
     }
 
-    // Phase 2: resolve relationships and extract font data
-    let font_rels = parse_font_table_rels(zip);
+    // Phase 2: resolve relationships and deobfuscate the font data
+    let font_rels = package.rels("word/fontTable.xml");
 
     for info in embeds {
         let Some(target) = font_rels.get(&info.rel_id) else {
             continue;
         };
-
-        let zip_path = target
-            .strip_prefix('/')
-            .map(String::from)
-            .unwrap_or_else(|| format!("word/{}", target));
-
-        let Ok(mut entry) = zip.by_name(&zip_path) else {
+        let Some(data) = package.part(&resolve_part_path("word", target)) else {
             continue;
         };
-        let mut data = Vec::new();
-        if entry.read_to_end(&mut data).is_err() {
-            continue;
-        }
-        drop(entry);
+        let mut data = data.to_vec();
 
         if let Some(ref guid_str) = info.font_key
             && let Some(key) = parse_guid_to_bytes(guid_str)
@@ -610,36 +1706,110 @@ fn parse_font_table(
             info.italic,
             data.len()
         );
-        result.insert((info.font_name.to_lowercase(), info.bold, info.italic), data);
+        result.insert(
+            (info.font_name.to_lowercase(), info.bold, info.italic),
+            data,
+        );
     }
 
-    result
+    Ok(result)
 }
 
-fn parse_numbering(zip: &mut zip::ZipArchive<std::fs::File>) -> NumberingInfo {
-    let mut abstract_nums: HashMap<String, HashMap<u8, LevelDef>> = HashMap::new();
-    let mut num_to_abstract: HashMap<String, String> = HashMap::new();
-
-    let mut xml_content = String::new();
-    let Ok(mut file) = zip.by_name("word/numbering.xml") else {
-        return NumberingInfo {
-            abstract_nums,
-            num_to_abstract,
-        };
+/// Parse a `w:lvl` element of an abstractNum or a level override.
+fn parse_level(lvl: roxmltree::Node, theme: &Theme) -> Option<(u8, LevelDef)> {
+    let ilvl = lvl
+        .attribute((WML_NS, "ilvl"))
+        .and_then(|v| v.parse::<u8>().ok())?;
+    let num_fmt = wml_attr(lvl, "numFmt").unwrap_or("bullet").to_string();
+    let lvl_text = wml_attr(lvl, "lvlText").unwrap_or("").to_string();
+    let jc = wml_attr(lvl, "lvlJc")
+        .map(parse_alignment)
+        .unwrap_or(Alignment::Left);
+    let start = wml_attr(lvl, "start")
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(1);
+    let restart = wml_attr(lvl, "lvlRestart").and_then(|v| v.parse::<u8>().ok());
+    let is_legal = wml_toggle(lvl, "isLgl").unwrap_or(false);
+    let p_style = wml_attr(lvl, "pStyle").map(|s| s.to_string());
+    let suffix = match wml_attr(lvl, "suff") {
+        Some("space") => LabelSuffix::Space,
+        Some("nothing") => LabelSuffix::Nothing,
+        _ => LabelSuffix::Tab,
     };
-    if file.read_to_string(&mut xml_content).is_err() {
-        return NumberingInfo {
-            abstract_nums,
-            num_to_abstract,
-        };
+    let rpr = wml(lvl, "rPr");
+    let label_props = LabelProps {
+        font_name: rpr
+            .and_then(|n| wml(n, "rFonts"))
+            .map(|rfonts| resolve_font_from_node(rfonts, theme, ""))
+            .filter(|f| !f.is_empty()),
+        font_size: rpr
+            .and_then(|n| wml_attr(n, "sz"))
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|hp| hp / 2.0),
+        bold: rpr.and_then(|n| wml_toggle(n, "b")),
+        italic: rpr.and_then(|n| wml_toggle(n, "i")),
+        color: rpr.and_then(|n| run_color(n, theme)),
+    };
+    let ind = wml(lvl, "pPr").and_then(|ppr| wml(ppr, "ind"));
+    let indent_left = ind.and_then(|n| twips_attr(n, "left")).unwrap_or(0.0);
+    let indent_hanging = ind.and_then(|n| twips_attr(n, "hanging")).unwrap_or(0.0);
+    Some((
+        ilvl,
+        LevelDef {
+            num_fmt,
+            lvl_text,
+            jc,
+            label_props,
+            suffix,
+            start,
+            restart,
+            is_legal,
+            p_style,
+            indent_left,
+            indent_hanging,
+        },
+    ))
+}
+
+fn is_wml_element(node: &roxmltree::Node, name: &str) -> bool {
+    node.tag_name().name() == name && node.tag_name().namespace() == Some(WML_NS)
+}
+
+/// Children of `node`, with structured document tags (`w:sdt`) replaced by
+/// the children of their `w:sdtContent`, as if the wrapper weren't there.
+/// Tracked changes are shown accepted: insertions are unwrapped the same way
+/// and deletions (`w:del`, `w:moveFrom`) are left out.
+fn content_children<'a>(node: roxmltree::Node<'a, 'a>) -> Vec<roxmltree::Node<'a, 'a>> {
+    let mut children = Vec::new();
+    for child in node.children() {
+        if is_wml_element(&child, "sdt") {
+            if let Some(content) = wml(child, "sdtContent") {
+                children.extend(content_children(content));
+            }
+        } else if is_wml_element(&child, "ins") || is_wml_element(&child, "moveTo") {
+            children.extend(content_children(child));
+        } else if !is_wml_element(&child, "del") && !is_wml_element(&child, "moveFrom") {
+            children.push(child);
+        }
     }
-    let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-        return NumberingInfo {
-            abstract_nums,
-            num_to_abstract,
-        };
+    children
+}
+
+fn parse_numbering<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    theme: &Theme,
+) -> Result<NumberingInfo, Error> {
+    let mut info = NumberingInfo {
+        abstract_nums: HashMap::new(),
+        num_to_abstract: HashMap::new(),
+        overrides: HashMap::new(),
     };
 
+    let Some(xml_content) = read_zip_text(zip, "word/numbering.xml")? else {
+        return Ok(info);
+    };
+    let xml = parse_part("word/numbering.xml", &xml_content)?;
+
     let root = xml.root_element();
 
     for node in root.children() {
@@ -651,34 +1821,12 @@ fn parse_numbering(zip: &mut zip::ZipArchive<std::fs::File>) -> NumberingInfo {
                 let Some(abs_id) = node.attribute((WML_NS, "abstractNumId")) else {
                     continue;
                 };
-                let mut levels: HashMap<u8, LevelDef> = HashMap::new();
-                for lvl in node.children() {
-                    if lvl.tag_name().name() != "lvl" || lvl.tag_name().namespace() != Some(WML_NS)
-                    {
-                        continue;
-                    }
-                    let Some(ilvl) = lvl
-                        .attribute((WML_NS, "ilvl"))
-                        .and_then(|v| v.parse::<u8>().ok())
-                    else {
-                        continue;
-                    };
-                    let num_fmt = wml_attr(lvl, "numFmt").unwrap_or("bullet").to_string();
-                    let lvl_text = wml_attr(lvl, "lvlText").unwrap_or("").to_string();
-                    let ind = wml(lvl, "pPr").and_then(|ppr| wml(ppr, "ind"));
-                    let indent_left = ind.and_then(|n| twips_attr(n, "left")).unwrap_or(0.0);
-                    let indent_hanging = ind.and_then(|n| twips_attr(n, "hanging")).unwrap_or(0.0);
-                    levels.insert(
-                        ilvl,
-                        LevelDef {
-                            num_fmt,
-                            lvl_text,
-                            indent_left,
-                            indent_hanging,
-                        },
-                    );
-                }
-                abstract_nums.insert(abs_id.to_string(), levels);
+                let levels: HashMap<u8, LevelDef> = node
+                    .children()
+                    .filter(|n| is_wml_element(n, "lvl"))
+                    .filter_map(|lvl| parse_level(lvl, theme))
+                    .collect();
+                info.abstract_nums.insert(abs_id.to_string(), levels);
             }
             "num" => {
                 let Some(num_id) = node.attribute((WML_NS, "numId")) else {
@@ -687,16 +1835,32 @@ fn parse_numbering(zip: &mut zip::ZipArchive<std::fs::File>) -> NumberingInfo {
                 let Some(abs_id) = wml_attr(node, "abstractNumId") else {
                     continue;
                 };
-                num_to_abstract.insert(num_id.to_string(), abs_id.to_string());
+                info.num_to_abstract
+                    .insert(num_id.to_string(), abs_id.to_string());
+
+                let mut overrides: HashMap<u8, LevelOverride> = HashMap::new();
+                for ov in node.children().filter(|n| is_wml_element(n, "lvlOverride")) {
+                    let Some(ilvl) = ov
+                        .attribute((WML_NS, "ilvl"))
+                        .and_then(|v| v.parse::<u8>().ok())
+                    else {
+                        continue;
+                    };
+                    let start = wml_attr(ov, "startOverride").and_then(|v| v.parse::<u32>().ok());
+                    let level = wml(ov, "lvl")
+                        .and_then(|lvl| parse_level(lvl, theme))
+                        .map(|(_, def)| def);
+                    overrides.insert(ilvl, LevelOverride { start, level });
+                }
+                if !overrides.is_empty() {
+                    info.overrides.insert(num_id.to_string(), overrides);
+                }
             }
             _ => {}
         }
     }
 
-    NumberingInfo {
-        abstract_nums,
-        num_to_abstract,
-    }
+    Ok(info)
 }
 
 fn parse_tab_stops(ppr: roxmltree::Node) -> Vec<TabStop> {
@@ -707,21 +1871,20 @@ fn parse_tab_stops(ppr: roxmltree::Node) -> Vec<TabStop> {
         .children()
         .filter(|n| n.tag_name().name() == "tab" && n.tag_name().namespace() == Some(WML_NS))
         .filter_map(|n| {
-            let pos = twips_attr(n, "pos")?;
-            let val = n.attribute((WML_NS, "val")).unwrap_or("left");
-            if val == "clear" {
-                return None;
-            }
-            let alignment = match val {
+            let pos = twips_attr(n, "pos").filter(|p| p.is_finite())?;
+            let alignment = match n.attribute((WML_NS, "val")).unwrap_or("left") {
                 "center" => TabAlignment::Center,
-                "right" => TabAlignment::Right,
+                "right" | "end" => TabAlignment::Right,
                 "decimal" => TabAlignment::Decimal,
+                "bar" => TabAlignment::Bar,
+                "clear" => TabAlignment::Clear,
                 _ => TabAlignment::Left,
             };
             let leader = n.attribute((WML_NS, "leader")).and_then(|l| match l {
                 "dot" => Some('.'),
                 "hyphen" => Some('-'),
-                "underscore" => Some('_'),
+                "underscore" | "heavy" => Some('_'),
+                "middleDot" => Some('\u{00B7}'),
                 _ => None,
             });
             Some(TabStop {
@@ -731,16 +1894,125 @@ fn parse_tab_stops(ppr: roxmltree::Node) -> Vec<TabStop> {
             })
         })
         .collect();
-    stops.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap());
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    stops
+}
+
+/// Layer a paragraph's (or derived style's) `own` tab stops over `inherited`
+/// ones: a stop at the same position replaces the inherited one, which is how
+/// clear entries remove it. The clear entries are kept to suppress the
+/// default stop at their position.
+fn merge_tab_stops(inherited: &[TabStop], own: &[TabStop]) -> Vec<TabStop> {
+    let mut stops: Vec<TabStop> = inherited
+        .iter()
+        .filter(|s| !own.iter().any(|o| (o.position - s.position).abs() < 0.5))
+        .chain(own)
+        .cloned()
+        .collect();
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
     stops
 }
 
+/// Map a field instruction such as ` PAGE \* MERGEFORMAT ` to a field code.
+fn parse_field_instr(instr: &str) -> Option<FieldCode> {
+    let name = instr.split_whitespace().next()?;
+    match name.to_ascii_uppercase().as_str() {
+        "PAGE" => Some(FieldCode::Page),
+        "NUMPAGES" => Some(FieldCode::NumPages),
+        "DATE" => Some(FieldCode::Date(date_picture(instr))),
+        "TIME" => Some(FieldCode::Time(date_picture(instr))),
+        "FILENAME" => Some(FieldCode::Filename),
+        _ => None,
+    }
+}
+
+/// Extract the `\@ "dd.MM.yyyy"` date-time picture switch of a field instruction.
+fn date_picture(instr: &str) -> Option<String> {
+    let rest = instr.split_once("\\@")?.1.trim_start();
+    let picture = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => rest.split_whitespace().next()?,
+    };
+    Some(picture.to_string())
+}
+
+/// A complex field opened by `w:fldChar begin` and not yet ended.
+struct OpenField {
+    instr: String,
+    code: Option<FieldCode>, // fields we compute ourselves replace their cached result
+    in_result: bool,         // past `w:fldChar separate`
+}
+
+/// Complex field state. Fields can nest and span paragraphs (a TOC covers many),
+/// so one state is threaded through every paragraph of a story.
+#[derive(Default)]
+struct FieldState {
+    open: Vec<OpenField>,
+}
+
+impl FieldState {
+    /// Run content is shown outside fields and inside the cached result of
+    /// fields we don't compute; instruction text is never shown.
+    fn showing(&self) -> bool {
+        self.open.iter().all(|f| f.in_result && f.code.is_none())
+    }
+
+    fn begin(&mut self) {
+        self.open.push(OpenField {
+            instr: String::new(),
+            code: None,
+            in_result: false,
+        });
+    }
+
+    fn instr_text(&mut self, text: &str) {
+        if let Some(f) = self.open.last_mut()
+            && !f.in_result
+        {
+            f.instr.push_str(text);
+        }
+    }
+
+    /// Switch to the cached result. Returns the field to render in its place.
+    fn separate(&mut self) -> Option<FieldCode> {
+        let (field, outer) = self.open.split_last_mut()?;
+        if field.in_result {
+            return None;
+        }
+        field.in_result = true;
+        field.code = parse_field_instr(&field.instr);
+        let outer_showing = outer.iter().all(|f| f.in_result && f.code.is_none());
+        field.code.clone().filter(|_| outer_showing)
+    }
+
+    /// Close the innermost field. Returns the field to render when it had no
+    /// cached result.
+    fn end(&mut self) -> Option<FieldCode> {
+        let field = self.open.pop()?;
+        if field.in_result || !self.showing() {
+            return None;
+        }
+        parse_field_instr(&field.instr)
+    }
+}
+
 struct ParsedRuns {
     runs: Vec<Run>,
     has_page_break: bool,
 }
 
-fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFonts) -> ParsedRuns {
+/// Parse the runs of a paragraph. In a table cell, `table_format` is the run
+/// formatting of the table style (bold, italic and color), which sits between
+/// the document defaults and the paragraph style.
+fn parse_runs(
+    para_node: roxmltree::Node,
+    styles: &StylesInfo,
+    theme: &Theme,
+    rels: &HashMap<String, String>,
+    endnote_labels: &HashMap<String, String>,
+    fields: &mut FieldState,
+    table_format: Option<&TableCellFormat>,
+) -> ParsedRuns {
     let ppr = wml(para_node, "pPr");
     let para_style_id = ppr
         .and_then(|ppr| wml_attr(ppr, "pStyle"))
@@ -750,27 +2022,91 @@ fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFont
     let style_font_size = para_style
         .and_then(|s| s.font_size)
         .unwrap_or(styles.defaults.font_size);
+    let style_font_size_cs = para_style
+        .and_then(|s| s.font_size_cs)
+        .or(styles.defaults.font_size_cs);
     let style_font_name = para_style
         .and_then(|s| s.font_name.as_deref())
         .unwrap_or(&styles.defaults.font_name)
         .to_string();
-    let style_bold = para_style.and_then(|s| s.bold).unwrap_or(false);
-    let style_italic = para_style.and_then(|s| s.italic).unwrap_or(false);
-    let style_color: Option<[u8; 3]> = para_style.and_then(|s| s.color);
-
-    let run_nodes: Vec<_> = para_node
-        .children()
+    let style_script_fonts = para_style
+        .map(|s| s.script_fonts.clone())
+        .unwrap_or_default()
+        .or(&styles.defaults.script_fonts);
+    let style_bold = para_style
+        .and_then(|s| s.bold)
+        .or(table_format.and_then(|f| f.bold))
+        .unwrap_or(styles.defaults.bold);
+    let style_italic = para_style
+        .and_then(|s| s.italic)
+        .or(table_format.and_then(|f| f.italic))
+        .unwrap_or(styles.defaults.italic);
+    let style_color: Option<[u8; 3]> = para_style
+        .and_then(|s| s.color)
+        .or(table_format.and_then(|f| f.color))
+        .or(styles.defaults.color);
+    let style_underline = para_style
+        .and_then(|s| s.underline)
+        .unwrap_or(UnderlineStyle::None);
+    let style_underline_color = para_style.and_then(|s| s.underline_color);
+    let style_caps = para_style.and_then(|s| s.caps).unwrap_or(false);
+    let style_small_caps = para_style.and_then(|s| s.small_caps).unwrap_or(false);
+    let style_hidden = para_style.and_then(|s| s.hidden).unwrap_or(false);
+    let style_char_spacing = para_style.and_then(|s| s.char_spacing).unwrap_or(0.0);
+    let style_scale = para_style.and_then(|s| s.scale).unwrap_or(1.0);
+    let style_kern = para_style.and_then(|s| s.kern).or(styles.defaults.kern);
+
+    // (run node, hyperlink target, simple field, math text) in document order
+    let run_nodes: Vec<_> = content_children(para_node)
+        .into_iter()
         .flat_map(|child| {
             let name = child.tag_name().name();
             let is_wml = child.tag_name().namespace() == Some(WML_NS);
+            let is_run = |n: &roxmltree::Node| {
+                n.tag_name().name() == "r" && n.tag_name().namespace() == Some(WML_NS)
+            };
             if is_wml && name == "r" {
-                vec![child]
+                vec![(child, None, None, None)]
+            } else if is_wml && name == "fldSimple" {
+                // Known fields keep the formatting of their first result run; other
+                // fields show their cached result.
+                match child
+                    .attribute((WML_NS, "instr"))
+                    .and_then(parse_field_instr)
+                {
+                    Some(code) => {
+                        let run = child.children().find(is_run).unwrap_or(child);
+                        vec![(run, None, Some(code), None)]
+                    }
+                    None => child
+                        .children()
+                        .filter(is_run)
+                        .map(|n| (n, None, None, None))
+                        .collect(),
+                }
+            } else if child.tag_name().namespace() == Some(MATH_NS)
+                && (name == "oMath" || name == "oMathPara")
+            {
+                let mut pieces = Vec::new();
+                linearize_math(child, VertAlign::Baseline, &mut pieces);
+                pieces
+                    .into_iter()
+                    .map(|piece| (piece.node, None, None, Some(piece)))
+                    .collect()
             } else if is_wml && name == "hyperlink" {
+                let link = child
+                    .attribute((REL_NS, "id"))
+                    .and_then(|rid| rels.get(rid))
+                    .map(|url| LinkTarget::Url(url.clone()))
+                    .or_else(|| {
+                        child
+                            .attribute((WML_NS, "anchor"))
+                            .map(|a| LinkTarget::Anchor(a.to_string()))
+                    });
                 child
                     .children()
-                    .filter(|n| {
-                        n.tag_name().name() == "r" && n.tag_name().namespace() == Some(WML_NS)
-                    })
+                    .filter(is_run)
+                    .map(|n| (n, link.clone(), None, None))
                     .collect()
             } else {
                 vec![]
@@ -780,49 +2116,82 @@ fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFont
 
     let mut runs = Vec::new();
     let mut has_page_break = false;
-    let mut in_field = false;
-    let mut field_instr = String::new();
 
-    for run_node in run_nodes {
+    for (run_node, link, simple_field, math) in run_nodes {
         let rpr = wml(run_node, "rPr");
+        // The run's character style sits between the paragraph style and the
+        // run's direct formatting
+        let char_style = rpr
+            .and_then(|n| wml_attr(n, "rStyle"))
+            .and_then(|id| styles.character_style(id));
 
         let font_size = rpr
             .and_then(|n| wml_attr(n, "sz"))
             .and_then(|v| v.parse::<f32>().ok())
             .map(|hp| hp / 2.0)
+            .or_else(|| char_style.and_then(|s| s.font_size))
             .unwrap_or(style_font_size);
 
+        let base_font_name = char_style
+            .and_then(|s| s.font_name.clone())
+            .unwrap_or_else(|| style_font_name.clone());
         let font_name = rpr
             .and_then(|n| wml(n, "rFonts"))
-            .map(|rfonts| resolve_font_from_node(rfonts, theme, &style_font_name))
-            .unwrap_or_else(|| style_font_name.clone());
+            .map(|rfonts| resolve_font_from_node(rfonts, theme, &base_font_name))
+            .unwrap_or(base_font_name);
+
+        // Characters outside ASCII take the font of their script's slot
+        let rfonts = rpr.and_then(|n| wml(n, "rFonts"));
+        let script_fonts = rfonts
+            .map(|n| ScriptFonts::from_node(n, theme))
+            .unwrap_or_default()
+            .or(&char_style
+                .map(|s| s.script_fonts.clone())
+                .unwrap_or_default())
+            .or(&style_script_fonts);
+        let h_ansi_font = script_fonts.h_ansi.unwrap_or_else(|| font_name.clone());
+        let east_asia_font = script_fonts.east_asia.unwrap_or_else(|| font_name.clone());
+        let cs_font = script_fonts.cs.unwrap_or_else(|| font_name.clone());
+        let font_size_cs = rpr
+            .and_then(|n| wml_attr(n, "szCs"))
+            .and_then(|v| v.parse::<f32>().ok())
+            .map(|hp| hp / 2.0)
+            .or_else(|| char_style.and_then(|s| s.font_size_cs))
+            .or(style_font_size_cs)
+            .unwrap_or(font_size);
+        let east_asia_hint = rfonts.and_then(|n| n.attribute((WML_NS, "hint"))) == Some("eastAsia");
+        // w:cs and w:rtl format the whole run as complex script
+        let complex_script = rpr.is_some_and(|n| {
+            wml_toggle(n, "cs").unwrap_or(false) || wml_toggle(n, "rtl").unwrap_or(false)
+        });
 
         let bold = match rpr.and_then(|n| wml(n, "b")) {
             Some(n) => n
                 .attribute((WML_NS, "val"))
                 .is_none_or(|v| v != "0" && v != "false"),
-            None => style_bold,
+            None => char_style.and_then(|s| s.bold).unwrap_or(style_bold),
         };
         let italic = match rpr.and_then(|n| wml(n, "i")) {
             Some(n) => n
                 .attribute((WML_NS, "val"))
                 .is_none_or(|v| v != "0" && v != "false"),
-            None => style_italic,
+            None => char_style.and_then(|s| s.italic).unwrap_or(style_italic),
         };
-        let underline = rpr
-            .and_then(|n| wml(n, "u"))
-            .and_then(|n| n.attribute((WML_NS, "val")))
-            .is_some_and(|v| v != "none");
-        let strikethrough = rpr
-            .and_then(|n| wml(n, "strike"))
-            .is_some_and(|n| {
-                n.attribute((WML_NS, "val"))
-                    .is_none_or(|v| v != "0" && v != "false")
-            });
+        let (underline, underline_color) = rpr
+            .and_then(|n| parse_underline(n, theme))
+            .or_else(|| {
+                let s = char_style?;
+                Some((s.underline?, s.underline_color))
+            })
+            .unwrap_or((style_underline, style_underline_color));
+        let strikethrough = rpr.and_then(|n| wml(n, "strike")).is_some_and(|n| {
+            n.attribute((WML_NS, "val"))
+                .is_none_or(|v| v != "0" && v != "false")
+        });
 
         let color = rpr
-            .and_then(|n| wml_attr(n, "color"))
-            .and_then(parse_hex_color)
+            .and_then(|n| run_color(n, theme))
+            .or_else(|| char_style.and_then(|s| s.color))
             .or(style_color);
 
         let vertical_align = rpr
@@ -834,6 +2203,109 @@ fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFont
             })
             .unwrap_or(VertAlign::Baseline);
 
+        let caps = rpr
+            .and_then(|n| wml_toggle(n, "caps"))
+            .or_else(|| char_style.and_then(|s| s.caps))
+            .unwrap_or(style_caps);
+        let small_caps = rpr
+            .and_then(|n| wml_toggle(n, "smallCaps"))
+            .or_else(|| char_style.and_then(|s| s.small_caps))
+            .unwrap_or(style_small_caps);
+        let hidden = rpr
+            .and_then(|n| wml_toggle(n, "vanish"))
+            .or_else(|| char_style.and_then(|s| s.hidden))
+            .unwrap_or(style_hidden);
+        let char_spacing = rpr
+            .and_then(|n| wml(n, "spacing"))
+            .and_then(|n| twips_attr(n, "val"))
+            .or_else(|| char_style.and_then(|s| s.char_spacing))
+            .unwrap_or(style_char_spacing);
+        let scale = rpr
+            .and_then(parse_text_scale)
+            .or_else(|| char_style.and_then(|s| s.scale))
+            .unwrap_or(style_scale);
+        let kerning = rpr
+            .and_then(parse_kern)
+            .or_else(|| char_style.and_then(|s| s.kern))
+            .or(style_kern)
+            .is_some_and(|min_size| min_size > 0.0 && font_size >= min_size);
+
+        let text_run = |text: String| Run {
+            text,
+            font_size,
+            font_name: font_name.clone(),
+            bold,
+            italic,
+            underline,
+            underline_color,
+            strikethrough,
+            color,
+            is_tab: false,
+            is_line_break: false,
+            is_page_break: false,
+            vertical_align,
+            field_code: None,
+            caps,
+            small_caps,
+            hidden,
+            link: link.clone(),
+            char_spacing,
+            scale,
+            kerning,
+        };
+        // Text split into runs of one font and size each
+        let script_runs = |text: String| {
+            let mut pieces: Vec<Run> = Vec::new();
+            let mut slot = FontSlot::Ascii;
+            for c in text.chars() {
+                if complex_script {
+                    slot = FontSlot::ComplexScript;
+                } else if let Some(s) = font_slot(c, east_asia_hint) {
+                    slot = s;
+                }
+                let (font, size) = match slot {
+                    FontSlot::Ascii => (&font_name, font_size),
+                    FontSlot::HAnsi => (&h_ansi_font, font_size),
+                    FontSlot::EastAsia => (&east_asia_font, font_size),
+                    FontSlot::ComplexScript => (&cs_font, font_size_cs),
+                };
+                match pieces.last_mut() {
+                    Some(last) if last.font_name == *font && last.font_size == size => {
+                        last.text.push(c)
+                    }
+                    _ => pieces.push(Run {
+                        font_name: font.clone(),
+                        font_size: size,
+                        ..text_run(c.to_string())
+                    }),
+                }
+            }
+            pieces
+        };
+
+        if let Some(code) = simple_field {
+            runs.push(Run {
+                field_code: Some(code),
+                ..text_run(String::new())
+            });
+            continue;
+        }
+
+        if let Some(piece) = math {
+            let has_font = rpr.and_then(|n| wml(n, "rFonts")).is_some();
+            runs.push(Run {
+                font_name: if has_font {
+                    font_name.clone()
+                } else {
+                    "Cambria Math".into()
+                },
+                italic: italic || piece.italic,
+                vertical_align: piece.vertical_align,
+                ..text_run(piece.text)
+            });
+            continue;
+        }
+
         // Iterate children in document order to handle w:t, w:tab, w:br, w:fldChar, w:instrText
         let mut pending_text = String::new();
         for child in run_node.children() {
@@ -846,120 +2318,134 @@ fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFont
                         Some("begin") => {
                             // Flush pending text before entering field
                             if !pending_text.is_empty() {
+                                runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                            }
+                            fields.begin();
+                        }
+                        Some("separate") => {
+                            if let Some(code) = fields.separate() {
                                 runs.push(Run {
-                                    text: std::mem::take(&mut pending_text),
-                                    font_size,
-                                    font_name: font_name.clone(),
-                                    bold,
-                                    italic,
-                                    underline,
-                                    strikethrough,
-                                    color,
-                                    is_tab: false,
-                                    vertical_align,
-                                    field_code: None,
+                                    field_code: Some(code),
+                                    ..text_run(String::new())
                                 });
                             }
-                            in_field = true;
-                            field_instr.clear();
                         }
                         Some("end") => {
-                            if in_field {
-                                let trimmed = field_instr.trim();
-                                let fc = if trimmed.eq_ignore_ascii_case("PAGE") {
-                                    Some(FieldCode::Page)
-                                } else if trimmed.eq_ignore_ascii_case("NUMPAGES") {
-                                    Some(FieldCode::NumPages)
-                                } else {
-                                    None
-                                };
-                                if let Some(code) = fc {
-                                    runs.push(Run {
-                                        text: String::new(),
-                                        font_size,
-                                        font_name: font_name.clone(),
-                                        bold,
-                                        italic,
-                                        underline: false,
-                                        strikethrough: false,
-                                        color,
-                                        is_tab: false,
-                                        vertical_align: VertAlign::Baseline,
-                                        field_code: Some(code),
-                                    });
-                                }
-                                in_field = false;
-                                field_instr.clear();
+                            if let Some(code) = fields.end() {
+                                runs.push(Run {
+                                    field_code: Some(code),
+                                    ..text_run(String::new())
+                                });
                             }
                         }
                         _ => {}
                     }
                 }
-                "instrText" if in_field => {
+                "instrText" => {
                     if let Some(t) = child.text() {
-                        field_instr.push_str(t);
+                        fields.instr_text(t);
                     }
                 }
-                "t" if !in_field => {
+                "t" if fields.showing() => {
                     if let Some(t) = child.text() {
                         pending_text.push_str(t);
                     }
                 }
-                "tab" if !in_field => {
-                    // Flush any pending text before the tab
-                    if !pending_text.is_empty() {
+                "noBreakHyphen" if fields.showing() => pending_text.push('\u{2011}'),
+                "softHyphen" if fields.showing() => pending_text.push('\u{00AD}'),
+                "sym" if fields.showing() => {
+                    let sym_char = child
+                        .attribute((WML_NS, "char"))
+                        .and_then(|v| u32::from_str_radix(v, 16).ok())
+                        .map(|code| if code < 0x100 { code + 0xF000 } else { code })
+                        .and_then(char::from_u32);
+                    if let Some(c) = sym_char {
+                        if !pending_text.is_empty() {
+                            runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                        }
+                        let sym_font = child
+                            .attribute((WML_NS, "font"))
+                            .map(|f| f.to_string())
+                            .unwrap_or_else(|| font_name.clone());
+                        runs.push(Run {
+                            font_name: sym_font,
+                            ..text_run(c.to_string())
+                        });
+                    }
+                }
+                "endnoteReference" | "endnoteRef" if fields.showing() => {
+                    // endnoteRef marks the number inside the note itself
+                    let note_id = if child.tag_name().name() == "endnoteRef" {
+                        child
+                            .ancestors()
+                            .find(|n| n.tag_name().name() == "endnote")
+                            .and_then(|n| n.attribute((WML_NS, "id")))
+                    } else {
+                        child.attribute((WML_NS, "id"))
+                    };
+                    if let Some(label) = note_id.and_then(|id| endnote_labels.get(id)) {
+                        if !pending_text.is_empty() {
+                            runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                        }
                         runs.push(Run {
-                            text: std::mem::take(&mut pending_text),
-                            font_size,
-                            font_name: font_name.clone(),
-                            bold,
-                            italic,
-                            underline,
-                            strikethrough,
-                            color,
-                            is_tab: false,
-                            vertical_align,
-                            field_code: None,
+                            vertical_align: VertAlign::Superscript,
+                            ..text_run(label.clone())
                         });
                     }
+                }
+                "tab" if fields.showing() => {
+                    // Flush any pending text before the tab
+                    if !pending_text.is_empty() {
+                        runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                    }
                     // Insert tab marker run
                     runs.push(Run {
-                        text: String::new(),
-                        font_size,
-                        font_name: font_name.clone(),
                         bold: false,
                         italic: false,
-                        underline: false,
+                        underline: UnderlineStyle::None,
+                        underline_color: None,
                         strikethrough: false,
                         color: None,
                         is_tab: true,
                         vertical_align: VertAlign::Baseline,
-                        field_code: None,
+                        caps: false,
+                        small_caps: false,
+                        ..text_run(String::new())
                     });
                 }
-                "br" if !in_field => {
-                    if child.attribute((WML_NS, "type")) == Some("page") {
-                        has_page_break = true;
+                "br" if fields.showing() => match child.attribute((WML_NS, "type")) {
+                    Some("page") => {
+                        // A break before any text moves the whole paragraph to the next
+                        // page; otherwise the paragraph is split at this marker.
+                        if pending_text.is_empty() && runs.iter().all(|r| r.text.is_empty()) {
+                            has_page_break = true;
+                        } else {
+                            if !pending_text.is_empty() {
+                                runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                            }
+                            runs.push(Run {
+                                is_page_break: true,
+                                ..text_run(String::new())
+                            });
+                        }
                     }
-                }
+                    Some("column") => {}
+                    _ => {
+                        if !pending_text.is_empty() {
+                            runs.extend(script_runs(std::mem::take(&mut pending_text)));
+                        }
+                        runs.push(Run {
+                            is_line_break: true,
+                            ..text_run(String::new())
+                        });
+                    }
+                },
                 _ => {}
             }
         }
         // Flush remaining text
         if !pending_text.is_empty() {
-            runs.push(Run {
-                text: pending_text,
-                font_size,
-                font_name,
-                bold,
-                italic,
-                underline,
-                strikethrough,
-                color,
-                is_tab: false,
-                vertical_align,
-                field_code: None,
-            });
+            runs.extend(script_runs(pending_text));
         }
     }
 
@@ -995,175 +2481,514 @@ fn parse_runs(para_node: roxmltree::Node, styles: &StylesInfo, theme: &ThemeFont
                 font_name: mark_font_name,
                 bold: style_bold,
                 italic: style_italic,
-                underline: false,
+                underline: UnderlineStyle::None,
+                underline_color: None,
                 strikethrough: false,
                 color: None,
                 is_tab: false,
+                is_line_break: false,
+                is_page_break: false,
                 vertical_align: VertAlign::Baseline,
                 field_code: None,
+                caps: false,
+                small_caps: false,
+                hidden: false,
+                link: None,
+                char_spacing: 0.0,
+                scale: 1.0,
+                kerning: false,
+            });
+        }
+    }
+
+    ParsedRuns {
+        runs,
+        has_page_break,
+    }
+}
+
+/// A paragraph of a header, footer or text box: alignment, spacing, tab stops,
+/// runs and images, without lists, borders or page flow.
+fn parse_simple_paragraph(
+    node: roxmltree::Node,
+    styles: &StylesInfo,
+    theme: &Theme,
+    rels: &HashMap<String, String>,
+    fields: &mut FieldState,
+    package: &Package,
+    warnings: &mut Vec<Warning>,
+) -> Paragraph {
+    let ppr = wml(node, "pPr");
+    let para_style_id = ppr
+        .and_then(|ppr| wml_attr(ppr, "pStyle"))
+        .unwrap_or("Normal");
+    let para_style = styles.paragraph_styles.get(para_style_id);
+
+    let alignment = ppr
+        .and_then(|ppr| wml_attr(ppr, "jc"))
+        .map(parse_alignment)
+        .or_else(|| para_style.and_then(|s| s.alignment))
+        .unwrap_or(Alignment::Left);
+
+    let inline_spacing = ppr.and_then(|ppr| wml(ppr, "spacing"));
+    let line_h = nominal_line_height(
+        para_style
+            .and_then(|s| s.font_size)
+            .unwrap_or(styles.defaults.font_size),
+        para_style
+            .and_then(|s| s.line_spacing)
+            .unwrap_or(styles.defaults.line_spacing),
+    );
+    let space_before = inline_spacing
+        .and_then(|n| paragraph_spacing(n, "before", line_h))
+        .or_else(|| para_style.map(|s| s.space_before))
+        .unwrap_or(0.0);
+    let space_after = inline_spacing
+        .and_then(|n| paragraph_spacing(n, "after", line_h))
+        .or_else(|| para_style.and_then(|s| s.space_after))
+        .unwrap_or(styles.defaults.space_after);
+
+    let parsed = parse_runs(node, styles, theme, rels, &HashMap::new(), fields, None);
+    let drawing = compute_drawing_info(node, rels, package, warnings);
+
+    Paragraph {
+        runs: parsed.runs,
+        space_before,
+        space_after,
+        content_height: drawing.height,
+        alignment,
+        indent_left: 0.0,
+        indent_hanging: 0.0,
+        indent_right: 0.0,
+        indent_first_line: 0.0,
+        list_label: None,
+        contextual_spacing: false,
+        keep_next: false,
+        keep_lines: false,
+        widow_control: true,
+        line_spacing: None,
+        image: drawing.image,
+        border_bottom: None,
+        page_break_before: false,
+        tab_stops: merge_tab_stops(
+            para_style.map_or(&[], |s| s.tab_stops.as_slice()),
+            &ppr.map(parse_tab_stops).unwrap_or_default(),
+        ),
+        bookmarks: vec![],
+        text_boxes: vec![],
+        outline_level: None,
+    }
+}
+
+fn parse_header_footer_xml(
+    part: &str,
+    xml_content: &str,
+    styles: &StylesInfo,
+    theme: &Theme,
+    rels: &HashMap<String, String>,
+    package: &Package,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<HeaderFooter>, Error> {
+    let xml = parse_part(part, xml_content)?;
+    let root = xml.root_element();
+    let mut fields = FieldState::default();
+
+    let paragraphs: Vec<Paragraph> = content_children(root)
+        .into_iter()
+        .filter(|n| is_wml_element(n, "p"))
+        .map(|node| {
+            parse_simple_paragraph(node, styles, theme, rels, &mut fields, package, warnings)
+        })
+        .collect();
+
+    Ok((!paragraphs.is_empty()).then_some(HeaderFooter { paragraphs }))
+}
+
+/// `wp:positionH` or `wp:positionV` of a floating object.
+fn parse_float_position(anchor: roxmltree::Node, axis: &str) -> (FloatRelative, FloatPosition) {
+    let Some(node) = anchor
+        .children()
+        .find(|n| n.tag_name().name() == axis && n.tag_name().namespace() == Some(WPD_NS))
+    else {
+        return (FloatRelative::Margin, FloatPosition::Offset(0.0));
+    };
+    let relative = match node.attribute("relativeFrom") {
+        Some("page") => FloatRelative::Page,
+        Some("column" | "character" | "paragraph" | "line") => FloatRelative::Paragraph,
+        _ => FloatRelative::Margin,
+    };
+    let child_text = |name: &str| {
+        node.children()
+            .find(|n| n.tag_name().name() == name && n.tag_name().namespace() == Some(WPD_NS))
+            .and_then(|n| n.text())
+            .map(str::trim)
+    };
+    let position = if let Some(offset) = child_text("posOffset").and_then(|v| v.parse::<f32>().ok())
+    {
+        FloatPosition::Offset(offset / 12700.0)
+    } else {
+        match child_text("align") {
+            Some("center") => FloatPosition::Center,
+            Some("right" | "bottom" | "outside") => FloatPosition::End,
+            _ => FloatPosition::Start,
+        }
+    };
+    (relative, position)
+}
+
+/// Text boxes anchored in a paragraph, including those inside
+/// `mc:AlternateContent` (whose VML fallback is skipped).
+fn parse_text_boxes(
+    para_node: roxmltree::Node,
+    styles: &StylesInfo,
+    theme: &Theme,
+    rels: &HashMap<String, String>,
+    fields: &mut FieldState,
+    package: &Package,
+    warnings: &mut Vec<Warning>,
+) -> Vec<TextBox> {
+    let mut boxes = Vec::new();
+    for anchor in para_node
+        .descendants()
+        .filter(|n| n.tag_name().name() == "anchor" && n.tag_name().namespace() == Some(WPD_NS))
+    {
+        let Some(content) = anchor
+            .descendants()
+            .find(|n| is_wml_element(n, "txbxContent"))
+        else {
+            continue;
+        };
+        let extent = anchor
+            .children()
+            .find(|n| n.tag_name().name() == "extent" && n.tag_name().namespace() == Some(WPD_NS));
+        let emu = |node: Option<roxmltree::Node>, attr: &str, default: f32| {
+            node.and_then(|n| n.attribute(attr))
+                .and_then(|v| v.parse::<f32>().ok())
+                .unwrap_or(default)
+                / 12700.0
+        };
+        let body_pr = anchor
+            .descendants()
+            .find(|n| n.tag_name().name() == "bodyPr");
+        let (h_relative, h_position) = parse_float_position(anchor, "positionH");
+        let (v_relative, v_position) = parse_float_position(anchor, "positionV");
+        boxes.push(TextBox {
+            paragraphs: content_children(content)
+                .into_iter()
+                .filter(|n| is_wml_element(n, "p"))
+                .map(|p| parse_simple_paragraph(p, styles, theme, rels, fields, package, warnings))
+                .collect(),
+            width: emu(extent, "cx", 0.0),
+            height: emu(extent, "cy", 0.0),
+            h_relative,
+            h_position,
+            v_relative,
+            v_position,
+            insets: [
+                emu(body_pr, "lIns", 91440.0),
+                emu(body_pr, "tIns", 45720.0),
+                emu(body_pr, "rIns", 91440.0),
+                emu(body_pr, "bIns", 45720.0),
+            ],
+        });
+    }
+    boxes
+}
+
+/// A piece of an equation written out as linear text: the node whose `w:rPr`
+/// formats it (an `m:r`, or the structure that added the text) and its text.
+struct MathText<'a> {
+    node: roxmltree::Node<'a, 'a>,
+    text: String,
+    vertical_align: VertAlign,
+    italic: bool, // math variables are italic unless m:sty or m:nor says otherwise
+}
+
+fn math_child<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<roxmltree::Node<'a, 'a>> {
+    node.children()
+        .find(|n| n.tag_name().name() == name && n.tag_name().namespace() == Some(MATH_NS))
+}
+
+/// `m:val` of property `prop` in the `m:*Pr` child `pr` of an OMML element.
+fn math_prop<'a>(node: roxmltree::Node<'a, 'a>, pr: &str, prop: &str) -> Option<&'a str> {
+    math_child(math_child(node, pr)?, prop)?.attribute((MATH_NS, "val"))
+}
+
+/// Write an OMML equation out as linear text: scripts become superscript and
+/// subscript pieces, fractions "a/b", radicals "√(x)" and delimiters keep their
+/// characters. Layout in two dimensions is not attempted.
+fn linearize_math<'a>(
+    node: roxmltree::Node<'a, 'a>,
+    vertical_align: VertAlign,
+    out: &mut Vec<MathText<'a>>,
+) {
+    let push = |out: &mut Vec<MathText<'a>>, text: &str| {
+        out.push(MathText {
+            node,
+            text: text.to_string(),
+            vertical_align,
+            italic: false,
+        })
+    };
+    // Linearize the content of the argument `name`, e.g. m:e or m:sup
+    let part = |out: &mut Vec<MathText<'a>>, name: &str, va: VertAlign| {
+        for child in math_child(node, name)
+            .into_iter()
+            .flat_map(|n| n.children())
+        {
+            linearize_math(child, va, out);
+        }
+    };
+    if node.tag_name().namespace() != Some(MATH_NS) {
+        return;
+    }
+    match node.tag_name().name() {
+        "r" => {
+            let text: String = node
+                .children()
+                .filter(|n| n.tag_name().name() == "t" && n.tag_name().namespace() == Some(MATH_NS))
+                .filter_map(|n| n.text())
+                .collect();
+            let normal = math_child(node, "rPr")
+                .and_then(|pr| math_child(pr, "nor"))
+                .is_some();
+            let sty = math_prop(node, "rPr", "sty");
+            out.push(MathText {
+                node,
+                text,
+                vertical_align,
+                italic: !normal && matches!(sty, None | Some("i" | "bi")),
+            });
+        }
+        "sSup" => {
+            part(out, "e", vertical_align);
+            part(out, "sup", VertAlign::Superscript);
+        }
+        "sSub" => {
+            part(out, "e", vertical_align);
+            part(out, "sub", VertAlign::Subscript);
+        }
+        "sSubSup" => {
+            part(out, "e", vertical_align);
+            part(out, "sub", VertAlign::Subscript);
+            part(out, "sup", VertAlign::Superscript);
+        }
+        "sPre" => {
+            part(out, "sub", VertAlign::Subscript);
+            part(out, "sup", VertAlign::Superscript);
+            part(out, "e", vertical_align);
+        }
+        "f" => {
+            // Parenthesize numerators and denominators longer than one symbol
+            for (i, name) in ["num", "den"].into_iter().enumerate() {
+                if i > 0 {
+                    push(out, "/");
+                }
+                let mut arg = Vec::new();
+                part(&mut arg, name, vertical_align);
+                let len: usize = arg.iter().map(|p| p.text.chars().count()).sum();
+                if len > 1 {
+                    push(out, "(");
+                    out.append(&mut arg);
+                    push(out, ")");
+                } else {
+                    out.append(&mut arg);
+                }
+            }
+        }
+        "rad" => {
+            if math_prop(node, "radPr", "degHide").is_none_or(|v| v == "0" || v == "off") {
+                part(out, "deg", VertAlign::Superscript);
+            }
+            push(out, "\u{221A}(");
+            part(out, "e", vertical_align);
+            push(out, ")");
+        }
+        "d" => {
+            let beg = math_prop(node, "dPr", "begChr").unwrap_or("(");
+            let sep = math_prop(node, "dPr", "sepChr").unwrap_or("|");
+            let end = math_prop(node, "dPr", "endChr").unwrap_or(")");
+            push(out, beg);
+            let args = node.children().filter(|n| {
+                n.tag_name().name() == "e" && n.tag_name().namespace() == Some(MATH_NS)
             });
+            for (i, arg) in args.enumerate() {
+                if i > 0 {
+                    push(out, sep);
+                }
+                for child in arg.children() {
+                    linearize_math(child, vertical_align, out);
+                }
+            }
+            push(out, end);
         }
-    }
-
-    ParsedRuns {
-        runs,
-        has_page_break,
-    }
-}
-
-fn parse_header_footer_xml(
-    xml_content: &str,
-    styles: &StylesInfo,
-    theme: &ThemeFonts,
-) -> Option<HeaderFooter> {
-    let xml = roxmltree::Document::parse(xml_content).ok()?;
-    let root = xml.root_element();
-    let mut paragraphs = Vec::new();
-
-    for node in root.children() {
-        if node.tag_name().namespace() != Some(WML_NS) || node.tag_name().name() != "p" {
-            continue;
+        "nary" => {
+            push(out, math_prop(node, "naryPr", "chr").unwrap_or("\u{222B}"));
+            part(out, "sub", VertAlign::Subscript);
+            part(out, "sup", VertAlign::Superscript);
+            part(out, "e", vertical_align);
+        }
+        "limLow" => {
+            part(out, "e", vertical_align);
+            part(out, "lim", VertAlign::Subscript);
+        }
+        "limUpp" => {
+            part(out, "e", vertical_align);
+            part(out, "lim", VertAlign::Superscript);
+        }
+        // Property elements carry no text
+        name if name.ends_with("Pr") => {}
+        _ => {
+            for child in node.children() {
+                linearize_math(child, vertical_align, out);
+            }
         }
-        let ppr = wml(node, "pPr");
-        let para_style_id = ppr
-            .and_then(|ppr| wml_attr(ppr, "pStyle"))
-            .unwrap_or("Normal");
-        let para_style = styles.paragraph_styles.get(para_style_id);
-
-        let alignment = ppr
-            .and_then(|ppr| wml_attr(ppr, "jc"))
-            .map(parse_alignment)
-            .or_else(|| para_style.and_then(|s| s.alignment))
-            .unwrap_or(Alignment::Left);
-
-        let parsed = parse_runs(node, styles, theme);
-
-        paragraphs.push(Paragraph {
-            runs: parsed.runs,
-            space_before: 0.0,
-            space_after: 0.0,
-            content_height: 0.0,
-            alignment,
-            indent_left: 0.0,
-            indent_hanging: 0.0,
-            list_label: String::new(),
-            contextual_spacing: false,
-            keep_next: false,
-            line_spacing: None,
-            image: None,
-            border_bottom: None,
-            page_break_before: false,
-            tab_stops: vec![],
-        });
     }
+}
 
-    if paragraphs.is_empty() {
-        None
-    } else {
-        Some(HeaderFooter { paragraphs })
-    }
+/// Parse the XML of part `part`, naming the part in the error.
+fn parse_part<'a>(part: &str, xml_content: &'a str) -> Result<roxmltree::Document<'a>, Error> {
+    roxmltree::Document::parse(xml_content).map_err(|source| Error::Xml {
+        part: part.into(),
+        source,
+    })
 }
 
-fn read_zip_text(zip: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Option<String> {
+/// The text of part `name`, or None if the archive has no such part. A part
+/// that is there but cannot be read or decompressed is an error naming it.
+fn read_zip_text<R: Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Option<String>, Error> {
+    let part_error = |source| Error::Part {
+        part: name.into(),
+        source,
+    };
+    let mut file = match zip.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(part_error(e.into())),
+    };
     let mut content = String::new();
-    zip.by_name(name).ok()?.read_to_string(&mut content).ok()?;
-    Some(content)
+    file.read_to_string(&mut content).map_err(part_error)?;
+    Ok(Some(content))
 }
 
-pub fn parse(path: &Path) -> Result<Document, Error> {
+pub fn parse(path: &Path, warnings: &mut Vec<Warning>) -> Result<Document, Error> {
     let file = std::fs::File::open(path).map_err(|e| match e.kind() {
         std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied => Error::Io(
             std::io::Error::new(e.kind(), format!("{}: {}", e, path.display())),
         ),
         _ => Error::Io(e),
     })?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    parse_reader(file, file_name, warnings)
+}
 
-    let mut zip = zip::ZipArchive::new(file)
+/// Parse a DOCX from any seekable reader, such as a `Cursor` over an upload
+/// held in memory. `file_name` is the text of FILENAME fields. Anything left
+/// out of the document is reported in `warnings`.
+pub fn parse_reader<R: Read + Seek>(
+    reader: R,
+    file_name: String,
+    warnings: &mut Vec<Warning>,
+) -> Result<Document, Error> {
+    const DOCUMENT_PART: &str = "word/document.xml";
+    let mut zip = zip::ZipArchive::new(reader)
         .map_err(|_| Error::InvalidDocx("file is not a ZIP archive".into()))?;
 
-    let theme = parse_theme(&mut zip);
-    let styles = parse_styles(&mut zip, &theme);
-    let numbering = parse_numbering(&mut zip);
-    let rels = parse_relationships(&mut zip);
-    let embedded_fonts = parse_font_table(&mut zip);
-
-    let mut xml_content = String::new();
-    zip.by_name("word/document.xml")
-        .map_err(|_| Error::InvalidDocx("missing word/document.xml (is this a DOCX file?)".into()))?
-        .read_to_string(&mut xml_content)?;
+    let theme = parse_theme(&mut zip)?;
+    let styles = parse_styles(&mut zip, &theme)?;
+    let numbering = parse_numbering(&mut zip, &theme)?;
+    let package = Package::read(&mut zip)?;
+    let rels = package.rels(DOCUMENT_PART);
+    let embedded_fonts = parse_font_table(&mut zip, &package)?;
+    let settings = parse_settings(&mut zip)?;
+    let metadata = parse_core_properties(&mut zip)?;
+
+    let xml_content = read_zip_text(&mut zip, DOCUMENT_PART)?.ok_or_else(|| {
+        Error::InvalidDocx(format!("missing {DOCUMENT_PART} (is this a DOCX file?)"))
+    })?;
 
-    let xml = roxmltree::Document::parse(&xml_content)?;
+    let xml = parse_part(DOCUMENT_PART, &xml_content)?;
     let root = xml.root_element();
 
-    let body = wml(root, "body").ok_or_else(|| Error::Pdf("Missing w:body".into()))?;
-
-    let sect = wml(body, "sectPr");
-    let pg_sz = sect.and_then(|s| wml(s, "pgSz"));
-    let pg_mar = sect.and_then(|s| wml(s, "pgMar"));
-    let doc_grid = sect.and_then(|s| wml(s, "docGrid"));
-
-    let page_width = pg_sz.and_then(|n| twips_attr(n, "w")).unwrap_or(612.0);
-    let page_height = pg_sz.and_then(|n| twips_attr(n, "h")).unwrap_or(792.0);
-    let margin_top = pg_mar.and_then(|n| twips_attr(n, "top")).unwrap_or(72.0);
-    let margin_bottom = pg_mar.and_then(|n| twips_attr(n, "bottom")).unwrap_or(72.0);
-    let margin_left = pg_mar.and_then(|n| twips_attr(n, "left")).unwrap_or(72.0);
-    let margin_right = pg_mar.and_then(|n| twips_attr(n, "right")).unwrap_or(72.0);
-    let header_margin = pg_mar.and_then(|n| twips_attr(n, "header")).unwrap_or(36.0);
-    let footer_margin = pg_mar.and_then(|n| twips_attr(n, "footer")).unwrap_or(36.0);
-    let line_pitch = doc_grid
-        .and_then(|n| twips_attr(n, "linePitch"))
-        .unwrap_or(styles.defaults.font_size * 1.2);
-
-    let different_first_page = sect.and_then(|s| wml(s, "titlePg")).is_some();
-
-    // Parse header/footer references from sectPr
-    let mut header_default_rid = None;
-    let mut header_first_rid = None;
-    let mut footer_default_rid = None;
-    let mut footer_first_rid = None;
-    if let Some(sect) = sect {
-        for child in sect.children() {
-            if child.tag_name().namespace() != Some(WML_NS) {
-                continue;
-            }
-            let hf_type = child.attribute((WML_NS, "type")).unwrap_or("");
-            let rid = child.attribute((REL_NS, "id"));
-            match child.tag_name().name() {
-                "headerReference" => match hf_type {
-                    "default" => header_default_rid = rid,
-                    "first" => header_first_rid = rid,
-                    _ => {}
-                },
-                "footerReference" => match hf_type {
-                    "default" => footer_default_rid = rid,
-                    "first" => footer_first_rid = rid,
-                    _ => {}
-                },
-                _ => {}
-            }
+    let body = wml(root, "body")
+        .ok_or_else(|| Error::InvalidDocx(format!("{DOCUMENT_PART} has no w:body")))?;
+
+    let body_sect = wml(body, "sectPr");
+
+    // Endnotes are numbered in order of their first reference in the body
+    let endnote_fmt = body_sect
+        .and_then(|s| wml(s, "endnotePr"))
+        .and_then(|n| wml_attr(n, "numFmt"))
+        .unwrap_or("lowerRoman");
+    let mut endnote_order: Vec<&str> = Vec::new();
+    for node in body.descendants() {
+        if node.tag_name().name() == "endnoteReference"
+            && node.tag_name().namespace() == Some(WML_NS)
+            && let Some(id) = node.attribute((WML_NS, "id"))
+            && !endnote_order.contains(&id)
+        {
+            endnote_order.push(id);
         }
     }
+    let endnote_labels: HashMap<String, String> = endnote_order
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.to_string(), format_number(i + 1, endnote_fmt)))
+        .collect();
 
-    let resolve_hf =
-        |rid: Option<&str>, zip: &mut zip::ZipArchive<std::fs::File>| -> Option<HeaderFooter> {
-            let target = rels.get(rid?)?;
-            let zip_path = target
-                .strip_prefix('/')
-                .map(String::from)
-                .unwrap_or_else(|| format!("word/{}", target));
-            let xml_text = read_zip_text(zip, &zip_path)?;
-            parse_header_footer_xml(&xml_text, &styles, &theme)
-        };
-
-    let header_default = resolve_hf(header_default_rid, &mut zip);
-    let header_first = resolve_hf(header_first_rid, &mut zip);
-    let footer_default = resolve_hf(footer_default_rid, &mut zip);
-    let footer_first = resolve_hf(footer_first_rid, &mut zip);
+    let endnotes_xml = if endnote_order.is_empty() {
+        None
+    } else {
+        read_zip_text(&mut zip, "word/endnotes.xml")?
+    };
+    let endnotes_doc = endnotes_xml
+        .as_deref()
+        .map(|x| parse_part("word/endnotes.xml", x))
+        .transpose()?;
+    let endnote_paras: Vec<roxmltree::Node> = endnotes_doc
+        .as_ref()
+        .map(|d| {
+            let notes: Vec<_> = d
+                .root_element()
+                .children()
+                .filter(|n| n.tag_name().name() == "endnote")
+                .collect();
+            endnote_order
+                .iter()
+                .filter_map(|id| {
+                    notes
+                        .iter()
+                        .find(|n| n.attribute((WML_NS, "id")) == Some(*id))
+                })
+                .flat_map(|n| n.children())
+                .collect()
+        })
+        .unwrap_or_default();
 
     let mut blocks = Vec::new();
-    let mut counters: HashMap<(String, u8), u32> = HashMap::new();
-
-    for node in body.children() {
+    let mut endnotes_start = None;
+    let mut fields = FieldState::default();
+    // (number of blocks when the section ends, its sectPr)
+    let mut section_ends: Vec<(usize, Option<roxmltree::Node>)> = Vec::new();
+    let mut counters = ListCounters::default();
+    // Bookmarks between paragraphs anchor at the next paragraph
+    let mut pending_bookmarks: Vec<String> = Vec::new();
+    // Body elements we skip, with how often they occur
+    let mut unsupported: Vec<(&str, usize)> = Vec::new();
+
+    let body_children = content_children(body);
+    let body_nodes = body_children.len();
+    for (node_idx, node) in body_children.into_iter().chain(endnote_paras).enumerate() {
+        if node_idx == body_nodes {
+            endnotes_start = Some(blocks.len());
+            fields = FieldState::default();
+        }
         if node.tag_name().namespace() != Some(WML_NS) {
             continue;
         }
@@ -1178,26 +3003,131 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                     .filter_map(|n| twips_attr(n, "w"))
                     .collect();
 
+                let tbl_pr = wml(node, "tblPr");
+                let table_fill = tbl_pr
+                    .and_then(|pr| wml(pr, "shd"))
+                    .and_then(|shd| shading_fill(shd, &theme));
+                // Inline tblBorders and tblCellMar override the table style edge by edge
+                let style_id = tbl_pr.and_then(|pr| wml_attr(pr, "tblStyle"));
+                let table_style = styles.table_style(style_id);
+                let look = TableLook::parse(tbl_pr.and_then(|pr| wml(pr, "tblLook")));
+                let table_borders =
+                    BorderSet::parse(tbl_pr.and_then(|pr| wml(pr, "tblBorders")), &theme)
+                        .or(table_style.borders);
+                let table_margins = MarginSet::parse(tbl_pr.and_then(|pr| wml(pr, "tblCellMar")))
+                    .or(table_style.cell_margins);
+                // Rows deleted as a tracked change are left out
+                let table_rows: Vec<_> = content_children(node)
+                    .into_iter()
+                    .filter(|n| {
+                        is_wml_element(n, "tr")
+                            && wml(*n, "trPr").and_then(|pr| wml(pr, "del")).is_none()
+                    })
+                    .collect();
+                let n_rows = table_rows.len();
+                let mut last_row_bottoms = Vec::new();
+
                 let mut rows = Vec::new();
-                for tr in node.children().filter(|n| {
-                    n.tag_name().name() == "tr" && n.tag_name().namespace() == Some(WML_NS)
-                }) {
+                // Grid columns with a vertical merge still open from the rows above
+                let mut merge_open: Vec<bool> = Vec::new();
+                for &tr in &table_rows {
                     let mut cells = Vec::new();
-                    for tc in tr.children().filter(|n| {
-                        n.tag_name().name() == "tc" && n.tag_name().namespace() == Some(WML_NS)
-                    }) {
-                        let cell_width = wml(tc, "tcPr")
+                    let mut grid_col = 0;
+                    let mut cell_bottoms = Vec::new();
+                    let row_children = content_children(tr);
+                    let n_cells = row_children
+                        .iter()
+                        .filter(|n| is_wml_element(n, "tc"))
+                        .count();
+                    for &tc in row_children.iter().filter(|n| is_wml_element(n, "tc")) {
+                        let tc_pr = wml(tc, "tcPr");
+                        let grid_span = tc_pr
+                            .and_then(|pr| wml_attr(pr, "gridSpan"))
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .unwrap_or(1)
+                            .max(1);
+                        let cell_width = tc_pr
                             .and_then(|pr| wml(pr, "tcW"))
                             .and_then(|w| twips_attr(w, "w"))
                             .unwrap_or_else(|| {
-                                col_widths.get(cells.len()).copied().unwrap_or(72.0)
+                                let w: f32 = col_widths.iter().skip(grid_col).take(grid_span).sum();
+                                if w > 0.0 { w } else { 72.0 }
                             });
+                        let format =
+                            table_style.cell_format(look, rows.len(), n_rows, cells.len(), n_cells);
+                        // Outer edges come from the table's outer borders, the
+                        // rest from insideH/insideV; the last row is fixed up below
+                        let own =
+                            BorderSet::parse(tc_pr.and_then(|pr| wml(pr, "tcBorders")), &theme)
+                                .or(format.borders);
+                        let first_row = rows.is_empty();
+                        let borders = CellBorders {
+                            top: own
+                                .top
+                                .or(if first_row {
+                                    table_borders.top
+                                } else {
+                                    table_borders.inside_h
+                                })
+                                .flatten(),
+                            left: own
+                                .left
+                                .or(if cells.is_empty() {
+                                    table_borders.left
+                                } else {
+                                    table_borders.inside_v
+                                })
+                                .flatten(),
+                            bottom: own.bottom.or(table_borders.inside_h).flatten(),
+                            right: own
+                                .right
+                                .or(if cells.len() + 1 == n_cells {
+                                    table_borders.right
+                                } else {
+                                    table_borders.inside_v
+                                })
+                                .flatten(),
+                        };
+                        cell_bottoms.push(own.bottom);
+                        let margins = MarginSet::parse(tc_pr.and_then(|pr| wml(pr, "tcMar")))
+                            .or(table_margins)
+                            .resolve();
+
+                        let mut v_merge = match tc_pr.and_then(|pr| wml(pr, "vMerge")) {
+                            Some(vm) if vm.attribute((WML_NS, "val")) == Some("restart") => {
+                                VMerge::Restart
+                            }
+                            Some(_) => VMerge::Continue,
+                            None => VMerge::None,
+                        };
+                        if merge_open.len() <= grid_col {
+                            merge_open.resize(grid_col + 1, false);
+                        }
+                        if v_merge == VMerge::Continue && !merge_open[grid_col] {
+                            v_merge = VMerge::None;
+                        }
+                        merge_open[grid_col] = v_merge != VMerge::None;
+                        grid_col += grid_span;
+                        // fill="auto" on the cell clears the table's shading
+                        let shading = match tc_pr.and_then(|pr| wml(pr, "shd")) {
+                            Some(shd) => shading_fill(shd, &theme),
+                            None => format.shading.unwrap_or(table_fill),
+                        };
 
                         let mut cell_paras = Vec::new();
-                        for p in tc.children().filter(|n| {
-                            n.tag_name().name() == "p" && n.tag_name().namespace() == Some(WML_NS)
-                        }) {
-                            let parsed = parse_runs(p, &styles, &theme);
+                        for p in content_children(tc)
+                            .into_iter()
+                            .filter(|n| is_wml_element(n, "p"))
+                        {
+                            let parsed = parse_runs(
+                                p,
+                                &styles,
+                                &theme,
+                                &rels,
+                                &endnote_labels,
+                                &mut fields,
+                                Some(&format),
+                            );
                             let ppr = wml(p, "pPr");
                             let para_style_id = ppr
                                 .and_then(|ppr| wml_attr(ppr, "pStyle"))
@@ -1216,24 +3146,90 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                                 alignment,
                                 indent_left: 0.0,
                                 indent_hanging: 0.0,
-                                list_label: String::new(),
+                                indent_right: 0.0,
+                                indent_first_line: 0.0,
+                                list_label: None,
                                 contextual_spacing: false,
                                 keep_next: false,
-                                line_spacing: Some(1.0),
+                                keep_lines: false,
+                                widow_control: true,
+                                line_spacing: Some(LineSpacing::Auto(1.0)),
                                 image: None,
                                 border_bottom: None,
                                 page_break_before: false,
                                 tab_stops: vec![],
+                                bookmarks: vec![],
+                                text_boxes: vec![],
+                                outline_level: None,
                             });
                         }
                         cells.push(TableCell {
                             width: cell_width,
+                            grid_span,
+                            v_merge,
+                            shading,
+                            borders,
+                            margins,
+                            v_align: tc_pr
+                                .and_then(|pr| wml_attr(pr, "vAlign"))
+                                .map(|v| match v {
+                                    "center" => CellVAlign::Center,
+                                    "bottom" => CellVAlign::Bottom,
+                                    _ => CellVAlign::Top,
+                                }),
                             paragraphs: cell_paras,
                         });
                     }
-                    rows.push(TableRow { cells });
+                    let tr_height = wml(tr, "trPr").and_then(|pr| wml(pr, "trHeight"));
+                    let height = tr_height.and_then(|h| twips_attr(h, "val")).unwrap_or(0.0);
+                    let height_rule = match tr_height.and_then(|h| h.attribute((WML_NS, "hRule"))) {
+                        _ if height <= 0.0 => RowHeightRule::Auto,
+                        Some("exact") => RowHeightRule::Exact,
+                        Some("auto") => RowHeightRule::Auto,
+                        _ => RowHeightRule::AtLeast,
+                    };
+                    rows.push(TableRow {
+                        cells,
+                        height,
+                        height_rule,
+                        cant_split: wml(tr, "trPr")
+                            .and_then(|pr| wml_toggle(pr, "cantSplit"))
+                            .unwrap_or(false),
+                    });
+                    last_row_bottoms = cell_bottoms;
+                }
+                if let Some(last) = rows.last_mut() {
+                    for (cell, own) in last.cells.iter_mut().zip(last_row_bottoms) {
+                        cell.borders.bottom = own.or(table_borders.bottom).flatten();
+                    }
+                }
+                let header_rows = table_rows
+                    .iter()
+                    .take_while(|tr| {
+                        wml(**tr, "trPr")
+                            .and_then(|pr| wml_toggle(pr, "tblHeader"))
+                            .unwrap_or(false)
+                    })
+                    .count();
+                blocks.push(Block::Table(Table {
+                    col_widths,
+                    rows,
+                    header_rows,
+                    alignment: tbl_pr
+                        .and_then(|pr| wml_attr(pr, "jc"))
+                        .map(parse_alignment)
+                        .unwrap_or(Alignment::Left),
+                    indent: tbl_pr
+                        .and_then(|pr| wml(pr, "tblInd"))
+                        .and_then(|n| twips_attr(n, "w"))
+                        .unwrap_or(0.0),
+                    width_pct: tbl_pr.and_then(|pr| wml(pr, "tblW")).and_then(pct_width),
+                }));
+            }
+            "bookmarkStart" => {
+                if let Some(name) = node.attribute((WML_NS, "name")) {
+                    pending_bookmarks.push(name.to_string());
                 }
-                blocks.push(Block::Table(Table { col_widths, rows }));
             }
             "p" => {
                 let ppr = wml(node, "pPr");
@@ -1246,12 +3242,22 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
 
                 let inline_spacing = ppr.and_then(|ppr| wml(ppr, "spacing"));
 
+                let line_spacing = inline_spacing
+                    .and_then(parse_line_spacing)
+                    .or_else(|| para_style.and_then(|s| s.line_spacing));
+                let line_h = nominal_line_height(
+                    para_style
+                        .and_then(|s| s.font_size)
+                        .unwrap_or(styles.defaults.font_size),
+                    line_spacing.unwrap_or(styles.defaults.line_spacing),
+                );
+
                 let space_before = inline_spacing
-                    .and_then(|n| twips_attr(n, "before"))
+                    .and_then(|n| paragraph_spacing(n, "before", line_h))
                     .or_else(|| para_style.map(|s| s.space_before))
                     .unwrap_or(0.0);
 
-                let inline_bdr = ppr.and_then(parse_border_bottom);
+                let inline_bdr = ppr.and_then(|ppr| parse_border_bottom(ppr, &theme));
                 let inline_bdr_extra = inline_bdr
                     .as_ref()
                     .map(|b| b.space_pt + b.width_pt)
@@ -1265,7 +3271,7 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                     )
                 };
                 let space_after = inline_spacing
-                    .and_then(|n| twips_attr(n, "after"))
+                    .and_then(|n| paragraph_spacing(n, "after", line_h))
                     .or_else(|| para_style.and_then(|s| s.space_after))
                     .unwrap_or(styles.defaults.space_after)
                     + bdr_extra;
@@ -1285,17 +3291,52 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                 let keep_next = ppr.and_then(|ppr| wml(ppr, "keepNext")).is_some()
                     || para_style.is_some_and(|s| s.keep_next);
 
-                let line_spacing = inline_spacing
-                    .and_then(|n| n.attribute((WML_NS, "line")))
-                    .and_then(|v| v.parse::<f32>().ok())
-                    .map(|val| val / 240.0)
-                    .or_else(|| para_style.and_then(|s| s.line_spacing));
+                let keep_lines = ppr
+                    .and_then(|ppr| wml_toggle(ppr, "keepLines"))
+                    .unwrap_or_else(|| para_style.is_some_and(|s| s.keep_lines));
+
+                let widow_control = ppr
+                    .and_then(|ppr| wml_toggle(ppr, "widowControl"))
+                    .or_else(|| para_style.and_then(|s| s.widow_control))
+                    .unwrap_or(styles.defaults.widow_control);
+
+                let parsed = parse_runs(
+                    node,
+                    &styles,
+                    &theme,
+                    &rels,
+                    &endnote_labels,
+                    &mut fields,
+                    None,
+                );
+                let mut runs = parsed.runs;
+
+                // Override font defaults from style for runs that used doc defaults
+                for run in &mut runs {
+                    if run.color.is_none() && style_color.is_some() {
+                        run.color = style_color;
+                    }
+                }
 
+                // Inline numPr values win over those of the paragraph style
                 let num_pr = ppr.and_then(|ppr| wml(ppr, "numPr"));
+                let num_id = num_pr
+                    .and_then(|n| wml_attr(n, "numId"))
+                    .or_else(|| para_style.and_then(|s| s.num_id.as_deref()));
+                let ilvl = num_pr
+                    .and_then(|n| wml_attr(n, "ilvl"))
+                    .and_then(|v| v.parse::<u8>().ok())
+                    .or_else(|| {
+                        let num_id = num_id?;
+                        numbering
+                            .style_level(num_id, para_style_id)
+                            .or_else(|| para_style.and_then(|s| s.num_ilvl))
+                    });
                 let (mut indent_left, mut indent_hanging, list_label) =
-                    parse_list_info(num_pr, &numbering, &mut counters);
+                    parse_list_info(num_id, ilvl, &numbering, &mut counters, &runs);
 
-                if let Some(ind) = ppr.and_then(|ppr| wml(ppr, "ind")) {
+                let inline_ind = ppr.and_then(|ppr| wml(ppr, "ind"));
+                if let Some(ind) = inline_ind {
                     if let Some(v) = twips_attr(ind, "left") {
                         indent_left = v;
                     }
@@ -1303,19 +3344,43 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                         indent_hanging = v;
                     }
                 }
+                let indent_right = inline_ind
+                    .and_then(indent_right_attr)
+                    .or_else(|| para_style.and_then(|s| s.indent_right))
+                    .unwrap_or(0.0);
+                let indent_first_line = inline_ind
+                    .and_then(indent_first_line_attr)
+                    .or_else(|| para_style.and_then(|s| s.indent_first_line))
+                    .unwrap_or(0.0);
 
-                let parsed = parse_runs(node, &styles, &theme);
-                let mut runs = parsed.runs;
-
-                // Override font defaults from style for runs that used doc defaults
-                for run in &mut runs {
-                    if run.color.is_none() && style_color.is_some() {
-                        run.color = style_color;
-                    }
-                }
-
-                let tab_stops = ppr.map(parse_tab_stops).unwrap_or_default();
-                let drawing = compute_drawing_info(node, &rels, &mut zip);
+                let tab_stops = merge_tab_stops(
+                    para_style.map_or(&[], |s| s.tab_stops.as_slice()),
+                    &ppr.map(parse_tab_stops).unwrap_or_default(),
+                );
+                let mut bookmarks = std::mem::take(&mut pending_bookmarks);
+                bookmarks.extend(
+                    node.descendants()
+                        .filter(|n| {
+                            n.tag_name().name() == "bookmarkStart"
+                                && n.tag_name().namespace() == Some(WML_NS)
+                        })
+                        .filter_map(|n| n.attribute((WML_NS, "name")))
+                        .map(str::to_string),
+                );
+                let outline_level = ppr
+                    .and_then(parse_outline_level)
+                    .or_else(|| para_style.and_then(|s| s.outline_level))
+                    .filter(|&level| level < 9);
+                let drawing = compute_drawing_info(node, &rels, &package, warnings);
+                let text_boxes = parse_text_boxes(
+                    node,
+                    &styles,
+                    &theme,
+                    &rels,
+                    &mut fields,
+                    &package,
+                    warnings,
+                );
 
                 blocks.push(Block::Paragraph(Paragraph {
                     runs,
@@ -1325,100 +3390,390 @@ pub fn parse(path: &Path) -> Result<Document, Error> {
                     alignment,
                     indent_left,
                     indent_hanging,
+                    indent_right,
+                    indent_first_line,
                     list_label,
                     contextual_spacing,
                     keep_next,
+                    keep_lines,
+                    widow_control,
                     line_spacing,
                     image: drawing.image,
                     border_bottom,
                     page_break_before: parsed.has_page_break,
                     tab_stops,
+                    bookmarks,
+                    text_boxes,
+                    outline_level,
                 }));
+
+                // A sectPr in the paragraph mark ends a section after this paragraph
+                if let Some(sect) = ppr.and_then(|ppr| wml(ppr, "sectPr")) {
+                    section_ends.push((blocks.len(), Some(sect)));
+                }
             }
-            _ => {}
+            // Markers without content of their own
+            "sectPr" | "bookmarkEnd" | "proofErr" | "permStart" | "permEnd"
+            | "commentRangeStart" | "commentRangeEnd" => {}
+            tag => match unsupported.iter_mut().find(|(t, _)| *t == tag) {
+                Some((_, count)) => *count += 1,
+                None => unsupported.push((tag, 1)),
+            },
+        }
+    }
+    section_ends.push((blocks.len(), body_sect));
+    warnings.extend(
+        unsupported
+            .into_iter()
+            .map(|(tag, count)| Warning::UnsupportedElement {
+                tag: tag.to_string(),
+                count,
+            }),
+    );
+
+    let mut hf_cache: HashMap<String, Option<Arc<HeaderFooter>>> = HashMap::new();
+    let mut resolve_hf = |rid: &str| -> Result<Option<Arc<HeaderFooter>>, Error> {
+        if let Some(hf) = hf_cache.get(rid) {
+            return Ok(hf.clone());
         }
+        let mut hf = None;
+        if let Some(target) = rels.get(rid) {
+            let zip_path = resolve_part_path("word", target);
+            if let Some(xml_text) = read_zip_text(&mut zip, &zip_path)? {
+                let hf_rels = package.rels(&zip_path);
+                hf = parse_header_footer_xml(
+                    &zip_path, &xml_text, &styles, &theme, &hf_rels, &package, warnings,
+                )?
+                .map(Arc::new);
+            }
+        }
+        hf_cache.insert(rid.to_string(), hf.clone());
+        Ok(hf)
+    };
+
+    let default_line_pitch = styles.defaults.font_size * 1.2;
+    let mut sections: Vec<Section> = Vec::new();
+    let mut first_block = 0;
+    for (end, sect) in section_ends {
+        let section = parse_section(
+            sect,
+            first_block,
+            sections.last(),
+            default_line_pitch,
+            &theme,
+            &mut resolve_hf,
+        )?;
+        sections.push(section);
+        first_block = end;
     }
 
+    let lang = styles.defaults.lang.clone().or(settings.lang);
     Ok(Document {
-        page_width,
-        page_height,
-        margin_top,
-        margin_bottom,
-        margin_left,
-        margin_right,
-        line_pitch,
         line_spacing: styles.defaults.line_spacing,
         blocks,
+        sections,
+        even_and_odd_headers: settings.even_and_odd_headers,
+        decimal_separator: lang.as_deref().map_or('.', decimal_separator),
+        lang,
         embedded_fonts,
+        endnotes_start,
+        file_name,
+        metadata,
+    })
+}
+
+fn parse_section(
+    sect: Option<roxmltree::Node>,
+    first_block: usize,
+    prev: Option<&Section>,
+    default_line_pitch: f32,
+    theme: &Theme,
+    resolve_hf: &mut impl FnMut(&str) -> Result<Option<Arc<HeaderFooter>>, Error>,
+) -> Result<Section, Error> {
+    let pg_sz = sect.and_then(|s| wml(s, "pgSz"));
+    let pg_mar = sect.and_then(|s| wml(s, "pgMar"));
+    let doc_grid = sect.and_then(|s| wml(s, "docGrid"));
+    let cols = sect.and_then(|s| wml(s, "cols"));
+    let pg_num_type = sect.and_then(|s| wml(s, "pgNumType"));
+
+    // Missing dimensions default to Letter in the declared orientation; some
+    // producers also write portrait dimensions alongside orient="landscape".
+    let landscape = pg_sz.and_then(|n| n.attribute((WML_NS, "orient"))) == Some("landscape");
+    let mut page_width = pg_sz.and_then(|n| twips_attr(n, "w")).unwrap_or(612.0);
+    let mut page_height = pg_sz.and_then(|n| twips_attr(n, "h")).unwrap_or(792.0);
+    if landscape && page_width < page_height {
+        std::mem::swap(&mut page_width, &mut page_height);
+    }
+
+    let start = match sect.and_then(|s| wml_attr(s, "type")) {
+        Some("continuous") => SectionBreak::Continuous,
+        _ => SectionBreak::NextPage,
+    };
+
+    // Parse header/footer references from sectPr
+    let mut header_default = prev.and_then(|p| p.header_default.clone());
+    let mut header_first = prev.and_then(|p| p.header_first.clone());
+    let mut header_even = prev.and_then(|p| p.header_even.clone());
+    let mut footer_default = prev.and_then(|p| p.footer_default.clone());
+    let mut footer_first = prev.and_then(|p| p.footer_first.clone());
+    let mut footer_even = prev.and_then(|p| p.footer_even.clone());
+    for child in sect.iter().flat_map(|s| s.children()) {
+        if child.tag_name().namespace() != Some(WML_NS) {
+            continue;
+        }
+        let hf_type = child.attribute((WML_NS, "type")).unwrap_or("");
+        let Some(rid) = child.attribute((REL_NS, "id")) else {
+            continue;
+        };
+        match (child.tag_name().name(), hf_type) {
+            ("headerReference", "default") => header_default = resolve_hf(rid)?,
+            ("headerReference", "first") => header_first = resolve_hf(rid)?,
+            ("headerReference", "even") => header_even = resolve_hf(rid)?,
+            ("footerReference", "default") => footer_default = resolve_hf(rid)?,
+            ("footerReference", "first") => footer_first = resolve_hf(rid)?,
+            ("footerReference", "even") => footer_even = resolve_hf(rid)?,
+            _ => {}
+        }
+    }
+
+    Ok(Section {
+        first_block,
+        start,
+        page_width,
+        page_height,
+        margin_top: pg_mar.and_then(|n| twips_attr(n, "top")).unwrap_or(72.0),
+        margin_bottom: pg_mar.and_then(|n| twips_attr(n, "bottom")).unwrap_or(72.0),
+        margin_left: pg_mar.and_then(|n| twips_attr(n, "left")).unwrap_or(72.0),
+        margin_right: pg_mar.and_then(|n| twips_attr(n, "right")).unwrap_or(72.0),
+        line_pitch: doc_grid
+            .and_then(|n| twips_attr(n, "linePitch"))
+            .unwrap_or(default_line_pitch),
+        columns: cols
+            .and_then(|n| n.attribute((WML_NS, "num")))
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(1)
+            .max(1),
+        column_space: cols.and_then(|n| twips_attr(n, "space")).unwrap_or(36.0),
+        page_number_format: pg_num_type
+            .and_then(|n| n.attribute((WML_NS, "fmt")))
+            .unwrap_or("decimal")
+            .to_string(),
+        page_number_start: pg_num_type
+            .and_then(|n| n.attribute((WML_NS, "start")))
+            .and_then(|v| v.parse::<usize>().ok()),
+        page_borders: sect
+            .and_then(|s| wml(s, "pgBorders"))
+            .and_then(|b| parse_page_borders(b, theme)),
+        v_align: match sect.and_then(|s| wml_attr(s, "vAlign")) {
+            Some("center") => PageVAlign::Center,
+            Some("bottom") => PageVAlign::Bottom,
+            _ => PageVAlign::Top,
+        },
         header_default,
         header_first,
+        header_even,
         footer_default,
         footer_first,
-        header_margin,
-        footer_margin,
-        different_first_page,
+        footer_even,
+        header_margin: pg_mar.and_then(|n| twips_attr(n, "header")).unwrap_or(36.0),
+        footer_margin: pg_mar.and_then(|n| twips_attr(n, "footer")).unwrap_or(36.0),
+        different_first_page: sect.and_then(|s| wml(s, "titlePg")).is_some(),
     })
 }
 
 fn parse_list_info(
-    num_pr: Option<roxmltree::Node>,
+    num_id: Option<&str>,
+    ilvl: Option<u8>,
     numbering: &NumberingInfo,
-    counters: &mut HashMap<(String, u8), u32>,
-) -> (f32, f32, String) {
-    let Some(num_pr) = num_pr else {
-        return (0.0, 0.0, String::new());
+    counters: &mut ListCounters,
+    runs: &[Run],
+) -> (f32, f32, Option<Box<ListLabel>>) {
+    let no_list = (0.0, 0.0, None);
+    let Some(num_id) = num_id else {
+        return no_list;
     };
-    let Some(num_id) = wml_attr(num_pr, "numId") else {
-        return (0.0, 0.0, String::new());
+    let ilvl = ilvl.unwrap_or(0).min(8);
+
+    let Some(abs_id) = numbering.num_to_abstract.get(num_id) else {
+        return no_list;
     };
-    let ilvl = wml_attr(num_pr, "ilvl")
-        .and_then(|v| v.parse::<u8>().ok())
-        .unwrap_or(0);
-
-    let Some(def) = numbering
-        .num_to_abstract
-        .get(num_id)
-        .and_then(|abs_id| numbering.abstract_nums.get(abs_id))
-        .and_then(|levels| levels.get(&ilvl))
-    else {
-        return (0.0, 0.0, String::new());
+    let Some((def, start)) = numbering.level(num_id, ilvl) else {
+        return no_list;
     };
 
-    let counter = counters
-        .entry((num_id.to_string(), ilvl))
-        .and_modify(|c| *c += 1)
-        .or_insert(1);
-    let label = if def.num_fmt == "bullet" {
-        "\u{2022}".to_string()
+    let level_counters = counters.by_abstract.entry(abs_id.clone()).or_default();
+    // Overridden levels restart where this numId is first used
+    if counters.seen_nums.insert(num_id.to_string())
+        && let Some(overrides) = numbering.overrides.get(num_id)
+    {
+        for &ov_lvl in overrides.keys() {
+            if let Some(counter) = level_counters.get_mut(ov_lvl as usize) {
+                *counter = None;
+            }
+        }
+    }
+    let lvl = ilvl as usize;
+    level_counters[lvl] = Some(level_counters[lvl].map_or(start, |c| c + 1));
+    // Deeper levels restart after a shallower level is used, unless their
+    // lvlRestart says otherwise
+    for (deeper, counter) in level_counters.iter_mut().enumerate().skip(lvl + 1) {
+        let restart = numbering
+            .level(num_id, deeper as u8)
+            .and_then(|(d, _)| d.restart);
+        if restart.is_none_or(|r| r != 0 && lvl < r as usize) {
+            *counter = None;
+        }
+    }
+
+    let text = if def.num_fmt == "bullet" {
+        // Bullets in symbol fonts are private-use characters like U+F0B7
+        if def.label_props.font_name.is_some() && !def.lvl_text.is_empty() {
+            def.lvl_text.clone()
+        } else {
+            "\u{2022}".to_string()
+        }
     } else {
-        def.lvl_text
-            .replace(&format!("%{}", ilvl + 1), &counter.to_string())
+        // Each %n shows the counter of level n-1 in that level's own format
+        let mut label = def.lvl_text.clone();
+        for (i, counter) in level_counters.iter().enumerate() {
+            let placeholder = format!("%{}", i + 1);
+            if !label.contains(&placeholder) {
+                continue;
+            }
+            let level = numbering.level(num_id, i as u8);
+            let value = counter.unwrap_or_else(|| level.map_or(1, |(_, start)| start));
+            let fmt = match level {
+                Some((l, _)) if !def.is_legal => l.num_fmt.as_str(),
+                _ => "decimal",
+            };
+            label = label.replace(&placeholder, &format_number(value as usize, fmt));
+        }
+        label
+    };
+    // Without a run there is no line to put the label on
+    let Some(base) = runs.iter().find(|r| !r.hidden) else {
+        return (def.indent_left, def.indent_hanging, None);
+    };
+    let props = &def.label_props;
+    let run = Run {
+        text,
+        font_size: props.font_size.unwrap_or(base.font_size),
+        font_name: props
+            .font_name
+            .clone()
+            .unwrap_or_else(|| base.font_name.clone()),
+        bold: props.bold.unwrap_or(base.bold),
+        italic: props.italic.unwrap_or(base.italic),
+        underline: UnderlineStyle::None,
+        underline_color: None,
+        strikethrough: false,
+        color: props.color.or(base.color),
+        is_tab: false,
+        is_line_break: false,
+        is_page_break: false,
+        vertical_align: VertAlign::Baseline,
+        field_code: None,
+        caps: false,
+        small_caps: false,
+        hidden: false,
+        link: None,
+        char_spacing: 0.0,
+        scale: 1.0,
+        kerning: false,
+    };
+    let label = ListLabel {
+        run,
+        alignment: def.jc,
+        suffix: def.suffix,
     };
-    (def.indent_left, def.indent_hanging, label)
+    (def.indent_left, def.indent_hanging, Some(Box::new(label)))
 }
 
 const REL_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
 
-fn parse_relationships(zip: &mut zip::ZipArchive<std::fs::File>) -> HashMap<String, String> {
-    let mut rels = HashMap::new();
-    let mut xml_content = String::new();
-    let Ok(mut file) = zip.by_name("word/_rels/document.xml.rels") else {
-        return rels;
-    };
-    if file.read_to_string(&mut xml_content).is_err() {
-        return rels;
-    }
-    let Ok(xml) = roxmltree::Document::parse(&xml_content) else {
-        return rels;
-    };
-    for node in xml.root_element().children() {
-        if node.tag_name().name() == "Relationship"
-            && let (Some(id), Some(target)) = (node.attribute("Id"), node.attribute("Target"))
-        {
-            rels.insert(id.to_string(), target.to_string());
+/// The relationships of every part, and the image and font parts they point
+/// at. Both are read from the archive up front, the parts in one pass in
+/// archive order, so that parsing looks them up instead of decompressing
+/// each picture or font where the document refers to it.
+struct Package {
+    rels: HashMap<String, HashMap<String, String>>, // owner part -> relationship ID -> target
+    parts: HashMap<String, Vec<u8>>,                // image and font parts, by path
+}
+
+impl Package {
+    fn read<R: Read + Seek>(zip: &mut zip::ZipArchive<R>) -> Result<Self, Error> {
+        let rels_paths: Vec<String> = zip
+            .file_names()
+            .filter(|name| name.ends_with(".rels"))
+            .map(str::to_string)
+            .collect();
+        let mut rels = HashMap::new();
+        let mut wanted: HashSet<String> = HashSet::new();
+        for rels_path in rels_paths {
+            let Some(owner) = rels_owner(&rels_path) else {
+                continue;
+            };
+            let Some(xml_content) = read_zip_text(zip, &rels_path)? else {
+                continue;
+            };
+            let xml = parse_part(&rels_path, &xml_content)?;
+            let dir = owner.rsplit_once('/').map_or("", |(dir, _)| dir);
+            let mut targets = HashMap::new();
+            for node in xml.root_element().children() {
+                if node.tag_name().name() != "Relationship" {
+                    continue;
+                }
+                let (Some(id), Some(target)) = (node.attribute("Id"), node.attribute("Target"))
+                else {
+                    continue;
+                };
+                let kind = node.attribute("Type").unwrap_or("");
+                if node.attribute("TargetMode") != Some("External")
+                    && (kind.ends_with("/image") || kind.ends_with("/font"))
+                {
+                    wanted.insert(resolve_part_path(dir, target));
+                }
+                targets.insert(id.to_string(), target.to_string());
+            }
+            rels.insert(owner, targets);
+        }
+
+        let mut parts = HashMap::new();
+        for i in 0..zip.len() {
+            if !zip
+                .name_for_index(i)
+                .is_some_and(|name| wanted.contains(name))
+            {
+                continue;
+            }
+            let Ok(mut entry) = zip.by_index(i) else {
+                continue;
+            };
+            let mut data = Vec::new();
+            if entry.read_to_end(&mut data).is_ok() {
+                parts.insert(entry.name().to_string(), data);
+            }
         }
+        Ok(Package { rels, parts })
+    }
+
+    /// Relationship ID -> target for the part at `owner`, such as
+    /// "word/document.xml"; empty if it has no relationships.
+    fn rels(&self, owner: &str) -> HashMap<String, String> {
+        self.rels.get(owner).cloned().unwrap_or_default()
+    }
+
+    /// An image or font part some relationship points at.
+    fn part(&self, path: &str) -> Option<&[u8]> {
+        self.parts.get(path).map(Vec::as_slice)
     }
-    rels
+}
+
+/// The part whose relationships a `.rels` part holds: "word/document.xml" for
+/// "word/_rels/document.xml.rels", and "" for the package's own "_rels/.rels".
+fn rels_owner(rels_path: &str) -> Option<String> {
+    let (dir, file) = rels_path.rsplit_once("_rels/")?;
+    Some(format!("{dir}{}", file.strip_suffix(".rels")?))
 }
 
 fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
@@ -1453,71 +3808,279 @@ fn find_blip_embed<'a>(container: roxmltree::Node<'a, 'a>) -> Option<&'a str> {
         .and_then(|n| n.attribute((REL_NS, "embed")))
 }
 
+/// Alternative text of a DrawingML object, from the `descr` of its `wp:docPr`.
+fn drawing_alt_text(container: roxmltree::Node) -> Option<String> {
+    container
+        .children()
+        .find(|n| n.tag_name().name() == "docPr" && n.tag_name().namespace() == Some(WPD_NS))
+        .and_then(|n| n.attribute("descr"))
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+}
+
 struct DrawingInfo {
     height: f32,
     image: Option<EmbeddedImage>,
 }
 
+/// A JPEG or PNG image part; `target` is the target of relationship `rel_id`,
+/// relative to `word/`.
+fn load_image(
+    (rel_id, target): (&str, &str),
+    package: &Package,
+    display_width: f32,
+    display_height: f32,
+    warnings: &mut Vec<Warning>,
+) -> Option<EmbeddedImage> {
+    let zip_path = resolve_part_path("word", target);
+    let Some(data) = package.part(&zip_path) else {
+        warnings.push(Warning::MissingImagePart {
+            rel_id: rel_id.to_string(),
+        });
+        return None;
+    };
+    let jpeg = jpeg_dimensions(data).map(|size| (size, ImageFormat::Jpeg));
+    let png = || png::dimensions(data).map(|size| (size, ImageFormat::Png));
+    let Some(((pixel_width, pixel_height), format)) = jpeg.or_else(png) else {
+        log::warn!("Image {zip_path} is neither a JPEG nor a PNG we can embed");
+        warnings.push(Warning::UnsupportedImageFormat { part: zip_path });
+        return None;
+    };
+    Some(EmbeddedImage {
+        data: data.to_vec(),
+        format,
+        pixel_width,
+        pixel_height,
+        display_width,
+        display_height,
+        alt_text: None,
+    })
+}
+
+/// The branch of an `mc:AlternateContent` to read: the `mc:Choice` when it holds
+/// a DrawingML picture or text box, otherwise the (usually VML) `mc:Fallback`.
+fn alternate_content_branch<'a>(ac: roxmltree::Node<'a, 'a>) -> Option<roxmltree::Node<'a, 'a>> {
+    let is_mc = |n: &roxmltree::Node, name: &str| {
+        n.tag_name().name() == name && n.tag_name().namespace() == Some(MC_NS)
+    };
+    let understood = |n: &roxmltree::Node| {
+        n.descendants().any(|d| {
+            (d.tag_name().name() == "blip" && d.tag_name().namespace() == Some(DML_NS))
+                || is_wml_element(&d, "txbxContent")
+        })
+    };
+    ac.children()
+        .find(|n| is_mc(n, "Choice") && understood(n))
+        .or_else(|| ac.children().find(|n| is_mc(n, "Fallback")))
+}
+
+/// Children of a run, with `mc:AlternateContent` replaced by the children of
+/// the branch we read.
+fn run_objects<'a>(run: roxmltree::Node<'a, 'a>) -> Vec<roxmltree::Node<'a, 'a>> {
+    let mut objects = Vec::new();
+    for child in run.children() {
+        if child.tag_name().name() == "AlternateContent"
+            && child.tag_name().namespace() == Some(MC_NS)
+        {
+            objects.extend(
+                alternate_content_branch(child)
+                    .into_iter()
+                    .flat_map(|b| b.children()),
+            );
+        } else {
+            objects.push(child);
+        }
+    }
+    objects
+}
+
+/// Resolve a relationship target against the folder of the part that owns it,
+/// e.g. "../media/image1.png" from "word/charts" to "word/media/image1.png".
+fn resolve_part_path(dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            _ => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+/// The picture cached for a chart (`c:chart`): an image part related to the
+/// chart part, preferring formats we can embed.
+fn chart_image(
+    chart_target: &str,
+    package: &Package,
+    display_width: f32,
+    display_height: f32,
+    warnings: &mut Vec<Warning>,
+) -> Option<EmbeddedImage> {
+    let chart_path = resolve_part_path("word", chart_target);
+    let (dir, _) = chart_path.rsplit_once('/')?;
+    let chart_rels = package.rels(&chart_path);
+    let mut images: Vec<(&str, String)> = chart_rels
+        .iter()
+        .filter(|(_, t)| {
+            let ext = t.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+            matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "emf" | "wmf")
+        })
+        .map(|(id, t)| (id.as_str(), resolve_part_path(dir, t)))
+        .collect();
+    images.sort_by_key(|(_, p)| {
+        let ext = p.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+        (!matches!(ext.as_str(), "jpg" | "jpeg" | "png"), p.clone())
+    });
+    let Some((rel_id, path)) = images.first() else {
+        log::warn!("Chart {chart_path} has no cached picture; drawing a placeholder");
+        warnings.push(Warning::ChartWithoutPicture { part: chart_path });
+        return None;
+    };
+    let target = format!("/{path}");
+    load_image(
+        (rel_id, &target),
+        package,
+        display_width,
+        display_height,
+        warnings,
+    )
+}
+
+/// A length from a VML `style` attribute (e.g. "width:1.5in"), in points.
+fn vml_style_length(style: &str, property: &str) -> Option<f32> {
+    let value = style.split(';').find_map(|decl| {
+        let (name, value) = decl.split_once(':')?;
+        (name.trim() == property).then(|| value.trim())
+    })?;
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f32 = number.parse().ok()?;
+    Some(match unit {
+        "in" => number * 72.0,
+        "cm" => number * 72.0 / 2.54,
+        "mm" => number * 72.0 / 25.4,
+        "px" => number * 0.75,
+        "emu" => number / 12700.0,
+        _ => number, // pt, or no unit
+    })
+}
+
 fn compute_drawing_info(
     para_node: roxmltree::Node,
     rels: &HashMap<String, String>,
-    zip: &mut zip::ZipArchive<std::fs::File>,
+    package: &Package,
+    warnings: &mut Vec<Warning>,
 ) -> DrawingInfo {
     let mut max_height: f32 = 0.0;
     let mut image: Option<EmbeddedImage> = None;
 
-    for child in para_node.children() {
+    for child in content_children(para_node) {
         let is_wml = child.tag_name().namespace() == Some(WML_NS);
-        let drawing_node = match child.tag_name().name() {
-            "drawing" if is_wml => Some(child),
-            "r" if is_wml => wml(child, "drawing"),
-            _ => None,
+        let objects = match child.tag_name().name() {
+            "drawing" if is_wml => vec![child],
+            "r" if is_wml => run_objects(child),
+            _ => continue,
         };
 
-        let Some(drawing) = drawing_node else {
-            continue;
-        };
-        for container in drawing.children() {
-            let name = container.tag_name().name();
-            if (name == "inline" || name == "anchor")
-                && container.tag_name().namespace() == Some(WPD_NS)
-            {
-                let extent = container.children().find(|n| {
-                    n.tag_name().name() == "extent" && n.tag_name().namespace() == Some(WPD_NS)
-                });
-                let cx = extent
-                    .and_then(|n| n.attribute("cx"))
-                    .and_then(|v| v.parse::<f32>().ok())
-                    .unwrap_or(0.0);
-                let cy = extent
-                    .and_then(|n| n.attribute("cy"))
-                    .and_then(|v| v.parse::<f32>().ok())
-                    .unwrap_or(0.0);
-                let display_w = cx / 12700.0;
-                let display_h = cy / 12700.0;
-                max_height = max_height.max(display_h);
-
-                if image.is_none()
-                    && let Some(embed_id) = find_blip_embed(container)
-                    && let Some(target) = rels.get(embed_id)
+        for object in objects {
+            // Legacy VML picture, e.g. the fallback of an mc:AlternateContent,
+            // or the cached picture of an embedded OLE object
+            if is_wml_element(&object, "pict") || is_wml_element(&object, "object") {
+                let Some(shape) = object.children().find(|n| {
+                    n.tag_name().namespace() == Some(VML_NS)
+                        && matches!(n.tag_name().name(), "shape" | "rect")
+                }) else {
+                    continue;
+                };
+                let style = shape.attribute("style").unwrap_or("");
+                let display_w = vml_style_length(style, "width").unwrap_or(0.0);
+                let display_h = vml_style_length(style, "height").unwrap_or(0.0);
+                let rel_id = shape
+                    .children()
+                    .find(|n| {
+                        n.tag_name().name() == "imagedata"
+                            && n.tag_name().namespace() == Some(VML_NS)
+                    })
+                    .and_then(|n| n.attribute((REL_NS, "id")));
+                if let Some(rel_id) = rel_id {
+                    let Some(target) = rels.get(rel_id) else {
+                        warnings.push(Warning::MissingImagePart {
+                            rel_id: rel_id.to_string(),
+                        });
+                        continue;
+                    };
+                    max_height = max_height.max(display_h);
+                    if image.is_none() {
+                        image =
+                            load_image((rel_id, target), package, display_w, display_h, warnings)
+                                .map(|img| EmbeddedImage {
+                                    alt_text: shape.attribute("alt").map(str::to_string),
+                                    ..img
+                                });
+                    }
+                }
+                continue;
+            }
+            if !is_wml_element(&object, "drawing") {
+                continue;
+            }
+            for container in object.children() {
+                let name = container.tag_name().name();
+                // Text boxes float over the text instead of taking up space
+                if (name == "inline" || name == "anchor")
+                    && container.tag_name().namespace() == Some(WPD_NS)
+                    && !container
+                        .descendants()
+                        .any(|n| is_wml_element(&n, "txbxContent"))
                 {
-                    let zip_path = target
-                        .strip_prefix('/')
-                        .map(String::from)
-                        .unwrap_or_else(|| format!("word/{}", target));
-                    if let Ok(mut entry) = zip.by_name(&zip_path) {
-                        let mut data = Vec::new();
-                        if entry.read_to_end(&mut data).is_ok()
-                            && let Some((pw, ph)) = jpeg_dimensions(&data)
-                        {
-                            image = Some(EmbeddedImage {
-                                data,
-                                pixel_width: pw,
-                                pixel_height: ph,
-                                display_width: display_w,
-                                display_height: display_h,
+                    let extent = container.children().find(|n| {
+                        n.tag_name().name() == "extent" && n.tag_name().namespace() == Some(WPD_NS)
+                    });
+                    let cx = extent
+                        .and_then(|n| n.attribute("cx"))
+                        .and_then(|v| v.parse::<f32>().ok())
+                        .unwrap_or(0.0);
+                    let cy = extent
+                        .and_then(|n| n.attribute("cy"))
+                        .and_then(|v| v.parse::<f32>().ok())
+                        .unwrap_or(0.0);
+                    let display_w = cx / 12700.0;
+                    let display_h = cy / 12700.0;
+                    max_height = max_height.max(display_h);
+                    let with_alt_text = |img: EmbeddedImage| EmbeddedImage {
+                        alt_text: drawing_alt_text(container),
+                        ..img
+                    };
+
+                    if image.is_none()
+                        && let Some(embed_id) = find_blip_embed(container)
+                    {
+                        let Some(target) = rels.get(embed_id) else {
+                            warnings.push(Warning::MissingImagePart {
+                                rel_id: embed_id.to_string(),
                             });
-                        }
+                            continue;
+                        };
+                        image =
+                            load_image((embed_id, target), package, display_w, display_h, warnings)
+                                .map(with_alt_text);
+                    } else if image.is_none()
+                        && let Some(target) = container
+                            .descendants()
+                            .find(|n| n.tag_name().name() == "chart")
+                            .and_then(|n| n.attribute((REL_NS, "id")))
+                            .and_then(|id| rels.get(id))
+                    {
+                        image = chart_image(target, package, display_w, display_h, warnings)
+                            .map(with_alt_text);
                     }
                 }
             }
@@ -4,34 +4,123 @@ use std::fmt;
 pub enum Error {
     InvalidDocx(String),
     Zip(zip::result::ZipError),
-    Xml(roxmltree::Error),
+    /// A part of the archive, such as `word/document.xml`, that could not be
+    /// read or decompressed.
+    Part {
+        part: String,
+        source: std::io::Error,
+    },
+    /// A part that is not well-formed XML. The source error gives the line
+    /// and column.
+    Xml {
+        part: String,
+        source: roxmltree::Error,
+    },
     Pdf(String),
     Io(std::io::Error),
 }
 
+impl Error {
+    /// Path within the archive of the part the error is about, if any.
+    pub fn part(&self) -> Option<&str> {
+        match self {
+            Error::Part { part, .. } | Error::Xml { part, .. } => Some(part),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::InvalidDocx(reason) => write!(f, "not a valid DOCX file: {reason}"),
             Error::Zip(e) => write!(f, "ZIP error: {e}"),
-            Error::Xml(e) => write!(f, "XML error: {e}"),
+            Error::Part { part, source } => write!(f, "could not read {part}: {source}"),
+            Error::Xml { part, source } => write!(f, "malformed XML in {part}: {source}"),
             Error::Pdf(e) => write!(f, "PDF error: {e}"),
             Error::Io(e) => write!(f, "IO error: {e}"),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Zip(e) => Some(e),
+            Error::Part { source, .. } => Some(source),
+            Error::Xml { source, .. } => Some(source),
+            Error::Io(e) => Some(e),
+            Error::InvalidDocx(_) | Error::Pdf(_) => None,
+        }
+    }
+}
 
-impl From<zip::result::ZipError> for Error {
-    fn from(e: zip::result::ZipError) -> Self {
-        Error::Zip(e)
+/// Something a conversion could not reproduce faithfully, without failing.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A font that is neither embedded in the document nor installed; text in
+    /// it is set in Helvetica, or a substitute font for PDF/A.
+    MissingFont {
+        name: String,
+        bold: bool,
+        italic: bool,
+    },
+    /// Body elements that are not rendered, such as `altChunk`, with the
+    /// number of times each occurs.
+    UnsupportedElement { tag: String, count: usize },
+    /// Characters no font could show, drawn as '?'.
+    DroppedCharacters { chars: Vec<char> },
+    /// A picture whose relationship, or the part it points at, is missing.
+    MissingImagePart { rel_id: String },
+    /// A picture in a format that is not embedded (only JPEG and PNG are).
+    UnsupportedImageFormat { part: String },
+    /// A chart without a cached picture, drawn as a placeholder.
+    ChartWithoutPicture { part: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::MissingFont { name, bold, italic } => {
+                let style = match (bold, italic) {
+                    (false, false) => "",
+                    (true, false) => " bold",
+                    (false, true) => " italic",
+                    (true, true) => " bold italic",
+                };
+                write!(f, "font not found: {name}{style}")
+            }
+            Warning::UnsupportedElement { tag, count } => {
+                write!(f, "unsupported element w:{tag} skipped ({count}x)")
+            }
+            Warning::DroppedCharacters { chars } => {
+                let chars: Vec<String> = chars
+                    .iter()
+                    .map(|c| format!("{c} (U+{:04X})", *c as u32))
+                    .collect();
+                write!(
+                    f,
+                    "characters missing from every font: {}",
+                    chars.join(", ")
+                )
+            }
+            Warning::MissingImagePart { rel_id } => {
+                write!(f, "image part of relationship {rel_id} is missing")
+            }
+            Warning::UnsupportedImageFormat { part } => {
+                write!(f, "image {part} is not a JPEG or PNG and was left out")
+            }
+            Warning::ChartWithoutPicture { part } => {
+                write!(f, "chart {part} has no cached picture; drew a placeholder")
+            }
+        }
     }
 }
 
-impl From<roxmltree::Error> for Error {
-    fn from(e: roxmltree::Error) -> Self {
-        Error::Xml(e)
+impl From<zip::result::ZipError> for Error {
+    fn from(e: zip::result::ZipError) -> Self {
+        Error::Zip(e)
     }
 }
 
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// First line of the cache file. Bump the number when the format or what
+/// goes into it changes, so that older caches are rescanned.
+const HEADER: &str = "docxside-pdf font index 1";
+
+/// A face of a font file: its index within a collection, family and style.
+#[derive(Clone)]
+pub(crate) struct CachedFace {
+    pub(crate) index: u32,
+    pub(crate) family: String,
+    pub(crate) bold: bool,
+    pub(crate) italic: bool,
+}
+
+/// The faces of a font file, valid while its size and modification time
+/// stay the same.
+pub(crate) struct CachedFile {
+    pub(crate) size: u64,
+    pub(crate) modified: u128,
+    pub(crate) faces: Vec<CachedFace>,
+}
+
+#[derive(Clone)]
+pub(crate) enum Entry {
+    Dir(PathBuf),
+    Font(PathBuf),
+}
+
+/// The subdirectories and font files of a directory, sorted, valid while the
+/// directory's modification time stays the same.
+pub(crate) struct CachedDir {
+    pub(crate) modified: u128,
+    pub(crate) entries: Vec<Entry>,
+}
+
+/// What a scan of the font directories found, kept between runs so that only
+/// directories and files that changed are read again.
+#[derive(Default)]
+pub(crate) struct FontCache {
+    pub(crate) dirs: HashMap<PathBuf, CachedDir>,
+    pub(crate) files: HashMap<PathBuf, CachedFile>,
+}
+
+/// Modification time in nanoseconds since the epoch.
+pub(crate) fn modified(meta: &Metadata) -> Option<u128> {
+    let time = meta.modified().ok()?;
+    Some(time.duration_since(std::time::UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// Where the cache lives: `DOCXSIDE_FONT_CACHE` if set ("off" disables the
+/// cache), otherwise under the platform's cache directory.
+pub(crate) fn location() -> Option<PathBuf> {
+    if let Ok(val) = std::env::var("DOCXSIDE_FONT_CACHE") {
+        let val = val.trim();
+        return (!val.is_empty() && !val.eq_ignore_ascii_case("off")).then(|| val.into());
+    }
+    let env_dir = |name: &str| {
+        std::env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let cache_dir = if cfg!(target_os = "windows") {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        env_dir("HOME").map(|home| home.join("Library/Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+    };
+    Some(cache_dir?.join("docxside-pdf").join("font-index"))
+}
+
+impl FontCache {
+    /// Read the cache at `path`; empty if it is missing, from another
+    /// version or unreadable.
+    pub(crate) fn load(path: &Path) -> Self {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        Self::parse(&text).unwrap_or_else(|| {
+            log::info!("Ignoring font cache {}: unknown format", path.display());
+            Self::default()
+        })
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()? != HEADER {
+            return None;
+        }
+        let mut cache = Self::default();
+        let mut dir: Option<&mut CachedDir> = None;
+        let mut file: Option<&mut CachedFile> = None;
+        for line in lines {
+            let (kind, rest) = line.split_once('\t')?;
+            match kind {
+                "dir" => {
+                    let (modified, path) = rest.split_once('\t')?;
+                    let entry = cache.dirs.entry(path.into()).or_insert(CachedDir {
+                        modified: modified.parse().ok()?,
+                        entries: Vec::new(),
+                    });
+                    dir = Some(entry);
+                }
+                "sub" => dir.as_mut()?.entries.push(Entry::Dir(rest.into())),
+                "font" => {
+                    let mut fields = rest.splitn(3, '\t');
+                    let size = fields.next()?.parse().ok()?;
+                    let modified = fields.next()?.parse().ok()?;
+                    let path = PathBuf::from(fields.next()?);
+                    dir.as_mut()?.entries.push(Entry::Font(path.clone()));
+                    file = Some(cache.files.entry(path).or_insert(CachedFile {
+                        size,
+                        modified,
+                        faces: Vec::new(),
+                    }));
+                }
+                "face" => {
+                    let mut fields = rest.splitn(4, '\t');
+                    let index = fields.next()?.parse().ok()?;
+                    let bold = fields.next()? == "1";
+                    let italic = fields.next()? == "1";
+                    file.as_mut()?.faces.push(CachedFace {
+                        index,
+                        family: fields.next()?.to_string(),
+                        bold,
+                        italic,
+                    });
+                }
+                _ => return None,
+            }
+        }
+        Some(cache)
+    }
+
+    /// Write the cache to `path`, through a temporary file so that processes
+    /// reading it concurrently never see half of it. Paths that are not
+    /// UTF-8 are left out, to be scanned again next time.
+    pub(crate) fn save(&self, path: &Path) {
+        let mut text = format!("{HEADER}\n");
+        let mut dirs: Vec<(&str, &CachedDir)> = self
+            .dirs
+            .iter()
+            .filter_map(|(path, dir)| Some((printable(path)?, dir)))
+            .collect();
+        dirs.sort_by_key(|&(path, _)| path);
+        for (dir_path, dir) in dirs {
+            text += &format!("dir\t{}\t{dir_path}\n", dir.modified);
+            for entry in &dir.entries {
+                match entry {
+                    Entry::Dir(sub) => {
+                        if let Some(sub) = printable(sub) {
+                            text += &format!("sub\t{sub}\n");
+                        }
+                    }
+                    Entry::Font(font) => {
+                        let (Some(name), Some(file)) = (printable(font), self.files.get(font))
+                        else {
+                            continue;
+                        };
+                        text += &format!("font\t{}\t{}\t{name}\n", file.size, file.modified);
+                        for face in &file.faces {
+                            text += &format!(
+                                "face\t{}\t{}\t{}\t{}\n",
+                                face.index,
+                                face.bold as u8,
+                                face.italic as u8,
+                                face.family.replace(['\t', '\n'], " ")
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let Some(parent) = path.parent() else {
+            return;
+        };
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        let written = std::fs::create_dir_all(parent)
+            .and_then(|_| std::fs::File::create(&tmp))
+            .and_then(|mut f| f.write_all(text.as_bytes()))
+            .and_then(|_| std::fs::rename(&tmp, path));
+        if let Err(e) = written {
+            log::info!("Could not write font cache {}: {e}", path.display());
+            let _ = std::fs::remove_file(&tmp);
+        }
+    }
+}
+
+/// A path as it can be written on one line of the cache.
+fn printable(path: &Path) -> Option<&str> {
+    path.to_str().filter(|p| !p.contains(['\t', '\n']))
+}
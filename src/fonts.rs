@@ -1,18 +1,250 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
 
-use pdf_writer::{Name, Pdf, Rect, Ref};
-use ttf_parser::Face;
+use pdf_writer::types::{CidFontType, SystemInfo, UnicodeCmap};
+use pdf_writer::{Filter, Name, Pdf, Rect, Ref, Str};
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::{Face, GlyphId, Tag};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
 
-use crate::model::Run;
+use crate::error::Error;
+use crate::font_cache::{self, CachedDir, CachedFace, CachedFile, Entry, FontCache};
+use crate::model::{Block, Document, HeaderFooter, Paragraph, Run};
+use crate::pdf::deflate;
+use crate::sfnt::{self, StyleTables};
 
 pub(crate) struct FontEntry {
     pub(crate) pdf_name: String,
     pub(crate) font_ref: Ref,
-    pub(crate) widths_1000: Vec<f32>,
+    widths_1000: Vec<f32>,           // WinAnsi codes 32..=255
+    kerning: HashMap<(u8, u8), f32>, // pair adjustments between WinAnsi codes, at 1000 units/em
+    composite: Option<CompositeFont>,
+    font_file: Option<FontFile>,
+    words: RefCell<EncodedWords>,
+    pub(crate) missing: bool, // neither embedded nor installed, so another font stands in
+    pub(crate) synthetic: Synthetic,
     pub(crate) line_h_ratio: Option<f32>,
     pub(crate) ascender_ratio: Option<f32>,
+    pub(crate) underline_position: Option<f32>, // top of the underline from the baseline, in em
+    pub(crate) underline_thickness: Option<f32>, // in em
+    pub(crate) strikeout_position: Option<f32>, // top of the strikeout from the baseline, in em
+    pub(crate) strikeout_thickness: Option<f32>, // in em
+}
+
+/// Styles faked at render time because the face found lacks them: bold is
+/// drawn with a stroked outline and italic with a skewed text matrix.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) struct Synthetic {
+    pub(crate) bold: bool,
+    pub(crate) italic: bool,
+}
+
+/// Advances of synthesized bold glyphs grow by this factor, as the stroke
+/// thickens them.
+const SYNTHETIC_BOLD_ADVANCE: f32 = 1.02;
+
+/// The glyphs of an embedded face for text WinAnsi can't encode, drawn through
+/// a Type0 font with Identity-H encoding so that the codes are glyph IDs.
+/// Layout records the glyphs it uses; the font is written once it is done.
+struct CompositeFont {
+    pdf_name: String,
+    base_font: String,
+    descriptor_ref: Ref,
+    glyphs: HashMap<char, (u16, f32)>, // glyph ID (or CID), advance at 1000 units/em
+    used: RefCell<BTreeMap<u16, (char, f32)>>,
+    // Glyph IDs of a CID-keyed CFF face, whose codes are CIDs. Its glyphs
+    // have no names for the simple font to use, so all text is drawn here.
+    gids_by_cid: Option<HashMap<u16, u16>>,
+}
+
+/// Outline format of an embedded face, which decides how its font file and
+/// fonts are written.
+#[derive(Clone, Copy, PartialEq)]
+enum Outlines {
+    TrueType, // FontFile2, with TrueType and CIDFontType2 fonts
+    Cff,      // FontFile3 of subtype Type1C or CIDFontType0C, with Type1 and CIDFontType0 fonts
+}
+
+/// The face behind an embedded font, kept until layout is done so that only
+/// the glyphs it drew are written.
+struct FontFile {
+    data_ref: Ref,
+    data: Vec<u8>,
+    face_index: u32,
+    outlines: Outlines,
+    used_codes: RefCell<BTreeSet<u8>>, // WinAnsi codes shown through the simple font
+    to_unicode_ref: Ref, // ToUnicode CMap of the simple font, written with the font file
+    symbolic: bool,      // codes are symbol font (w:sym) codes rather than WinAnsi
+}
+
+/// Words a font entry has encoded, by text. Layout measures the same words
+/// many times over, when breaking lines, fitting table columns and reflowing
+/// across pages, and a long document uses few distinct ones; each is encoded
+/// once and shared by every measurement and the chunks that draw it.
+#[derive(Default)]
+struct EncodedWords {
+    plain: HashMap<String, Rc<EncodedText>>,
+    kerned: HashMap<String, Rc<EncodedText>>,
+}
+
+/// Text encoded for one of a font entry's two PDF fonts.
+pub(crate) struct EncodedText {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) composite: bool, // two-byte glyph IDs for the Type0 font
+    pub(crate) width_1000: f32,
+    pub(crate) glyph_count: usize,
+    pub(crate) kerning: Vec<(usize, f32)>, // (byte offset, adjustment at 1000 units/em) before that glyph
+}
+
+impl FontEntry {
+    /// Advance of a space at 1000 units/em.
+    pub(crate) fn space_width(&self) -> f32 {
+        self.widths_1000[0]
+    }
+
+    /// Resource name of the simple font, or of the composite font for text
+    /// encoded as glyph IDs.
+    pub(crate) fn pdf_font(&self, composite: bool) -> &str {
+        match &self.composite {
+            Some(cf) if composite => &cf.pdf_name,
+            _ => &self.pdf_name,
+        }
+    }
+
+    /// Encode `text` for both measuring and drawing. Text WinAnsi covers uses
+    /// the simple font; anything else the composite font, if the face has one.
+    pub(crate) fn encode(&self, text: &str) -> Rc<EncodedText> {
+        self.encode_memo(text, false)
+    }
+
+    /// Encode `text` like `encode`, with the face's kerning applied between
+    /// pairs of WinAnsi codes.
+    pub(crate) fn encode_kerned(&self, text: &str) -> Rc<EncodedText> {
+        self.encode_memo(text, true)
+    }
+
+    /// The encoding of `text` from an earlier call, or a new one. Reusing it
+    /// is safe because encoding only ever adds to the glyphs a font uses.
+    fn encode_memo(&self, text: &str, kerned: bool) -> Rc<EncodedText> {
+        let mut words = self.words.borrow_mut();
+        let words = if kerned {
+            &mut words.kerned
+        } else {
+            &mut words.plain
+        };
+        if let Some(encoded) = words.get(text) {
+            return Rc::clone(encoded);
+        }
+        let encoded = Rc::new(if kerned {
+            self.encode_text_kerned(text)
+        } else {
+            self.encode_text(text)
+        });
+        words.insert(text.to_string(), Rc::clone(&encoded));
+        encoded
+    }
+
+    pub(crate) fn encode_text(&self, text: &str) -> EncodedText {
+        if let Some(cf) = &self.composite
+            && (cf.gids_by_cid.is_some() || !text.chars().all(|c| winansi_byte(c).is_some()))
+        {
+            return cf.encode(text);
+        }
+        let bytes = to_winansi_bytes(text);
+        if let Some(file) = &self.font_file {
+            file.used_codes.borrow_mut().extend(bytes.iter().copied());
+        }
+        let width_1000 = bytes
+            .iter()
+            .filter(|&&b| b >= 32)
+            .map(|&b| self.widths_1000[(b - 32) as usize])
+            .sum();
+        EncodedText {
+            glyph_count: bytes.len(),
+            bytes,
+            composite: false,
+            width_1000,
+            kerning: Vec::new(),
+        }
+    }
+
+    fn encode_text_kerned(&self, text: &str) -> EncodedText {
+        let mut encoded = self.encode_text(text);
+        if !encoded.composite && !self.kerning.is_empty() {
+            for (i, pair) in encoded.bytes.windows(2).enumerate() {
+                if let Some(&adjust) = self.kerning.get(&(pair[0], pair[1])) {
+                    encoded.kerning.push((i + 1, adjust));
+                    encoded.width_1000 += adjust;
+                }
+            }
+        }
+        encoded
+    }
+
+    /// Characters of `text` that this font can only show as '?'.
+    pub(crate) fn unmappable_chars<'a>(&'a self, text: &'a str) -> impl Iterator<Item = char> + 'a {
+        text.chars().filter(move |&c| match &self.composite {
+            Some(cf) => {
+                !c.is_control()
+                    && !cf.glyphs.contains_key(&c)
+                    && substitute(c, |d| cf.glyphs.contains_key(&d)).is_none()
+            }
+            None => {
+                winansi_byte(c).is_none() && substitute(c, |d| winansi_byte(d).is_some()).is_none()
+            }
+        })
+    }
+}
+
+impl CompositeFont {
+    fn encode(&self, text: &str) -> EncodedText {
+        let mut used = self.used.borrow_mut();
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        let mut width_1000 = 0.0;
+        let mut push = |c: char| {
+            if let Some(&(gid, w)) = self.glyphs.get(&c) {
+                used.entry(gid).or_insert((c, w));
+                bytes.extend(gid.to_be_bytes());
+                width_1000 += w;
+            }
+        };
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.glyphs.contains_key(&c) {
+                push(c);
+            } else if let Some(sub) = substitute(c, |d| self.glyphs.contains_key(&d)) {
+                sub.chars().for_each(&mut push);
+            } else {
+                push('?');
+            }
+        }
+        EncodedText {
+            glyph_count: bytes.len() / 2,
+            bytes,
+            composite: true,
+            width_1000,
+            kerning: Vec::new(),
+        }
+    }
+}
+
+/// What layout needs from an embedded TrueType face; ratios are of the em.
+struct FaceMetrics {
+    widths: Vec<f32>,
+    kerning: HashMap<(u8, u8), f32>,
+    synthetic: Synthetic,
+    glyphs: Option<HashMap<char, (u16, f32)>>, // None for symbol fonts
+    gids_by_cid: Option<HashMap<u16, u16>>,
+    outlines: Outlines,
+    line_h_ratio: f32,
+    ascender_ratio: f32,
+    underline: Option<(f32, f32)>, // post table underlinePosition, underlineThickness
+    strikeout: Option<(f32, f32)>, // OS/2 yStrikeoutPosition, yStrikeoutSize
 }
 
 /// (lowercase family name, bold, italic) -> (file path, face index within TTC)
@@ -35,12 +267,53 @@ fn font_family_name(face: &Face) -> Option<String> {
     None
 }
 
-fn read_font_style(data: &[u8], face_index: u32) -> Option<(String, bool, bool)> {
-    let face = Face::parse(data, face_index).ok()?;
-    let family = font_family_name(&face)?;
+fn face_style(face: &Face) -> Option<(String, bool, bool)> {
+    let family = font_family_name(face)?;
     Some((family, face.is_bold(), face.is_italic()))
 }
 
+pub(crate) fn read_font_style(data: &[u8], face_index: u32) -> Option<(String, bool, bool)> {
+    face_style(&Face::parse(data, face_index).ok()?)
+}
+
+/// Family and style of each face of a font file, read from just the tables
+/// they come from. Faces those tables don't describe are read again from
+/// the whole file, so the result is always that of `read_font_style`.
+pub(crate) fn read_file_faces(path: &Path) -> Option<Vec<CachedFace>> {
+    let mut file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+    let is_collection = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("ttc"));
+    let face_count = if is_collection {
+        sfnt::face_count(&mut file)
+    } else {
+        1
+    };
+    let mut data: Option<Vec<u8>> = None;
+    let mut faces = Vec::new();
+    for index in 0..face_count {
+        let tables = StyleTables::read(&mut file, index);
+        let style = match tables.as_ref().and_then(|t| t.face()) {
+            Some(face) => face_style(&face),
+            None => {
+                if data.is_none() {
+                    data = Some(std::fs::read(path).ok()?);
+                }
+                read_font_style(data.as_deref()?, index)
+            }
+        };
+        if let Some((family, bold, italic)) = style {
+            faces.push(CachedFace {
+                index,
+                family,
+                bold,
+                italic,
+            });
+        }
+    }
+    Some(faces)
+}
+
 fn font_directories() -> Vec<PathBuf> {
     let mut dirs: Vec<PathBuf> = Vec::new();
 
@@ -101,51 +374,135 @@ fn font_directories() -> Vec<PathBuf> {
     dirs
 }
 
+/// Index the installed fonts, reading only the directories and font files
+/// that changed since the cached scan, if there is one.
 fn scan_font_dirs() -> FontLookup {
+    index_font_dirs(font_directories(), font_cache::location())
+}
+
+/// Index the fonts in `roots` and their subdirectories, reusing what the
+/// cache at `cache_path` holds for directories and files that are unchanged
+/// and saving it again if anything was not.
+pub(crate) fn index_font_dirs(roots: Vec<PathBuf>, cache_path: Option<PathBuf>) -> FontLookup {
+    let old = cache_path
+        .as_deref()
+        .map(FontCache::load)
+        .unwrap_or_default();
+    let mut new = FontCache::default();
+    let mut changed = false;
     let mut index = FontLookup::new();
-    let dirs = font_directories();
 
     // Recursive walk using a stack
-    let mut stack: Vec<PathBuf> = dirs;
+    let mut stack = roots;
     while let Some(dir) = stack.pop() {
-        let Ok(entries) = std::fs::read_dir(&dir) else {
+        let Ok(meta) = std::fs::metadata(&dir) else {
             continue;
         };
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                stack.push(path);
-                continue;
+        let modified = font_cache::modified(&meta);
+        let entries = match old.dirs.get(&dir) {
+            Some(cached) if Some(cached.modified) == modified => cached.entries.clone(),
+            _ => {
+                changed = true;
+                let Some(entries) = list_font_dir(&dir) else {
+                    continue;
+                };
+                entries
             }
-            let is_collection = match path.extension().and_then(|e| e.to_str()) {
-                Some("ttf" | "otf" | "TTF" | "OTF") => false,
-                Some("ttc" | "TTC") => true,
-                _ => continue,
+        };
+        for entry in &entries {
+            let path = match entry {
+                Entry::Dir(path) => {
+                    stack.push(path.clone());
+                    continue;
+                }
+                Entry::Font(path) => path,
             };
-            let Ok(data) = std::fs::read(&path) else {
+            let Some((file, read)) = font_file_faces(path, &old) else {
                 continue;
             };
-            let face_count = if is_collection {
-                ttf_parser::fonts_in_collection(&data).unwrap_or(1)
-            } else {
-                1
-            };
-            for face_idx in 0..face_count {
-                if let Some((family, bold, italic)) = read_font_style(&data, face_idx) {
-                    index
-                        .entry((family.to_lowercase(), bold, italic))
-                        .or_insert((path.clone(), face_idx));
-                }
+            changed |= read;
+            for face in &file.faces {
+                index
+                    .entry((face.family.to_lowercase(), face.bold, face.italic))
+                    .or_insert((path.clone(), face.index));
             }
+            new.files.insert(path.clone(), file);
+        }
+        if let Some(modified) = modified {
+            new.dirs.insert(dir, CachedDir { modified, entries });
         }
     }
+
+    if let Some(path) = cache_path
+        && (changed || new.dirs.len() != old.dirs.len())
+    {
+        new.save(&path);
+    }
     index
 }
 
+/// Subdirectories and font files of `dir`, sorted: directory order varies
+/// between file systems, and sorting makes the first of two files for the
+/// same face win everywhere.
+fn list_font_dir(dir: &Path) -> Option<Vec<Entry>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    let entries = paths
+        .into_iter()
+        .filter_map(|path| {
+            if path.is_dir() {
+                return Some(Entry::Dir(path));
+            }
+            let ext = path.extension().and_then(|e| e.to_str())?;
+            let is_font = matches!(ext, "ttf" | "otf" | "ttc" | "TTF" | "OTF" | "TTC");
+            is_font.then_some(Entry::Font(path))
+        })
+        .collect();
+    Some(entries)
+}
+
+/// The faces of a font file: from the cache while the file's size and
+/// modification time are unchanged, otherwise read from the file, which the
+/// flag returned tells.
+fn font_file_faces(path: &Path, cache: &FontCache) -> Option<(CachedFile, bool)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let size = meta.len();
+    let modified = font_cache::modified(&meta);
+    if let Some(cached) = cache.files.get(path)
+        && cached.size == size
+        && Some(cached.modified) == modified
+    {
+        let file = CachedFile {
+            size,
+            modified: cached.modified,
+            faces: cached.faces.clone(),
+        };
+        return Some((file, false));
+    }
+    let faces = read_file_faces(path)?;
+    let file = CachedFile {
+        size,
+        // Files without a modification time are read every time
+        modified: modified.unwrap_or(0),
+        faces,
+    };
+    Some((file, true))
+}
+
 fn get_font_index() -> &'static FontLookup {
     FONT_INDEX.get_or_init(scan_font_dirs)
 }
 
+/// Scan the installed fonts now rather than in the first conversion that
+/// needs them.
+pub(crate) fn warm_font_index() {
+    get_font_index();
+}
+
 /// Look up a font file by family name and style using the OS/2 table metadata index.
 /// Falls back to the regular variant if the requested bold/italic is not available.
 fn find_font_file(font_name: &str, bold: bool, italic: bool) -> Option<(PathBuf, u32)> {
@@ -198,42 +555,105 @@ fn winansi_to_char(byte: u8) -> char {
     }
 }
 
+/// WinAnsi (Windows-1252) byte for a char, if the encoding has one.
+fn winansi_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        0x0000..=0x007F => Some(c as u8),
+        0x00A0..=0x00FF => Some(c as u8), // Latin-1 supplement maps directly
+        0x2007 | 0x202F => Some(0xA0),    // figure / narrow no-break space
+        0x2011 => Some(b'-'),             // non-breaking hyphen
+        0xF020..=0xF0FF => Some((c as u32 - 0xF000) as u8), // symbol font (w:sym) codes
+        0x20AC => Some(0x80),
+        0x201A => Some(0x82),
+        0x0192 => Some(0x83),
+        0x201E => Some(0x84),
+        0x2026 => Some(0x85),
+        0x2020 => Some(0x86),
+        0x2021 => Some(0x87),
+        0x02C6 => Some(0x88),
+        0x2030 => Some(0x89),
+        0x0160 => Some(0x8A),
+        0x2039 => Some(0x8B),
+        0x0152 => Some(0x8C),
+        0x017D => Some(0x8E),
+        0x2018 => Some(0x91),
+        0x2019 => Some(0x92),
+        0x201C => Some(0x93),
+        0x201D => Some(0x94),
+        0x2022 => Some(0x95), // bullet
+        0x2013 => Some(0x96),
+        0x2014 => Some(0x97),
+        0x02DC => Some(0x98),
+        0x2122 => Some(0x99),
+        0x0161 => Some(0x9A),
+        0x203A => Some(0x9B),
+        0x0153 => Some(0x9C),
+        0x017E => Some(0x9E),
+        0x0178 => Some(0x9F),
+        _ => None,
+    }
+}
+
+/// Stand-ins for common characters WinAnsi lacks, themselves in WinAnsi.
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => "", // zero-width
+        '\u{2000}'..='\u{2006}' | '\u{2008}'..='\u{200A}' | '\u{205F}' => " ",
+        '\u{2010}' | '\u{2012}' | '\u{2043}' | '\u{2212}' => "-",
+        '\u{2015}' => "\u{2014}",
+        '\u{2032}' | '\u{2035}' | '\u{02B9}' | '\u{02BC}' => "'",
+        '\u{2033}' | '\u{2036}' | '\u{02BA}' => "\"",
+        '\u{201B}' => "\u{2018}",
+        '\u{201F}' => "\u{201C}",
+        '\u{2044}' | '\u{2215}' => "/",
+        '\u{2190}' => "<-",
+        '\u{2192}' => "->",
+        '\u{2194}' => "<->",
+        '\u{21D0}' => "<=",
+        '\u{21D2}' => "=>",
+        '\u{21D4}' => "<=>",
+        '\u{2264}' => "<=",
+        '\u{2265}' => ">=",
+        '\u{2260}' => "!=",
+        '\u{2248}' => "~",
+        '\u{2217}' => "*",
+        '\u{2219}' | '\u{22C5}' => "\u{00B7}",
+        '\u{2023}' | '\u{25AA}' | '\u{25CF}' | '\u{25E6}' => "\u{2022}",
+        _ => return None,
+    })
+}
+
+/// A stand-in for `c` made only of characters `has` accepts: its
+/// transliteration, else its compatibility decomposition without combining
+/// marks, e.g. "ā" -> "a" and "ﬁ" -> "fi".
+fn substitute(c: char, has: impl Fn(char) -> bool) -> Option<String> {
+    if let Some(sub) = transliterate(c)
+        && sub.chars().all(&has)
+    {
+        return Some(sub.to_string());
+    }
+    let base: String = std::iter::once(c)
+        .nfkd()
+        .filter(|&d| !is_combining_mark(d))
+        .collect();
+    (!base.is_empty() && !base.starts_with(c) && base.chars().all(has)).then_some(base)
+}
+
 /// Convert a UTF-8 string to WinAnsi (Windows-1252) bytes for PDF Str encoding.
-pub(crate) fn to_winansi_bytes(s: &str) -> Vec<u8> {
-    s.chars()
-        .filter_map(|c| match c as u32 {
-            0x0000..=0x007F => Some(c as u8),
-            0x00A0..=0x00FF => Some(c as u8), // Latin-1 supplement maps directly
-            0x20AC => Some(0x80),
-            0x201A => Some(0x82),
-            0x0192 => Some(0x83),
-            0x201E => Some(0x84),
-            0x2026 => Some(0x85),
-            0x2020 => Some(0x86),
-            0x2021 => Some(0x87),
-            0x02C6 => Some(0x88),
-            0x2030 => Some(0x89),
-            0x0160 => Some(0x8A),
-            0x2039 => Some(0x8B),
-            0x0152 => Some(0x8C),
-            0x017D => Some(0x8E),
-            0x2018 => Some(0x91),
-            0x2019 => Some(0x92),
-            0x201C => Some(0x93),
-            0x201D => Some(0x94),
-            0x2022 => Some(0x95), // bullet
-            0x2013 => Some(0x96),
-            0x2014 => Some(0x97),
-            0x02DC => Some(0x98),
-            0x2122 => Some(0x99),
-            0x0161 => Some(0x9A),
-            0x203A => Some(0x9B),
-            0x0153 => Some(0x9C),
-            0x017E => Some(0x9E),
-            0x0178 => Some(0x9F),
-            _ => None,
-        })
-        .collect()
+/// Characters outside WinAnsi are transliterated where there is a common
+/// stand-in, stripped of accents otherwise, and shown as '?' as a last resort.
+fn to_winansi_bytes(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len());
+    for c in s.chars() {
+        if let Some(b) = winansi_byte(c) {
+            bytes.push(b);
+        } else if let Some(sub) = substitute(c, |d| winansi_byte(d).is_some()) {
+            bytes.extend(sub.chars().filter_map(winansi_byte));
+        } else {
+            bytes.push(b'?');
+        }
+    }
+    bytes
 }
 
 /// Approximate Helvetica widths at 1000 units/em for WinAnsi chars 32..=255.
@@ -256,18 +676,41 @@ fn helvetica_widths() -> Vec<f32> {
         .collect()
 }
 
-/// Embed a TrueType/OpenType font (raw bytes) into the PDF.
+/// Embed a TrueType/OpenType font (raw bytes) into the PDF; faces with CFF
+/// outlines become Type1 fonts. The font file itself is written by `write_font_file` once layout is done.
 fn embed_truetype(
     pdf: &mut Pdf,
-    font_ref: Ref,
-    descriptor_ref: Ref,
-    data_ref: Ref,
-    font_name: &str,
+    (font_ref, descriptor_ref, data_ref, to_unicode_ref): (Ref, Ref, Ref, Ref),
+    base_font: &str,
     font_data: &[u8],
     face_index: u32,
-) -> Option<(Vec<f32>, f32, f32)> {
+    bold: bool,
+    italic: bool,
+) -> Option<FaceMetrics> {
     let face = Face::parse(font_data, face_index).ok()?;
 
+    // The regular face stands in for a missing bold or italic one
+    let synthetic = Synthetic {
+        bold: bold && !face.is_bold(),
+        italic: italic && !face.is_italic() && !face.is_oblique(),
+    };
+    let advance_scale = if synthetic.bold {
+        SYNTHETIC_BOLD_ADVANCE
+    } else {
+        1.0
+    };
+
+    // OpenType fonts with CFF outlines are no TrueType programs
+    let tables = face.tables();
+    let outlines = if tables.cff.is_some() || tables.cff2.is_some() {
+        Outlines::Cff
+    } else {
+        Outlines::TrueType
+    };
+    // CID-keyed CFF fonts select glyphs by CID, also through the composite font
+    let cid = |gid: GlyphId| tables.cff.as_ref().and_then(|cff| cff.glyph_cid(gid));
+    let cid_keyed = cid(GlyphId(0)).is_some();
+
     let units = face.units_per_em() as f32;
     let ascent = face.ascender() as f32 / units * 1000.0;
     let descent = face.descender() as f32 / units * 1000.0;
@@ -284,41 +727,91 @@ fn embed_truetype(
         bb.y_max as f32 / units * 1000.0,
     );
 
+    // Symbol fonts (Wingdings, Symbol, ...) carry a (3,0) cmap that maps
+    // 0xF000 + byte; PDF viewers use that mapping when no /Encoding is given.
+    // Some also map a few Unicode characters, but text in them is symbol codes.
+    let symbolic = face.tables().cmap.is_some_and(|cmap| {
+        cmap.subtables
+            .into_iter()
+            .any(|st| st.platform_id == ttf_parser::PlatformId::Windows && st.encoding_id == 0)
+    });
+
     let widths: Vec<f32> = (32u8..=255u8)
         .map(|byte| {
-            face.glyph_index(winansi_to_char(byte))
+            winansi_glyph(&face, byte)
                 .and_then(|gid| face.glyph_hor_advance(gid))
-                .map(|adv| adv as f32 / units * 1000.0)
+                .map(|adv| adv as f32 / units * 1000.0 * advance_scale)
                 .unwrap_or(0.0)
         })
         .collect();
 
-    let data_len = i32::try_from(font_data.len()).ok()?;
-    pdf.stream(data_ref, font_data)
-        .pair(Name(b"Length1"), data_len);
-
-    let ps_name = font_name.replace(' ', "");
+    // Every Unicode character the face maps, for text beyond WinAnsi
+    let glyphs = (!symbolic).then(|| {
+        let mut glyphs = HashMap::new();
+        let subtables = face.tables().cmap.map(|cmap| cmap.subtables);
+        for st in subtables.into_iter().flatten().filter(|st| st.is_unicode()) {
+            st.codepoints(|cp| {
+                if let Some(c) = char::from_u32(cp)
+                    && let Some(gid) = st.glyph_index(cp)
+                {
+                    let advance = face.glyph_hor_advance(gid).unwrap_or(0);
+                    let code = if cid_keyed {
+                        cid(gid).unwrap_or(0)
+                    } else {
+                        gid.0
+                    };
+                    glyphs
+                        .entry(c)
+                        .or_insert((code, advance as f32 / units * 1000.0 * advance_scale));
+                }
+            });
+        }
+        glyphs
+    });
+    let gids_by_cid = cid_keyed.then(|| {
+        (0..face.number_of_glyphs())
+            .filter_map(|gid| Some((cid(GlyphId(gid))?, gid)))
+            .collect()
+    });
 
-    pdf.font_descriptor(descriptor_ref)
-        .name(Name(ps_name.as_bytes()))
-        .flags(pdf_writer::types::FontFlags::NON_SYMBOLIC)
+    let mut descriptor = pdf.font_descriptor(descriptor_ref);
+    descriptor
+        .name(Name(base_font.as_bytes()))
+        .flags(if symbolic {
+            pdf_writer::types::FontFlags::SYMBOLIC
+        } else {
+            pdf_writer::types::FontFlags::NON_SYMBOLIC
+        })
         .bbox(bbox)
         .italic_angle(0.0)
         .ascent(ascent)
         .descent(descent)
         .cap_height(cap_height)
-        .stem_v(80.0)
-        .font_file2(data_ref);
+        .stem_v(80.0);
+    match outlines {
+        Outlines::TrueType => descriptor.font_file2(data_ref),
+        Outlines::Cff => descriptor.font_file3(data_ref),
+    };
+    drop(descriptor);
 
     {
         let mut d = pdf.indirect(font_ref).dict();
         d.pair(Name(b"Type"), Name(b"Font"));
-        d.pair(Name(b"Subtype"), Name(b"TrueType"));
-        d.pair(Name(b"BaseFont"), Name(ps_name.as_bytes()));
-        d.pair(Name(b"Encoding"), Name(b"WinAnsiEncoding"));
+        d.pair(
+            Name(b"Subtype"),
+            match outlines {
+                Outlines::TrueType => Name(b"TrueType"),
+                Outlines::Cff => Name(b"Type1"),
+            },
+        );
+        d.pair(Name(b"BaseFont"), Name(base_font.as_bytes()));
+        if !symbolic {
+            d.pair(Name(b"Encoding"), Name(b"WinAnsiEncoding"));
+        }
         d.pair(Name(b"FirstChar"), 32i32);
         d.pair(Name(b"LastChar"), 255i32);
         d.pair(Name(b"FontDescriptor"), descriptor_ref);
+        d.pair(Name(b"ToUnicode"), to_unicode_ref);
         d.insert(Name(b"Widths"))
             .array()
             .items(widths.iter().copied());
@@ -327,8 +820,127 @@ fn embed_truetype(
     let line_gap = face.line_gap() as f32;
     let line_h_ratio = (face.ascender() as f32 - face.descender() as f32 + line_gap) / units;
     let ascender_ratio = face.ascender() as f32 / units;
+    let underline = face
+        .underline_metrics()
+        .filter(|m| m.thickness > 0)
+        .map(|m| (m.position as f32 / units, m.thickness as f32 / units));
+    let strikeout = face
+        .strikeout_metrics()
+        .filter(|m| m.thickness > 0)
+        .map(|m| (m.position as f32 / units, m.thickness as f32 / units));
 
-    Some((widths, line_h_ratio, ascender_ratio))
+    Some(FaceMetrics {
+        widths,
+        kerning: kerning_pairs(&face, units),
+        synthetic,
+        glyphs,
+        gids_by_cid,
+        outlines,
+        line_h_ratio,
+        ascender_ratio,
+        underline,
+        strikeout,
+    })
+}
+
+/// Pair adjustments between the glyphs of WinAnsi codes at 1000 units/em,
+/// from the GPOS `kern` feature, or the legacy `kern` table of older fonts.
+fn kerning_pairs(face: &Face, units: f32) -> HashMap<(u8, u8), f32> {
+    let codes: Vec<(u8, GlyphId)> = (32u8..=255u8)
+        .filter_map(|byte| Some((byte, winansi_glyph(face, byte)?)))
+        .collect();
+    let mut pairs = HashMap::new();
+    // The first subtable covering a pair decides it
+    let mut add = |left: u8, right: u8, value: i16| {
+        if value != 0 {
+            pairs
+                .entry((left, right))
+                .or_insert(value as f32 / units * 1000.0);
+        }
+    };
+
+    if let Some(gpos) = face.tables().gpos {
+        let kern = Tag::from_bytes(b"kern");
+        let lookups: BTreeSet<u16> = gpos
+            .features
+            .into_iter()
+            .filter(|feature| feature.tag == kern)
+            .flat_map(|feature| feature.lookup_indices)
+            .collect();
+        for lookup in lookups.into_iter().filter_map(|i| gpos.lookups.get(i)) {
+            for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+                let PositioningSubtable::Pair(pair) = subtable else {
+                    continue;
+                };
+                for &(left, left_gid) in &codes {
+                    match &pair {
+                        PairAdjustment::Format1 { coverage, sets } => {
+                            let Some(set) = coverage.get(left_gid).and_then(|i| sets.get(i)) else {
+                                continue;
+                            };
+                            for &(right, right_gid) in &codes {
+                                if let Some((first, _)) = set.get(right_gid) {
+                                    add(left, right, first.x_advance);
+                                }
+                            }
+                        }
+                        PairAdjustment::Format2 {
+                            coverage,
+                            classes,
+                            matrix,
+                        } => {
+                            if !coverage.contains(left_gid) {
+                                continue;
+                            }
+                            let left_class = classes.0.get(left_gid);
+                            for &(right, right_gid) in &codes {
+                                if let Some((first, _)) =
+                                    matrix.get((left_class, classes.1.get(right_gid)))
+                                {
+                                    add(left, right, first.x_advance);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    } else if let Some(kern) = face.tables().kern {
+        let subtables = kern.subtables.into_iter();
+        for st in subtables.filter(|st| st.horizontal && !st.has_cross_stream && !st.variable) {
+            for &(left, left_gid) in &codes {
+                for &(right, right_gid) in &codes {
+                    if let Some(value) = st.glyphs_kerning(left_gid, right_gid) {
+                        add(left, right, value);
+                    }
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Glyph a WinAnsi code shows: through the Unicode cmap, or the (3,0) cmap
+/// of symbol fonts.
+fn winansi_glyph(face: &Face, byte: u8) -> Option<GlyphId> {
+    face.glyph_index(winansi_to_char(byte))
+        .or_else(|| face.glyph_index(char::from_u32(0xF000 + byte as u32)?))
+}
+
+/// BaseFont name of a subset font: a six-letter tag, unique to the PDF font,
+/// before the PostScript name, e.g. "KQWBZT+Aptos".
+fn subset_font_name(font_name: &str, pdf_name: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    (font_name, pdf_name).hash(&mut hasher);
+    let mut h = hasher.finish();
+    let tag: String = (0..6)
+        .map(|_| {
+            let c = (b'A' + (h % 26) as u8) as char;
+            h /= 26;
+            c
+        })
+        .collect();
+    format!("{tag}+{}", font_name.replace(' ', ""))
 }
 
 pub(crate) fn primary_font_name(name: &str) -> &str {
@@ -345,48 +957,605 @@ pub(crate) fn font_key(run: &Run) -> String {
     }
 }
 
-pub(crate) type EmbeddedFonts = HashMap<(String, bool, bool), Vec<u8>>;
+pub(crate) type EmbeddedFonts = BTreeMap<(String, bool, bool), Vec<u8>>;
+
+/// Installed faces embedded in place of a missing font in PDF/A mode, where
+/// the non-embedded Helvetica fallback is not allowed.
+const PDFA_SUBSTITUTE_FONTS: &[&str] = &["Liberation Sans", "DejaVu Sans", "Arial"];
 
+/// Register the font of `font_name` in the given style, embedding it from the
+/// DOCX or an installed file. A missing font falls back to Helvetica, or in
+/// `pdfa` mode to an installed substitute; without one that is an error.
 pub(crate) fn register_font(
     pdf: &mut Pdf,
     font_name: &str,
-    bold: bool,
-    italic: bool,
+    (bold, italic): (bool, bool),
     pdf_name: String,
     alloc: &mut impl FnMut() -> Ref,
     embedded_fonts: &EmbeddedFonts,
-) -> FontEntry {
+    pdfa: bool,
+) -> Result<FontEntry, Error> {
     let font_ref = alloc();
     let descriptor_ref = alloc();
     let data_ref = alloc();
+    let to_unicode_ref = alloc();
 
     let embedded_key = (font_name.to_lowercase(), bold, italic);
     let embedded_data = embedded_fonts.get(&embedded_key);
 
-    let (widths, line_h_ratio, ascender_ratio) = embedded_data
-        .and_then(|data| {
-            embed_truetype(pdf, font_ref, descriptor_ref, data_ref, font_name, data, 0)
-        })
+    let mut embed = |name: &str, data: Cow<[u8]>, face_index: u32| {
+        let base_font = subset_font_name(name, &pdf_name);
+        let m = embed_truetype(
+            pdf,
+            (font_ref, descriptor_ref, data_ref, to_unicode_ref),
+            &base_font,
+            &data,
+            face_index,
+            bold,
+            italic,
+        )?;
+        Some((m, base_font, data.into_owned(), face_index))
+    };
+    let installed = |name: &str| {
+        let (path, face_index) = find_font_file(name, bold, italic)?;
+        Some((Cow::Owned(std::fs::read(&path).ok()?), face_index))
+    };
+    let mut embedded = embedded_data
+        .and_then(|data| embed(font_name, Cow::Borrowed(data), 0))
         .or_else(|| {
-            find_font_file(font_name, bold, italic).and_then(|(path, face_index)| {
-                let data = std::fs::read(&path).ok()?;
-                embed_truetype(pdf, font_ref, descriptor_ref, data_ref, font_name, &data, face_index)
-            })
-        })
-        .map(|(w, r, ar)| (w, Some(r), Some(ar)))
-        .unwrap_or_else(|| {
-            log::warn!("Font not found: {font_name} bold={bold} italic={italic} — using Helvetica");
-            pdf.type1_font(font_ref)
-                .base_font(Name(b"Helvetica"))
-                .encoding_predefined(Name(b"WinAnsiEncoding"));
-            (helvetica_widths(), None, None)
+            let (data, face_index) = installed(font_name)?;
+            embed(font_name, data, face_index)
         });
+    let missing = embedded.is_none();
+    if missing && pdfa {
+        embedded = PDFA_SUBSTITUTE_FONTS.iter().find_map(|&substitute| {
+            let (data, face_index) = installed(substitute)?;
+            let embedded = embed(substitute, data, face_index)?;
+            log::warn!(
+                "Font not found: {font_name} bold={bold} italic={italic} — embedding {substitute} instead"
+            );
+            Some(embedded)
+        });
+    }
 
-    FontEntry {
+    let Some((m, base_font, data, face_index)) = embedded else {
+        if pdfa {
+            return Err(Error::Pdf(format!(
+                "PDF/A needs every font embedded, but neither {font_name} nor a substitute ({}) is installed",
+                PDFA_SUBSTITUTE_FONTS.join(", ")
+            )));
+        }
+        log::warn!("Font not found: {font_name} bold={bold} italic={italic} — using Helvetica");
+        pdf.type1_font(font_ref)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        return Ok(FontEntry {
+            pdf_name,
+            font_ref,
+            widths_1000: helvetica_widths(),
+            kerning: HashMap::new(),
+            composite: None,
+            font_file: None,
+            words: RefCell::default(),
+            missing,
+            synthetic: Synthetic { bold, italic },
+            line_h_ratio: None,
+            ascender_ratio: None,
+            underline_position: None,
+            underline_thickness: None,
+            strikeout_position: None,
+            strikeout_thickness: None,
+        });
+    };
+
+    let symbolic = m.glyphs.is_none();
+    let composite = m.glyphs.map(|glyphs| CompositeFont {
+        pdf_name: format!("{pdf_name}U"),
+        base_font,
+        descriptor_ref,
+        glyphs,
+        used: RefCell::new(BTreeMap::new()),
+        gids_by_cid: m.gids_by_cid,
+    });
+    Ok(FontEntry {
         pdf_name,
         font_ref,
-        widths_1000: widths,
-        line_h_ratio,
-        ascender_ratio,
+        widths_1000: m.widths,
+        kerning: m.kerning,
+        composite,
+        synthetic: m.synthetic,
+        words: RefCell::default(),
+        missing,
+        font_file: Some(FontFile {
+            data_ref,
+            data,
+            face_index,
+            outlines: m.outlines,
+            used_codes: RefCell::new(BTreeSet::new()),
+            to_unicode_ref,
+            symbolic,
+        }),
+        line_h_ratio: Some(m.line_h_ratio),
+        ascender_ratio: Some(m.ascender_ratio),
+        underline_position: m.underline.map(|(position, _)| position),
+        underline_thickness: m.underline.map(|(_, thickness)| thickness),
+        strikeout_position: m.strikeout.map(|(position, _)| position),
+        strikeout_thickness: m.strikeout.map(|(_, thickness)| thickness),
+    })
+}
+
+/// Write the Type0 font for `entry`'s glyphs beyond WinAnsi, if layout drew
+/// any, sharing the simple font's descriptor and embedded font file. Returns
+/// its resource name and reference.
+pub(crate) fn write_composite_font(
+    pdf: &mut Pdf,
+    entry: &FontEntry,
+    alloc: &mut impl FnMut() -> Ref,
+) -> Option<(String, Ref)> {
+    let cf = entry.composite.as_ref()?;
+    let used = cf.used.borrow();
+    if used.is_empty() {
+        return None;
+    }
+    let font_ref = alloc();
+    let cid_ref = alloc();
+    let cmap_ref = alloc();
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"Identity"),
+        supplement: 0,
+    };
+
+    pdf.type0_font(font_ref)
+        .base_font(Name(cf.base_font.as_bytes()))
+        .encoding_predefined(Name(b"Identity-H"))
+        .descendant_font(cid_ref)
+        .to_unicode(cmap_ref);
+
+    let cff = entry
+        .font_file
+        .as_ref()
+        .is_some_and(|f| f.outlines == Outlines::Cff);
+    let mut cid = pdf.cid_font(cid_ref);
+    cid.subtype(if cff {
+        CidFontType::Type0
+    } else {
+        CidFontType::Type2
+    })
+    .base_font(Name(cf.base_font.as_bytes()))
+    .system_info(system_info)
+    .font_descriptor(cf.descriptor_ref);
+    // CFF fonts map CIDs to glyphs themselves
+    if !cff {
+        cid.cid_to_gid_map_predefined(Name(b"Identity"));
+    }
+    // One W entry per run of consecutive glyph IDs
+    let mut widths = cid.widths();
+    let mut glyphs = used.iter().peekable();
+    while let Some((&start, &(_, w))) = glyphs.next() {
+        let mut run = vec![w];
+        while let Some(&(&gid, &(_, w))) = glyphs.peek()
+            && gid as usize == start as usize + run.len()
+        {
+            run.push(w);
+            glyphs.next();
+        }
+        widths.consecutive(start, run);
+    }
+    drop(widths);
+    drop(cid);
+
+    let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+    for (&gid, &(c, _)) in used.iter() {
+        cmap.pair(gid, c);
+    }
+    pdf.cmap(cmap_ref, &deflate(&cmap.finish()))
+        .filter(Filter::FlateDecode);
+
+    Some((cf.pdf_name.clone(), font_ref))
+}
+
+/// Write the ToUnicode CMap of `entry`'s simple font, mapping each code layout
+/// used back to its character so that text extraction doesn't rely on the
+/// viewer's reading of the encoding. Symbol font codes map to the private-use
+/// characters `w:sym` gives them.
+pub(crate) fn write_to_unicode(pdf: &mut Pdf, entry: &FontEntry) {
+    let Some(file) = &entry.font_file else {
+        return;
+    };
+    let system_info = SystemInfo {
+        registry: Str(b"Adobe"),
+        ordering: Str(b"UCS"),
+        supplement: 0,
+    };
+    let mut cmap = UnicodeCmap::<u8>::new(Name(b"Custom"), system_info);
+    for &code in file.used_codes.borrow().iter().filter(|&&b| b >= 32) {
+        let c = if file.symbolic {
+            char::from_u32(0xF000 + code as u32).unwrap_or('?')
+        } else {
+            winansi_to_char(code)
+        };
+        cmap.pair(code, c);
+    }
+    pdf.cmap(file.to_unicode_ref, &deflate(&cmap.finish()))
+        .filter(Filter::FlateDecode);
+}
+
+/// Write the font file stream of `entry`, subset to the glyphs layout drew
+/// through its simple and composite fonts. Glyph IDs are kept, so the
+/// encodings written at registration stay valid.
+pub(crate) fn write_font_file(pdf: &mut Pdf, entry: &FontEntry) {
+    let Some(file) = &entry.font_file else {
+        return;
+    };
+    let mut glyphs: BTreeSet<u16> = BTreeSet::from([0]);
+    let mut cid_keyed = false;
+    if let Ok(face) = Face::parse(&file.data, file.face_index) {
+        cid_keyed = face
+            .tables()
+            .cff
+            .and_then(|cff| cff.glyph_cid(GlyphId(0)))
+            .is_some();
+        glyphs.extend(
+            file.used_codes
+                .borrow()
+                .iter()
+                .filter_map(|&b| winansi_glyph(&face, b))
+                .map(|gid| gid.0),
+        );
+    }
+    if let Some(cf) = &entry.composite {
+        let used = cf.used.borrow();
+        match &cf.gids_by_cid {
+            Some(gids) => glyphs.extend(used.keys().filter_map(|cid| gids.get(cid))),
+            None => glyphs.extend(used.keys()),
+        }
+    }
+    let glyphs: Vec<u16> = glyphs.into_iter().collect();
+    let data = subsetter::subset(
+        &file.data,
+        file.face_index,
+        subsetter::Profile::pdf(&glyphs),
+    )
+    .map(Cow::Owned)
+    .unwrap_or_else(|e| {
+        log::warn!(
+            "Could not subset font {}: {e} — embedding it whole",
+            entry.pdf_name
+        );
+        Cow::Borrowed(&file.data)
+    });
+    match file.outlines {
+        Outlines::TrueType => {
+            // Length1 is the length of the decoded font program
+            let data_len = i32::try_from(data.len()).unwrap_or(i32::MAX);
+            pdf.stream(file.data_ref, &deflate(&data))
+                .filter(Filter::FlateDecode)
+                .pair(Name(b"Length1"), data_len);
+        }
+        Outlines::Cff => {
+            // The bare CFF table; CFF2 faces have none and stay OpenType
+            let index = if matches!(data, Cow::Owned(_)) {
+                0
+            } else {
+                file.face_index
+            };
+            let cff = ttf_parser::RawFace::parse(&data, index)
+                .ok()
+                .and_then(|raw| raw.table(Tag::from_bytes(b"CFF ")));
+            let (program, subtype) = match cff {
+                Some(cff) if cid_keyed => (cff, Name(b"CIDFontType0C")),
+                Some(cff) => (cff, Name(b"Type1C")),
+                None => (&data[..], Name(b"OpenType")),
+            };
+            pdf.stream(file.data_ref, &deflate(program))
+                .filter(Filter::FlateDecode)
+                .pair(Name(b"Subtype"), subtype);
+        }
+    }
+}
+
+/// Families tried, in order, for characters a run's font has no glyph for,
+/// before any other installed font.
+pub(crate) const DEFAULT_FALLBACK_FONTS: &[&str] = &[
+    "Segoe UI Symbol",
+    "Arial Unicode MS",
+    "Cambria Math",
+    "Noto Sans",
+    "Noto Sans Symbols",
+    "Noto Sans Symbols 2",
+    "Noto Sans CJK SC",
+    "Microsoft YaHei",
+    "PingFang SC",
+    "DejaVu Sans",
+];
+
+/// The characters a face maps, with its family name as the font spells it.
+/// Symbol fonts have no Unicode cmap and are taken to cover any text set in them.
+struct Coverage {
+    family: String,
+    chars: Option<HashSet<char>>,
+}
+
+impl Coverage {
+    fn has(&self, c: char) -> bool {
+        self.chars.as_ref().is_none_or(|chars| chars.contains(&c))
+    }
+}
+
+fn load_coverage(
+    font_name: &str,
+    bold: bool,
+    italic: bool,
+    embedded_fonts: &EmbeddedFonts,
+) -> Option<Coverage> {
+    let Some(data) = embedded_fonts.get(&(font_name.to_lowercase(), bold, italic)) else {
+        let (path, face_index) = find_font_file(font_name, bold, italic)?;
+        return file_coverage(&path, face_index, font_name);
+    };
+    let face = Face::parse(data, 0).ok()?;
+    Some(Coverage {
+        family: font_family_name(&face).unwrap_or_else(|| font_name.to_string()),
+        chars: cmap_chars(face.tables().cmap),
+    })
+}
+
+/// Coverage of an installed face, read from its `name` and `cmap` tables
+/// only rather than from the whole file.
+fn file_coverage(path: &Path, face_index: u32, font_name: &str) -> Option<Coverage> {
+    let mut file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+    let cmap = sfnt::read_cmap(&mut file, face_index)?;
+    let family = StyleTables::read(&mut file, face_index)
+        .and_then(|tables| tables.face().as_ref().and_then(font_family_name))
+        .unwrap_or_else(|| font_name.to_string());
+    Some(Coverage {
+        family,
+        chars: cmap_chars(cmap.as_deref().and_then(ttf_parser::cmap::Table::parse)),
+    })
+}
+
+/// Characters with a glyph in the Unicode subtables of `cmap`, or None when
+/// it has none, as in symbol fonts.
+fn cmap_chars(cmap: Option<ttf_parser::cmap::Table>) -> Option<HashSet<char>> {
+    let subtables = cmap.map(|cmap| cmap.subtables);
+    let mut unicode = subtables
+        .into_iter()
+        .flatten()
+        .filter(|st| st.is_unicode())
+        .peekable();
+    unicode.peek().is_some().then(|| {
+        let mut chars = HashSet::new();
+        for st in unicode {
+            st.codepoints(|cp| {
+                if let Some(c) = char::from_u32(cp)
+                    && st.glyph_index(cp).is_some()
+                {
+                    chars.insert(c);
+                }
+            });
+        }
+        chars
+    })
+}
+
+/// The first installed family, by name, with a glyph for each character.
+/// Built once per process, the first time a conversion has a character in
+/// neither the run's font nor the listed fallback families.
+static INSTALLED_COVERAGE: OnceLock<HashMap<char, String>> = OnceLock::new();
+
+fn installed_coverage() -> &'static HashMap<char, String> {
+    INSTALLED_COVERAGE.get_or_init(|| {
+        let mut faces: Vec<(&String, &(PathBuf, u32))> = get_font_index()
+            .iter()
+            .filter(|((_, bold, italic), _)| !bold && !italic)
+            .map(|((family, _, _), file)| (family, file))
+            .collect();
+        faces.sort_by(|a, b| a.0.cmp(b.0));
+        let mut index = HashMap::new();
+        for (family, (path, face_index)) in faces {
+            if let Some(Coverage {
+                family,
+                chars: Some(chars),
+            }) = file_coverage(path, *face_index, family)
+            {
+                for c in chars {
+                    index.entry(c).or_insert_with(|| family.clone());
+                }
+            }
+        }
+        index
+    })
+}
+
+/// Whether `c` is drawn with a glyph, rather than as space or nothing at all.
+/// Symbol font codes (w:sym) are left to the run's own font.
+fn needs_glyph(c: char) -> bool {
+    !(c.is_whitespace()
+        || c.is_control()
+        || matches!(
+            c,
+            '\u{00AD}' | '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}'
+        )
+        || ('\u{F000}'..='\u{F0FF}').contains(&c))
+}
+
+/// Fallback families from `DOCXSIDE_FALLBACK_FONTS` when set, separated like
+/// the directories of `DOCXSIDE_FONTS`, otherwise the configured ones.
+fn fallback_families(configured: &[String]) -> Vec<String> {
+    match std::env::var("DOCXSIDE_FALLBACK_FONTS") {
+        Ok(val) => {
+            let sep = if cfg!(windows) { ';' } else { ':' };
+            val.split(sep)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        }
+        Err(_) => configured.to_vec(),
+    }
+}
+
+/// Picks the font for characters a run's font can't show: the first fallback
+/// family with a glyph for it, else the first installed family that has one.
+struct FontFallback<'a> {
+    families: Vec<String>,
+    embedded_fonts: &'a EmbeddedFonts,
+    coverage: HashMap<(String, bool, bool), Option<Coverage>>,
+    used: BTreeMap<(String, String), BTreeSet<char>>, // (run font, fallback) -> chars
+}
+
+impl FontFallback<'_> {
+    fn coverage(&mut self, font_name: &str, bold: bool, italic: bool) -> Option<&Coverage> {
+        let embedded_fonts = self.embedded_fonts;
+        self.coverage
+            .entry((font_name.to_lowercase(), bold, italic))
+            .or_insert_with(|| load_coverage(font_name, bold, italic, embedded_fonts))
+            .as_ref()
+    }
+
+    /// A fallback family with a glyph for `c`; None when the run's font has
+    /// one, or no font does.
+    fn resolve(&mut self, run: &Run, c: char) -> Option<String> {
+        let primary = primary_font_name(&run.font_name);
+        let primary_has = match self.coverage(primary, run.bold, run.italic) {
+            Some(coverage) => coverage.has(c),
+            None => winansi_byte(c).is_some(), // drawn with the Helvetica substitute
+        };
+        if primary_has {
+            return None;
+        }
+        for family in self.families.clone() {
+            if let Some(coverage) = self.coverage(&family, false, false)
+                && coverage.chars.is_some()
+                && coverage.has(c)
+            {
+                return Some(coverage.family.clone());
+            }
+        }
+        installed_coverage().get(&c).cloned()
+    }
+
+    /// Split `run` into runs of text set in the same font, moving characters
+    /// its font lacks to a fallback font. Spaces stay with the text before them.
+    fn split(&mut self, run: Run) -> Vec<Run> {
+        if run.is_tab || run.is_line_break || run.field_code.is_some() {
+            return vec![run];
+        }
+        let mut pieces: Vec<(Option<String>, String)> = Vec::new();
+        for c in run.text.chars() {
+            let font = if needs_glyph(c) {
+                self.resolve(&run, c)
+            } else {
+                pieces.last().and_then(|(font, _)| font.clone())
+            };
+            if let Some(family) = &font
+                && needs_glyph(c)
+            {
+                let primary = primary_font_name(&run.font_name).to_string();
+                self.used
+                    .entry((primary, family.clone()))
+                    .or_default()
+                    .insert(c);
+            }
+            match pieces.last_mut() {
+                Some((last, text)) if *last == font => text.push(c),
+                _ => pieces.push((font, c.to_string())),
+            }
+        }
+        if pieces.iter().all(|(font, _)| font.is_none()) {
+            return vec![run];
+        }
+        pieces
+            .into_iter()
+            .map(|(font, text)| Run {
+                text,
+                font_name: font.unwrap_or_else(|| run.font_name.clone()),
+                ..run.clone()
+            })
+            .collect()
+    }
+}
+
+fn split_paragraph(fallback: &mut FontFallback, para: &mut Paragraph) {
+    let runs = std::mem::take(&mut para.runs);
+    para.runs = runs
+        .into_iter()
+        .flat_map(|run| fallback.split(run))
+        .collect();
+    for text_box in &mut para.text_boxes {
+        for p in &mut text_box.paragraphs {
+            split_paragraph(fallback, p);
+        }
+    }
+}
+
+/// Move characters that their run's font has no glyph for into runs set in a
+/// fallback font, tried in the order of `families` and then among all
+/// installed fonts, so that layout measures and draws them like any other run.
+pub(crate) fn apply_font_fallbacks(doc: &mut Document, families: &[String]) {
+    let embedded_fonts = std::mem::take(&mut doc.embedded_fonts);
+    let mut fallback = FontFallback {
+        families: fallback_families(families),
+        embedded_fonts: &embedded_fonts,
+        coverage: HashMap::new(),
+        used: BTreeMap::new(),
+    };
+
+    for block in &mut doc.blocks {
+        match block {
+            Block::Paragraph(para) => split_paragraph(&mut fallback, para),
+            Block::Table(table) => {
+                for cell in table.rows.iter_mut().flat_map(|row| row.cells.iter_mut()) {
+                    for para in &mut cell.paragraphs {
+                        split_paragraph(&mut fallback, para);
+                    }
+                }
+            }
+        }
+    }
+
+    // Sections share headers and footers; gather the slots of each one so it
+    // is split once and handed back to all of them. One the caller's document
+    // still holds is copied first.
+    let mut slots: Vec<&mut Option<Arc<HeaderFooter>>> = doc
+        .sections
+        .iter_mut()
+        .flat_map(|s| {
+            [
+                &mut s.header_default,
+                &mut s.header_first,
+                &mut s.header_even,
+                &mut s.footer_default,
+                &mut s.footer_first,
+                &mut s.footer_even,
+            ]
+        })
+        .collect();
+    let mut shared: Vec<(Arc<HeaderFooter>, Vec<usize>)> = Vec::new();
+    for (i, slot) in slots.iter_mut().enumerate() {
+        let Some(hf) = slot.take() else {
+            continue;
+        };
+        match shared.iter_mut().find(|(first, _)| Arc::ptr_eq(first, &hf)) {
+            Some((_, users)) => users.push(i),
+            None => shared.push((hf, vec![i])),
+        }
+    }
+    for (mut hf, users) in shared {
+        for para in &mut Arc::make_mut(&mut hf).paragraphs {
+            split_paragraph(&mut fallback, para);
+        }
+        for i in users {
+            *slots[i] = Some(hf.clone());
+        }
+    }
+
+    for ((primary, family), chars) in &fallback.used {
+        let chars: String = chars.iter().collect();
+        let count = chars.chars().count();
+        log::info!(
+            "Font fallback: {family} for {count} characters missing from {primary}: {chars}"
+        );
     }
+    doc.embedded_fonts = embedded_fonts;
 }
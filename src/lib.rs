@@ -1,15 +1,264 @@
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod docx;
 mod error;
+mod font_cache;
 mod fonts;
-mod model;
+pub mod model;
 mod pdf;
+mod png;
+mod sfnt;
+mod tags;
 
-pub use error::Error;
+pub use error::{Error, Warning};
+pub use model::Document;
 
-use std::path::Path;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Settings for a conversion.
+#[derive(Clone, Debug)]
+pub struct ConversionOptions {
+    /// Font families tried, in order, for characters a run's font has no glyph
+    /// for, before any other installed font. The `DOCXSIDE_FALLBACK_FONTS`
+    /// environment variable overrides the list.
+    pub fallback_fonts: Vec<String>,
+    /// Leave the creation and modification dates out of the PDF metadata, and
+    /// show the document's own last-saved date in DATE and TIME fields
+    /// instead of the current time, so converting the same document twice
+    /// gives the same bytes.
+    pub omit_dates: bool,
+    /// Write PDF/A-2b: every font embedded (missing ones replaced by an
+    /// installed Liberation Sans, DejaVu Sans or Arial), an sRGB output intent
+    /// and PDF/A identification in the XMP metadata.
+    pub pdfa: bool,
+    /// Write a tagged PDF: a structure tree of headings, paragraphs, lists,
+    /// tables and figures (with their alt text) for screen readers, with
+    /// headers, footers and decorative rules marked as artifacts.
+    pub tagged: bool,
+    /// Pages to write, numbered from 1 in the order they are laid out. The
+    /// document is paginated as a whole, so page numbers in headers and
+    /// footers stay those of the full document.
+    pub pages: Option<RangeInclusive<usize>>,
+    /// Page margins used for every section instead of the document's own.
+    pub override_margins: Option<Margins>,
+}
+
+/// Page margins in points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Margins {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl Margins {
+    /// The same margin on all four sides.
+    pub fn uniform(margin: f32) -> Self {
+        Margins {
+            top: margin,
+            bottom: margin,
+            left: margin,
+            right: margin,
+        }
+    }
+}
+
+/// What a conversion could not reproduce, returned by
+/// [`convert_docx_to_pdf_report`] and [`convert_docx_bytes_report`].
+#[derive(Clone, Debug, Default)]
+pub struct ConversionReport {
+    /// Each problem once, in the order it was found.
+    pub warnings: Vec<Warning>,
+}
+
+impl ConversionReport {
+    fn new(found: Vec<Warning>) -> Self {
+        let mut warnings = Vec::new();
+        for warning in found {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+        ConversionReport { warnings }
+    }
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        Self {
+            fallback_fonts: fonts::DEFAULT_FALLBACK_FONTS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            omit_dates: false,
+            pdfa: false,
+            tagged: false,
+            pages: None,
+            override_margins: None,
+        }
+    }
+}
 
 pub fn convert_docx_to_pdf(input: &Path, output: &Path) -> Result<(), Error> {
-    let doc = docx::parse(input)?;
-    let bytes = pdf::render(&doc)?;
-    std::fs::write(output, bytes).map_err(Error::Io)
+    convert_docx_to_pdf_with_options(input, output, &ConversionOptions::default())
+}
+
+pub fn convert_docx_to_pdf_with_options(
+    input: &Path,
+    output: &Path,
+    options: &ConversionOptions,
+) -> Result<(), Error> {
+    convert_docx_to_pdf_report(input, output, options).map(|_| ())
+}
+
+/// Like [`convert_docx_to_pdf_with_options`], also returning the warnings:
+/// missing fonts, skipped elements and pictures, and characters no font
+/// could show.
+pub fn convert_docx_to_pdf_report(
+    input: &Path,
+    output: &Path,
+    options: &ConversionOptions,
+) -> Result<ConversionReport, Error> {
+    let mut found = Vec::new();
+    let doc = docx::parse(input, &mut found)?;
+    let bytes = render(doc, options, &mut found)?;
+    std::fs::write(output, bytes).map_err(|e| {
+        Error::Io(std::io::Error::new(
+            e.kind(),
+            format!("{e}: {}", output.display()),
+        ))
+    })?;
+    Ok(ConversionReport::new(found))
+}
+
+/// Convert each `(input, output)` pair on up to `parallelism` threads (0 for
+/// one per core), returning the results in the order of `jobs`. Installed
+/// fonts are indexed once, before the first conversion starts.
+pub fn convert_many(jobs: &[(PathBuf, PathBuf)], parallelism: usize) -> Vec<Result<(), Error>> {
+    convert_many_with_options(jobs, parallelism, &ConversionOptions::default())
+        .into_iter()
+        .map(|result| result.map(|_| ()))
+        .collect()
+}
+
+/// Like [`convert_many`], with options, returning the report of each
+/// conversion as [`convert_docx_to_pdf_report`] does.
+pub fn convert_many_with_options(
+    jobs: &[(PathBuf, PathBuf)],
+    parallelism: usize,
+    options: &ConversionOptions,
+) -> Vec<Result<ConversionReport, Error>> {
+    fonts::warm_font_index();
+    let threads = match parallelism {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    // Each worker takes the next job until none are left
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, Result<ConversionReport, Error>)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.min(jobs.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((input, output)) = jobs.get(index) else {
+                            return done;
+                        };
+                        done.push((index, convert_docx_to_pdf_report(input, output, options)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+    results.sort_by_key(|&(index, _)| index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Convert a DOCX held in memory, such as an upload, and return the PDF
+/// bytes without touching the filesystem.
+pub fn convert_docx_bytes(input: &[u8]) -> Result<Vec<u8>, Error> {
+    convert_docx_bytes_with_options(input, &ConversionOptions::default())
+}
+
+/// Like [`convert_docx_bytes`], with options. FILENAME fields come out empty,
+/// as there is no file name.
+pub fn convert_docx_bytes_with_options(
+    input: &[u8],
+    options: &ConversionOptions,
+) -> Result<Vec<u8>, Error> {
+    convert_docx_bytes_report(input, options).map(|(bytes, _)| bytes)
+}
+
+/// Like [`convert_docx_bytes_with_options`], also returning the warnings as
+/// [`convert_docx_to_pdf_report`] does.
+pub fn convert_docx_bytes_report(
+    input: &[u8],
+    options: &ConversionOptions,
+) -> Result<(Vec<u8>, ConversionReport), Error> {
+    let mut found = Vec::new();
+    let doc = docx::parse_reader(std::io::Cursor::new(input), String::new(), &mut found)?;
+    let bytes = render(doc, options, &mut found)?;
+    Ok((bytes, ConversionReport::new(found)))
+}
+
+/// Parse a DOCX file into the document model, which can be inspected or
+/// changed before rendering it with [`render_pdf`].
+///
+/// ```
+/// use std::path::Path;
+///
+/// let mut doc = docxside_pdf::parse_docx(Path::new("tests/fixtures/case1/input.docx"))?;
+/// for section in &mut doc.sections {
+///     section.margin_left = 36.0;
+/// }
+/// let pdf = docxside_pdf::render_pdf(&doc)?;
+/// assert!(pdf.starts_with(b"%PDF-"));
+/// # Ok::<(), docxside_pdf::Error>(())
+/// ```
+pub fn parse_docx(path: &Path) -> Result<Document, Error> {
+    docx::parse(path, &mut Vec::new())
+}
+
+/// Render a parsed document to PDF bytes.
+pub fn render_pdf(doc: &Document) -> Result<Vec<u8>, Error> {
+    render_pdf_with_options(doc, &ConversionOptions::default())
+}
+
+/// Like [`render_pdf`], with options. Runs are split for fallback fonts on a
+/// copy, so `doc` is left as it is.
+pub fn render_pdf_with_options(
+    doc: &Document,
+    options: &ConversionOptions,
+) -> Result<Vec<u8>, Error> {
+    render(doc.clone(), options, &mut Vec::new())
+}
+
+fn render(
+    mut doc: Document,
+    options: &ConversionOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<u8>, Error> {
+    if let Some(margins) = options.override_margins {
+        for sect in &mut doc.sections {
+            sect.margin_top = margins.top;
+            sect.margin_bottom = margins.bottom;
+            sect.margin_left = margins.left;
+            sect.margin_right = margins.right;
+        }
+    }
+    fonts::apply_font_fallbacks(&mut doc, &options.fallback_fonts);
+    pdf::render(&mut doc, options, warnings)
 }
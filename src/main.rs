@@ -1,26 +1,77 @@
 use clap::Parser;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "docxside-pdf", about = "Convert DOCX files to PDF")]
 struct Args {
-    /// Input DOCX file
-    input: PathBuf,
-    /// Output PDF file (defaults to input with .pdf extension)
+    /// Input DOCX files, each converted to a PDF beside it. A single input
+    /// may be followed by its output file.
+    #[arg(required = true, value_name = "FILES")]
+    paths: Vec<PathBuf>,
+    /// Output PDF file, or with several inputs the directory to write their PDFs to
+    #[arg(long, short)]
     output: Option<PathBuf>,
+    /// Number of files to convert at once (defaults to one per core)
+    #[arg(long, short)]
+    jobs: Option<usize>,
+    /// Leave the current time out of the PDF, for byte-identical output
+    #[arg(long)]
+    no_dates: bool,
+    /// Write PDF/A-2b for archiving
+    #[arg(long)]
+    pdfa: bool,
+    /// Write a tagged PDF with a structure tree for screen readers
+    #[arg(long)]
+    tagged: bool,
+    /// Only write these pages, e.g. 2-4 or 3
+    #[arg(long, value_parser = parse_page_range)]
+    pages: Option<RangeInclusive<usize>>,
+    /// Use this margin (in points) on all sides instead of the document's margins
+    #[arg(long)]
+    margin: Option<f32>,
+    /// Fail, without keeping the PDF, if a conversion reports any warnings
+    #[arg(long)]
+    warnings_as_errors: bool,
 }
 
-fn available_path(path: PathBuf) -> PathBuf {
-    if !path.exists() {
+fn parse_page_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let (first, last) = s.split_once('-').unwrap_or((s, s));
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("invalid page number: {n}"))
+    };
+    let (first, last) = (parse(first)?, parse(last)?);
+    if first > last {
+        return Err(format!("page range ends before it starts: {s}"));
+    }
+    Ok(first..=last)
+}
+
+/// `path`, or if a file or an earlier job already has it, the first free
+/// `name(2).ext`, `name(3).ext`...
+fn available_path(path: PathBuf, taken: &[(PathBuf, PathBuf)]) -> PathBuf {
+    let free = |path: &PathBuf| !path.exists() && !taken.iter().any(|(_, output)| output == path);
+    if free(&path) {
         return path;
     }
-    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
-    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
     let parent = path.parent().unwrap_or(std::path::Path::new("."));
     let mut n = 2;
     loop {
         let candidate = parent.join(format!("{stem}({n}){ext}"));
-        if !candidate.exists() {
+        if free(&candidate) {
             return candidate;
         }
         n += 1;
@@ -31,23 +82,94 @@ fn main() {
     env_logger::init();
     let args = Args::parse();
 
-    if !args.input.exists() {
-        eprintln!("Error: file not found: {}", args.input.display());
+    // A single input may be followed by its output, unless that is another DOCX
+    let mut inputs = args.paths;
+    let output = match inputs.as_slice() {
+        [_, second]
+            if args.output.is_none()
+                && !second
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("docx")) =>
+        {
+            inputs.pop()
+        }
+        _ => args.output,
+    };
+    if inputs.len() > 1
+        && let Some(dir) = &output
+        && !dir.is_dir()
+    {
+        eprintln!("Error: not a directory: {}", dir.display());
         std::process::exit(1);
     }
-    if !args.input.is_file() {
-        eprintln!("Error: not a file: {}", args.input.display());
-        std::process::exit(1);
+    for input in &inputs {
+        if !input.exists() {
+            eprintln!("Error: file not found: {}", input.display());
+            std::process::exit(1);
+        }
+        if !input.is_file() {
+            eprintln!("Error: not a file: {}", input.display());
+            std::process::exit(1);
+        }
+    }
+    let mut jobs: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for input in &inputs {
+        let path = match &output {
+            Some(dir) if inputs.len() > 1 => {
+                dir.join(input.with_extension("pdf").file_name().unwrap_or_default())
+            }
+            Some(file) => file.clone(),
+            None => input.with_extension("pdf"),
+        };
+        let path = available_path(path, &jobs);
+        jobs.push((input.clone(), path));
     }
 
-    let output = args
-        .output
-        .unwrap_or_else(|| args.input.with_extension("pdf"));
-    let output = available_path(output);
+    let options = docxside_pdf::ConversionOptions {
+        omit_dates: args.no_dates,
+        pdfa: args.pdfa,
+        tagged: args.tagged,
+        pages: args.pages,
+        override_margins: args.margin.map(docxside_pdf::Margins::uniform),
+        ..Default::default()
+    };
+    let results = docxside_pdf::convert_many_with_options(&jobs, args.jobs.unwrap_or(0), &options);
 
-    if let Err(e) = docxside_pdf::convert_docx_to_pdf(&args.input, &output) {
-        eprintln!("Error: {e}");
+    // Messages name the input only when there are several
+    let prefix = |input: &PathBuf| {
+        if jobs.len() > 1 {
+            format!("{}: ", input.display())
+        } else {
+            String::new()
+        }
+    };
+    let mut failed = false;
+    for ((input, output), result) in jobs.iter().zip(results) {
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Error: {}{e}", prefix(input));
+                failed = true;
+                continue;
+            }
+        };
+        for warning in &report.warnings {
+            eprintln!("Warning: {}{warning}", prefix(input));
+        }
+        if args.warnings_as_errors && !report.warnings.is_empty() {
+            let _ = std::fs::remove_file(output);
+            let count = report.warnings.len();
+            let plural = if count == 1 { "" } else { "s" };
+            eprintln!(
+                "Error: {}{count} warning{plural}, treated as errors",
+                prefix(input)
+            );
+            failed = true;
+            continue;
+        }
+        println!("Converted to {}", output.display());
+    }
+    if failed {
         std::process::exit(1);
     }
-    println!("Converted to {}", output.display());
 }
@@ -1,4 +1,11 @@
+//! The document model a DOCX is parsed into and the PDF is rendered from.
+//! Lengths are in points.
+
+use std::fmt;
+use std::sync::Arc;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Alignment {
     Left,
     Center,
@@ -7,14 +14,18 @@ pub enum Alignment {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum TabAlignment {
     Left,
     Center,
     Right,
     Decimal,
+    Bar,   // not a stop: a vertical rule drawn at the position
+    Clear, // removes an inherited stop, and the default stop at the position
 }
 
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct TabStop {
     pub position: f32,
     pub alignment: TabAlignment,
@@ -22,17 +33,63 @@ pub struct TabStop {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum UnderlineStyle {
+    None,
+    Single,
+    Words,
+    Double,
+    Thick,
+    Dotted,
+    Dash,
+    DotDash,
+    Wave,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LinkTarget {
+    Url(String),    // external hyperlink (r:id resolved through the relationships)
+    Anchor(String), // internal hyperlink to a bookmark (w:anchor)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum VertAlign {
     Baseline,
     Superscript,
     Subscript,
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct HeaderFooter {
     pub paragraphs: Vec<Paragraph>,
 }
 
-pub struct Document {
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum SectionBreak {
+    NextPage, // also used for evenPage/oddPage
+    Continuous,
+}
+
+/// `w:vAlign` of a section: where content sits between the top and bottom margins.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PageVAlign {
+    Top, // also used for "both", which we don't justify
+    Center,
+    Bottom,
+}
+
+/// Page setup of one `w:sectPr`, applying to blocks from `first_block` up to
+/// the next section's `first_block`.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Section {
+    pub first_block: usize,
+    pub start: SectionBreak,
     pub page_width: f32,
     pub page_height: f32,
     pub margin_top: f32,
@@ -40,35 +97,151 @@ pub struct Document {
     pub margin_left: f32,
     pub margin_right: f32,
     pub line_pitch: f32,
-    pub line_spacing: f32, // auto line spacing factor (e.g. 278/240)
-    pub blocks: Vec<Block>,
-    /// Fonts embedded in the DOCX (deobfuscated TTF/OTF bytes).
-    /// Key: (lowercase_font_name, bold, italic)
-    pub embedded_fonts: std::collections::HashMap<(String, bool, bool), Vec<u8>>,
-    pub header_default: Option<HeaderFooter>,
-    pub header_first: Option<HeaderFooter>,
-    pub footer_default: Option<HeaderFooter>,
-    pub footer_first: Option<HeaderFooter>,
+    pub columns: usize,                   // w:cols w:num, equal-width columns
+    pub column_space: f32,                // gap between columns in points
+    pub page_number_format: String,       // w:pgNumType w:fmt, e.g. "lowerRoman"
+    pub page_number_start: Option<usize>, // w:pgNumType w:start restarts numbering
+    pub page_borders: Option<PageBorders>,
+    pub v_align: PageVAlign,
+    // Sections without their own references inherit the previous section's
+    pub header_default: Option<Arc<HeaderFooter>>,
+    pub header_first: Option<Arc<HeaderFooter>>,
+    pub header_even: Option<Arc<HeaderFooter>>,
+    pub footer_default: Option<Arc<HeaderFooter>>,
+    pub footer_first: Option<Arc<HeaderFooter>>,
+    pub footer_even: Option<Arc<HeaderFooter>>,
     pub header_margin: f32,
     pub footer_margin: f32,
     pub different_first_page: bool,
 }
 
+/// Line height rule of `w:spacing @line`/`@lineRule`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LineSpacing {
+    Auto(f32),    // factor of the font's natural line height (e.g. 278/240)
+    Exact(f32),   // line height in points, regardless of the font
+    AtLeast(f32), // minimum line height in points
+}
+
+/// Document properties from `docProps/core.xml`.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Metadata {
+    pub title: Option<String>,             // dc:title
+    pub author: Option<String>,            // dc:creator
+    pub subject: Option<String>,           // dc:subject
+    pub keywords: Option<String>,          // cp:keywords
+    pub created: Option<jiff::Timestamp>,  // dcterms:created
+    pub modified: Option<jiff::Timestamp>, // dcterms:modified
+}
+
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct Document {
+    pub line_spacing: LineSpacing, // from w:pPrDefault
+    pub blocks: Vec<Block>,
+    pub sections: Vec<Section>,     // never empty, ordered by first_block
+    pub even_and_odd_headers: bool, // w:evenAndOddHeaders in settings.xml
+    pub decimal_separator: char,    // from the document language, for decimal tabs
+    pub lang: Option<String>,       // default w:lang of the text, e.g. "en-US"
+    /// Fonts embedded in the DOCX (deobfuscated TTF/OTF bytes).
+    /// Key: (lowercase_font_name, bold, italic)
+    pub embedded_fonts: std::collections::BTreeMap<(String, bool, bool), Vec<u8>>,
+    pub file_name: String, // input file name, for FILENAME fields
+    pub metadata: Metadata,
+    pub endnotes_start: Option<usize>, // index in `blocks` where appended endnote paragraphs begin
+}
+
+// Font and image data are shown by size only
+impl fmt::Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Document")
+            .field("line_spacing", &self.line_spacing)
+            .field("blocks", &self.blocks)
+            .field("sections", &self.sections)
+            .field("even_and_odd_headers", &self.even_and_odd_headers)
+            .field("decimal_separator", &self.decimal_separator)
+            .field("lang", &self.lang)
+            .field("embedded_fonts", &self.embedded_fonts.keys())
+            .field("file_name", &self.file_name)
+            .field("metadata", &self.metadata)
+            .field("endnotes_start", &self.endnotes_start)
+            .finish()
+    }
+}
+
+/// Encoding of the bytes of an `EmbeddedImage`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+}
+
+#[derive(Clone)]
+#[non_exhaustive]
 pub struct EmbeddedImage {
     pub data: Vec<u8>,
+    pub format: ImageFormat,
     pub pixel_width: u32,
     pub pixel_height: u32,
-    pub display_width: f32,  // points
-    pub display_height: f32, // points
+    pub display_width: f32,       // points
+    pub display_height: f32,      // points
+    pub alt_text: Option<String>, // wp:docPr descr, or the VML shape's alt
 }
 
-#[derive(Clone)]
+impl fmt::Debug for EmbeddedImage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmbeddedImage")
+            .field("data", &format_args!("<{} bytes>", self.data.len()))
+            .field("format", &self.format)
+            .field("pixel_width", &self.pixel_width)
+            .field("pixel_height", &self.pixel_height)
+            .field("display_width", &self.display_width)
+            .field("display_height", &self.display_height)
+            .field("alt_text", &self.alt_text)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct BorderBottom {
     pub width_pt: f32,     // line thickness in points
     pub space_pt: f32,     // gap between text and border in points
     pub color: [u8; 3],    // RGB
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum BorderStyle {
+    Single, // also used for styles we don't draw specially
+    Double,
+}
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PageBorder {
+    pub style: BorderStyle,
+    pub width_pt: f32, // width of each line in points
+    pub space_pt: f32, // distance from the text or page edge in points
+    pub color: [u8; 3],
+}
+
+/// `w:pgBorders` of a section; drawn on every page without affecting layout.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PageBorders {
+    pub offset_from_page: bool, // w:offsetFrom="page", otherwise measured from the text
+    pub top: Option<PageBorder>,
+    pub left: Option<PageBorder>,
+    pub bottom: Option<PageBorder>,
+    pub right: Option<PageBorder>,
+}
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Paragraph {
     pub runs: Vec<Run>,
     pub space_before: f32,
@@ -77,50 +250,263 @@ pub struct Paragraph {
     pub alignment: Alignment,
     pub indent_left: f32,
     pub indent_hanging: f32,
-    pub list_label: String,
+    pub indent_right: f32,
+    pub indent_first_line: f32, // w:firstLine, added to the left indent of line 0
+    pub list_label: Option<Box<ListLabel>>,
     pub contextual_spacing: bool,
     pub keep_next: bool,
-    pub line_spacing: Option<f32>, // per-paragraph override (e.g. 240/240 = 1.0)
+    pub keep_lines: bool, // w:keepLines: don't split the paragraph across pages
+    pub widow_control: bool, // w:widowControl: no single line left on either side of a break
+    pub line_spacing: Option<LineSpacing>, // per-paragraph override of the document default
     pub image: Option<EmbeddedImage>,
     pub border_bottom: Option<BorderBottom>,
     pub page_break_before: bool,
     pub tab_stops: Vec<TabStop>,
+    pub bookmarks: Vec<String>, // w:bookmarkStart names anchored at this paragraph
+    pub text_boxes: Vec<TextBox>, // floating text boxes anchored in this paragraph
+    pub outline_level: Option<u8>, // 0 for Heading 1; headings get a PDF outline entry
+}
+
+impl Paragraph {
+    /// A left-aligned paragraph of `runs` without spacing, indents or
+    /// borders, using the document's line spacing.
+    pub fn new(runs: Vec<Run>) -> Self {
+        Paragraph {
+            runs,
+            space_before: 0.0,
+            space_after: 0.0,
+            content_height: 0.0,
+            alignment: Alignment::Left,
+            indent_left: 0.0,
+            indent_hanging: 0.0,
+            indent_right: 0.0,
+            indent_first_line: 0.0,
+            list_label: None,
+            contextual_spacing: false,
+            keep_next: false,
+            keep_lines: false,
+            widow_control: true,
+            line_spacing: None,
+            image: None,
+            border_bottom: None,
+            page_break_before: false,
+            tab_stops: vec![],
+            bookmarks: vec![],
+            text_boxes: vec![],
+            outline_level: None,
+        }
+    }
+}
+
+/// What a floating object's position is measured from (`wp:positionH`/`wp:positionV`
+/// `@relativeFrom`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum FloatRelative {
+    Page,
+    Margin,
+    Paragraph, // the anchor paragraph's column, or its top edge vertically
+}
+
+/// Position of a floating object along one axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum FloatPosition {
+    Offset(f32), // wp:posOffset in points
+    Start,       // wp:align left/top
+    Center,
+    End, // wp:align right/bottom
+}
+
+/// A text box (`wps:txbx`) drawn at its anchored position without affecting
+/// the flow of the surrounding text.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct TextBox {
+    pub paragraphs: Vec<Paragraph>,
+    pub width: f32,  // points
+    pub height: f32, // points
+    pub h_relative: FloatRelative,
+    pub h_position: FloatPosition,
+    pub v_relative: FloatRelative,
+    pub v_position: FloatPosition,
+    pub insets: [f32; 4], // wps:bodyPr left, top, right, bottom insets in points
+}
+
+/// What separates a list label from the paragraph text (`w:suff`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LabelSuffix {
+    Tab, // text starts at the left indent, label sits in the hanging indent
+    Space,
+    Nothing,
+}
+
+/// The number or bullet of a list paragraph.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ListLabel {
+    pub run: Run,             // label text with the level's run properties applied
+    pub alignment: Alignment, // w:lvlJc, relative to the number position
+    pub suffix: LabelSuffix,
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Run {
     pub text: String,
     pub font_size: f32,
     pub font_name: String,
     pub bold: bool,
     pub italic: bool,
-    pub underline: bool,
+    pub underline: UnderlineStyle,
+    pub underline_color: Option<[u8; 3]>, // None = same as text color
     pub strikethrough: bool,
     pub color: Option<[u8; 3]>, // None = automatic (black)
     pub is_tab: bool,
+    pub is_line_break: bool, // w:br (text wrapping) — forces a new line
+    pub is_page_break: bool, // w:br type="page" after text in the same paragraph
     pub vertical_align: VertAlign,
     pub field_code: Option<FieldCode>,
+    pub caps: bool,
+    pub small_caps: bool,
+    pub hidden: bool, // w:vanish
+    pub link: Option<LinkTarget>,
+    pub char_spacing: f32, // points added after each character (w:spacing in rPr)
+    pub scale: f32,        // horizontal glyph scaling (w:w), 1.0 = 100%
+    pub kerning: bool,     // w:kern applies at this font size
+}
+
+impl Run {
+    /// Plain black text in `font_name` at `font_size` points.
+    pub fn new(text: impl Into<String>, font_name: impl Into<String>, font_size: f32) -> Self {
+        Run {
+            text: text.into(),
+            font_size,
+            font_name: font_name.into(),
+            bold: false,
+            italic: false,
+            underline: UnderlineStyle::None,
+            underline_color: None,
+            strikethrough: false,
+            color: None,
+            is_tab: false,
+            is_line_break: false,
+            is_page_break: false,
+            vertical_align: VertAlign::Baseline,
+            field_code: None,
+            caps: false,
+            small_caps: false,
+            hidden: false,
+            link: None,
+            char_spacing: 0.0,
+            scale: 1.0,
+            kerning: false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum FieldCode {
     Page,
     NumPages,
+    Date(Option<String>), // date-time picture from the \@ switch
+    Time(Option<String>),
+    Filename,
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct Table {
     pub col_widths: Vec<f32>, // points
     pub rows: Vec<TableRow>,
+    pub header_rows: usize, // leading rows marked w:tblHeader, repeated on each page
+    pub alignment: Alignment, // w:jc of the table within the text area
+    pub indent: f32,        // w:tblInd in points, for left-aligned tables
+    pub width_pct: Option<f32>, // w:tblW of type pct, as a fraction of the text width
+}
+
+/// `w:trHeight w:hRule` of a table row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum RowHeightRule {
+    Auto, // sized to content
+    AtLeast,
+    Exact,
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct TableRow {
     pub cells: Vec<TableCell>,
+    pub height: f32, // points, used unless the rule is Auto
+    pub height_rule: RowHeightRule,
+    pub cant_split: bool, // w:cantSplit: the row moves to the next page whole
+}
+
+/// One edge of a table cell border.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct CellBorder {
+    pub width_pt: f32,
+    pub color: [u8; 3],
+}
+
+/// Borders of a table cell after resolving `w:tcBorders` against the table's
+/// `w:tblBorders`; `None` edges are not drawn.
+#[derive(Clone, Copy, Debug, Default)]
+#[non_exhaustive]
+pub struct CellBorders {
+    pub top: Option<CellBorder>,
+    pub left: Option<CellBorder>,
+    pub bottom: Option<CellBorder>,
+    pub right: Option<CellBorder>,
+}
+
+/// Space between a cell's edges and its text (`w:tcMar`/`w:tblCellMar`), in points.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct CellMargins {
+    pub top: f32,
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+}
+
+/// `w:vAlign` of a table cell: where the text sits within the row height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum CellVAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Role of a cell in a vertical merge (`w:vMerge`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum VMerge {
+    None,
+    Restart,  // top cell of a merged group, holds the content
+    Continue, // covered by the restart cell above it
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct TableCell {
-    pub width: f32, // points
+    pub width: f32,       // points
+    pub grid_span: usize, // w:gridSpan, number of grid columns covered
+    pub v_merge: VMerge,
+    pub shading: Option<[u8; 3]>, // w:shd fill, or the table's default
+    pub borders: CellBorders,
+    pub margins: CellMargins,
+    pub v_align: Option<CellVAlign>, // None: top, or centered for merged cells
     pub paragraphs: Vec<Paragraph>,
 }
 
+#[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Block {
     Paragraph(Paragraph),
     Table(Table),
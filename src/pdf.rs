@@ -1,24 +1,57 @@
-use std::collections::HashMap;
-
-use pdf_writer::{Content, Filter, Name, Pdf, Rect, Ref, Str};
-
-use crate::error::Error;
-use crate::fonts::{font_key, primary_font_name, register_font, to_winansi_bytes, FontEntry};
+use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::rc::Rc;
+
+use pdf_writer::types::{
+    ActionType, AnnotationFlags, AnnotationType, LineCapStyle, NumberingStyle, OutputIntentSubtype,
+    StructRole, TabOrder, TextRenderingMode,
+};
+use pdf_writer::writers::PageLabel;
+use pdf_writer::{Content, Date, Filter, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::ConversionOptions;
+use crate::docx::format_number;
+use crate::error::{Error, Warning};
+use crate::fonts::{
+    EncodedText, FontEntry, Synthetic, font_key, primary_font_name, register_font,
+    write_composite_font, write_font_file, write_to_unicode,
+};
 use crate::model::{
-    Alignment, Block, Document, FieldCode, HeaderFooter, Run, TabAlignment, TabStop, Table,
+    Alignment, Block, BorderStyle, CellVAlign, Document, EmbeddedImage, FieldCode, FloatPosition,
+    FloatRelative, HeaderFooter, ImageFormat, LabelSuffix, LineSpacing, LinkTarget, ListLabel,
+    Metadata, PageBorder, PageBorders, PageVAlign, Paragraph, RowHeightRule, Run, Section,
+    SectionBreak, TabAlignment, TabStop, Table, TableCell, TableRow, UnderlineStyle, VMerge,
     VertAlign,
 };
+use crate::png;
+use crate::tags::{Tags, write_struct_tree};
 
 struct WordChunk {
     pdf_font: String,
-    text: String,
+    text: Rc<EncodedText>,    // encoded for `pdf_font`
+    space_width: Option<f32>, // space of `pdf_font` at 1000 units/em; None when it has no space code
     font_size: f32,
     color: Option<[u8; 3]>,
     x_offset: f32, // x relative to line start
     width: f32,
-    underline: bool,
+    underline: UnderlineStyle,
+    underline_color: Option<[u8; 3]>,
+    underline_position: f32,  // top of the underline from the baseline, in em
+    underline_thickness: f32, // in em
     strikethrough: bool,
-    y_offset: f32, // vertical offset for superscript/subscript
+    strikeout_position: f32, // top of the strikethrough from the baseline, in em
+    strikeout_thickness: f32, // in em
+    y_offset: f32,           // vertical offset for superscript/subscript
+    char_spacing: f32,
+    scale: f32,
+    synthetic: Synthetic,
+    link: Option<LinkTarget>,
+}
+
+/// Zlib-compress a stream for `FlateDecode`.
+pub(crate) fn deflate(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
 }
 
 fn effective_font_size(run: &Run) -> f32 {
@@ -37,6 +70,215 @@ fn vert_y_offset(run: &Run) -> f32 {
 }
 
 const DEFAULT_TAB_INTERVAL: f32 = 36.0; // 0.5 inches
+// Underline and strikethrough placement for fonts without post/OS2 metrics
+// (the Helvetica substitute), in em
+const DEFAULT_UNDERLINE_POSITION: f32 = -0.12;
+const DEFAULT_UNDERLINE_THICKNESS: f32 = 0.05;
+const DEFAULT_STRIKEOUT_POSITION: f32 = 0.35;
+const DEFAULT_STRIKEOUT_THICKNESS: f32 = 0.05;
+const SMALL_CAPS_SCALE: f32 = 0.8;
+
+/// Whitespace that permits a line break. Non-breaking spaces stay inside the
+/// word so that e.g. "10\u{A0}kg" is measured and wrapped as a single unit.
+fn is_break_space(c: char) -> bool {
+    c.is_whitespace() && !matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}')
+}
+
+/// Split run text into words at breakable whitespace, each paired with the
+/// number of whitespace characters before it, so that runs of spaces keep
+/// their width.
+fn split_words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let start = rest.find(|c: char| !is_break_space(c))?;
+        let spaces = rest[..start].chars().count();
+        let tail = &rest[start..];
+        let end = tail.find(is_break_space).unwrap_or(tail.len());
+        rest = &tail[end..];
+        Some((spaces, &tail[..end]))
+    })
+}
+
+/// Number of whitespace characters after the last word of `text`.
+fn trailing_spaces(text: &str) -> usize {
+    text.chars()
+        .rev()
+        .take_while(|&c| is_break_space(c))
+        .count()
+}
+
+/// Whitespace that runs on from the end of a run into the next one: a run
+/// of only spaces adds to what came before it, otherwise its own trailing
+/// spaces start afresh.
+fn carry_spaces(pending: f32, text: &str, space_w: f32) -> f32 {
+    let trailing = trailing_spaces(text) as f32 * space_w;
+    if text.chars().all(is_break_space) {
+        pending + trailing
+    } else {
+        trailing
+    }
+}
+
+fn text_width(text: &str, entry: &FontEntry, font_size: f32) -> f32 {
+    entry.encode(text).width_1000 * font_size / 1000.0
+}
+
+/// A measured piece of a word. Words normally produce one piece; small caps
+/// split a word wherever the case changes, since lowercase letters are drawn
+/// as reduced-size capitals. The font entry encodes each distinct text once,
+/// and the same bytes are both measured and drawn.
+struct WordPiece {
+    text: Rc<EncodedText>,
+    font_size: f32,
+    width: f32,
+}
+
+const SOFT_HYPHEN: char = '\u{00AD}';
+
+fn word_pieces(word: &str, run: &Run, entry: &FontEntry, font_size: f32) -> Vec<WordPiece> {
+    // Soft hyphens are invisible unless a line break is taken at one
+    let stripped;
+    let word = if word.contains(SOFT_HYPHEN) {
+        stripped = word.replace(SOFT_HYPHEN, "");
+        stripped.as_str()
+    } else {
+        word
+    };
+    let mut raw: Vec<(Cow<str>, f32)> = Vec::new();
+    if run.caps {
+        raw.push((word.to_uppercase().into(), font_size));
+    } else if run.small_caps {
+        for c in word.chars() {
+            let (text, size) = if c.is_lowercase() {
+                (
+                    c.to_uppercase().collect::<String>(),
+                    font_size * SMALL_CAPS_SCALE,
+                )
+            } else {
+                (c.to_string(), font_size)
+            };
+            match raw.last_mut() {
+                Some((t, s)) if *s == size => t.to_mut().push_str(&text),
+                _ => raw.push((text.into(), size)),
+            }
+        }
+    } else {
+        raw.push((word.into(), font_size));
+    }
+    raw.into_iter()
+        .map(|(text, font_size)| {
+            let encoded = if run.kerning {
+                entry.encode_kerned(&text)
+            } else {
+                entry.encode(&text)
+            };
+            WordPiece {
+                width: encoded.width_1000 * font_size / 1000.0 * run.scale
+                    + run.char_spacing * encoded.glyph_count as f32,
+                text: encoded,
+                font_size,
+            }
+        })
+        .collect()
+}
+
+/// Find the last soft hyphen in `word` where the head plus a visible hyphen fits
+/// in `available`. Returns the hyphenated head pieces and the remaining tail.
+fn soft_hyphen_break<'w>(
+    word: &'w str,
+    run: &Run,
+    entry: &FontEntry,
+    font_size: f32,
+    available: f32,
+) -> Option<(Vec<WordPiece>, &'w str)> {
+    word.char_indices()
+        .filter(|&(_, c)| c == SOFT_HYPHEN)
+        .map(|(i, _)| i)
+        .rev()
+        .filter(|&i| i > 0)
+        .find_map(|i| {
+            let head = word_pieces(&format!("{}-", &word[..i]), run, entry, font_size);
+            let head_w: f32 = head.iter().map(|p| p.width).sum();
+            (head_w <= available).then(|| (head, &word[i + SOFT_HYPHEN.len_utf8()..]))
+        })
+}
+
+/// Split a word too wide for a whole line after its last character that still
+/// fits in `available`, the way Word breaks overlong words. At least one
+/// character is kept on the line. Returns the head pieces and the remaining tail.
+fn overflow_break<'w>(
+    word: &'w str,
+    run: &Run,
+    entry: &FontEntry,
+    font_size: f32,
+    available: f32,
+) -> Option<(Vec<WordPiece>, &'w str)> {
+    let mut ends = word.char_indices().map(|(i, c)| i + c.len_utf8());
+    let mut split = ends.next()?;
+    for end in ends {
+        let head_w: f32 = word_pieces(&word[..end], run, entry, font_size)
+            .iter()
+            .map(|p| p.width)
+            .sum();
+        if head_w > available {
+            break;
+        }
+        split = end;
+    }
+    let (head, tail) = word.split_at(split);
+    (!tail.is_empty()).then(|| (word_pieces(head, run, entry, font_size), tail))
+}
+
+/// Push one chunk per piece starting at `x`, returning the x after the word.
+fn push_word_chunks(
+    chunks: &mut Vec<WordChunk>,
+    pieces: Vec<WordPiece>,
+    run: &Run,
+    entry: &FontEntry,
+    mut x: f32,
+) -> f32 {
+    let y_off = vert_y_offset(run);
+    for piece in pieces {
+        chunks.push(WordChunk {
+            pdf_font: entry.pdf_font(piece.text.composite).to_string(),
+            space_width: (!piece.text.composite).then(|| entry.space_width()),
+            text: piece.text,
+            font_size: piece.font_size,
+            color: run.color,
+            x_offset: x,
+            width: piece.width,
+            underline: run.underline,
+            underline_color: run.underline_color,
+            underline_position: entry
+                .underline_position
+                .unwrap_or(DEFAULT_UNDERLINE_POSITION),
+            underline_thickness: entry
+                .underline_thickness
+                .unwrap_or(DEFAULT_UNDERLINE_THICKNESS),
+            strikethrough: run.strikethrough,
+            strikeout_position: entry
+                .strikeout_position
+                .unwrap_or(DEFAULT_STRIKEOUT_POSITION),
+            strikeout_thickness: entry
+                .strikeout_thickness
+                .unwrap_or(DEFAULT_STRIKEOUT_THICKNESS),
+            y_offset: y_off,
+            char_spacing: run.char_spacing,
+            scale: run.scale,
+            synthetic: entry.synthetic,
+            link: run.link.clone(),
+        });
+        x += piece.width;
+    }
+    x
+}
+
+/// Clickable area of a hyperlink on a single line, in page coordinates.
+struct LinkArea {
+    rect: Rect,
+    target: LinkTarget,
+    tag: Option<usize>, // Link structure element, in tagged PDF
+}
 
 struct TextLine {
     chunks: Vec<WordChunk>,
@@ -55,79 +297,107 @@ fn finish_line(chunks: &mut Vec<WordChunk>) -> TextLine {
 /// Handles cross-run contiguous text correctly: no space is inserted between
 /// runs unless the preceding text ended with whitespace or the new run starts
 /// with whitespace (e.g., "bold" + ", " → "bold," not "bold ,").
-fn build_paragraph_lines(
-    runs: &[Run],
+/// The first line starts at `first_line_indent`, leaving it that much narrower;
+/// its chunk offsets include the indent, so alignment and justification just
+/// see a line with a wider start.
+fn build_paragraph_lines<R: Borrow<Run>>(
+    runs: &[R],
     seen_fonts: &HashMap<String, FontEntry>,
     max_width: f32,
+    first_line_indent: f32,
 ) -> Vec<TextLine> {
     let mut lines: Vec<TextLine> = Vec::new();
     let mut current_chunks: Vec<WordChunk> = Vec::new();
-    let mut current_x: f32 = 0.0;
-    let mut prev_ended_with_ws = false;
-    let mut prev_space_w: f32 = 0.0;
-
-    for run in runs {
+    let mut current_x: f32 = first_line_indent;
+    // Width of the whitespace at the end of the previous runs, before the next word
+    let mut pending_gap: f32 = 0.0;
+    // Whitespace is dropped at the start of a wrapped line, but kept at the
+    // start of the paragraph and after a manual line break
+    let mut wrapped = false;
+    let mut ended_with_break = false;
+
+    for run in runs.iter().map(Borrow::borrow) {
+        if run.hidden {
+            continue;
+        }
+        if run.is_line_break {
+            lines.push(finish_line(&mut current_chunks));
+            current_x = 0.0;
+            pending_gap = 0.0;
+            wrapped = false;
+            ended_with_break = true;
+            continue;
+        }
         if run.is_tab {
             continue; // tabs handled in build_tabbed_line
         }
+        ended_with_break &= run.text.trim().is_empty();
         let key = font_key(run);
         let entry = seen_fonts.get(&key).expect("font registered");
         let eff_fs = effective_font_size(run);
-        let space_w = entry.widths_1000[0] * eff_fs / 1000.0;
-        let starts_with_ws = run.text.starts_with(char::is_whitespace);
-        let y_off = vert_y_offset(run);
+        let space_w = entry.space_width() * eff_fs / 1000.0 * run.scale + run.char_spacing;
 
-        for (i, word) in run.text.split_whitespace().enumerate() {
-            let ww: f32 = to_winansi_bytes(word)
-                .iter()
-                .filter(|&&b| b >= 32)
-                .map(|&b| entry.widths_1000[(b - 32) as usize] * eff_fs / 1000.0)
-                .sum();
-
-            let need_space = !current_chunks.is_empty()
-                && (i > 0 || starts_with_ws || prev_ended_with_ws);
-
-            // Use the space width from the run that owns the space character:
-            // within a run (i > 0) or leading ws → this run's space_w;
-            // trailing ws from previous run → previous run's space_w
-            let effective_space_w = if i > 0 || starts_with_ws {
-                space_w
-            } else {
-                prev_space_w
-            };
+        for (i, (spaces, word)) in split_words(&run.text).enumerate() {
+            let mut word = word;
+            let mut pieces = word_pieces(word, run, entry, eff_fs);
+            let mut ww: f32 = pieces.iter().map(|p| p.width).sum();
 
-            let proposed_x = if need_space {
-                current_x + effective_space_w
+            // Each space is as wide as in the run that owns it: trailing
+            // whitespace of earlier runs was measured in their own font
+            let carried = if i == 0 { pending_gap } else { 0.0 };
+            let gap = carried + spaces as f32 * space_w;
+
+            let mut proposed_x = if !current_chunks.is_empty() || !wrapped {
+                current_x + gap
             } else {
                 current_x
             };
 
+            // Take a soft-hyphen break if the word would otherwise overflow
+            while proposed_x + ww > max_width
+                && let Some((head, tail)) =
+                    soft_hyphen_break(word, run, entry, eff_fs, max_width - proposed_x)
+            {
+                push_word_chunks(&mut current_chunks, head, run, entry, proposed_x);
+                lines.push(finish_line(&mut current_chunks));
+                wrapped = true;
+                proposed_x = 0.0;
+                word = tail;
+                pieces = word_pieces(word, run, entry, eff_fs);
+                ww = pieces.iter().map(|p| p.width).sum();
+            }
+
             if !current_chunks.is_empty() && proposed_x + ww > max_width {
                 lines.push(finish_line(&mut current_chunks));
+                wrapped = true;
                 current_x = 0.0;
             } else {
                 current_x = proposed_x;
             }
 
-            current_chunks.push(WordChunk {
-                pdf_font: entry.pdf_name.clone(),
-                text: word.to_string(),
-                font_size: eff_fs,
-                color: run.color,
-                x_offset: current_x,
-                width: ww,
-                underline: run.underline,
-                strikethrough: run.strikethrough,
-                y_offset: y_off,
-            });
-            current_x += ww;
+            // A word wider than a whole line is broken between characters
+            while current_chunks.is_empty()
+                && current_x + ww > max_width
+                && let Some((head, tail)) =
+                    overflow_break(word, run, entry, eff_fs, max_width - current_x)
+            {
+                push_word_chunks(&mut current_chunks, head, run, entry, current_x);
+                lines.push(finish_line(&mut current_chunks));
+                wrapped = true;
+                current_x = 0.0;
+                word = tail;
+                pieces = word_pieces(word, run, entry, eff_fs);
+                ww = pieces.iter().map(|p| p.width).sum();
+            }
+
+            current_x = push_word_chunks(&mut current_chunks, pieces, run, entry, current_x);
         }
 
-        prev_ended_with_ws = run.text.ends_with(char::is_whitespace);
-        prev_space_w = space_w;
+        pending_gap = carry_spaces(pending_gap, &run.text, space_w);
     }
 
-    if !current_chunks.is_empty() {
+    // A trailing break still produces an (empty) line of its own
+    if !current_chunks.is_empty() || ended_with_break {
         lines.push(finish_line(&mut current_chunks));
     }
 
@@ -140,18 +410,31 @@ fn build_paragraph_lines(
     lines
 }
 
+/// Whether a tab can stop at `stop`; bar and clear entries only share the list.
+fn is_tab_target(stop: &TabStop) -> bool {
+    !matches!(stop.alignment, TabAlignment::Bar | TabAlignment::Clear)
+}
+
 fn find_next_tab_stop<'a>(
     current_x: f32,
     tab_stops: &'a [TabStop],
     indent_left: f32,
 ) -> TabStop {
     let abs_x = current_x + indent_left;
-    for stop in tab_stops {
+    for stop in tab_stops.iter().filter(|s| is_tab_target(s)) {
         if stop.position > abs_x + 0.5 {
             return stop.clone();
         }
     }
-    let next_default = ((abs_x / DEFAULT_TAB_INTERVAL).floor() + 1.0) * DEFAULT_TAB_INTERVAL;
+    let cleared = |pos: f32| {
+        tab_stops
+            .iter()
+            .any(|s| s.alignment == TabAlignment::Clear && (s.position - pos).abs() < 0.5)
+    };
+    let mut next_default = ((abs_x / DEFAULT_TAB_INTERVAL).floor() + 1.0) * DEFAULT_TAB_INTERVAL;
+    while cleared(next_default) {
+        next_default += DEFAULT_TAB_INTERVAL;
+    }
     TabStop {
         position: next_default,
         alignment: TabAlignment::Left,
@@ -161,96 +444,168 @@ fn find_next_tab_stop<'a>(
 
 fn segment_width(runs: &[&Run], seen_fonts: &HashMap<String, FontEntry>) -> f32 {
     let mut w: f32 = 0.0;
-    let mut first = true;
-    for run in runs {
+    let mut pending_gap: f32 = 0.0;
+    for run in runs.iter().filter(|r| !r.hidden) {
         let key = font_key(run);
         let entry = seen_fonts.get(&key).expect("font registered");
         let eff_fs = effective_font_size(run);
-        let space_w = entry.widths_1000[0] * eff_fs / 1000.0;
-        for (i, word) in run.text.split_whitespace().enumerate() {
-            if !first || i > 0 {
-                w += space_w;
-            }
-            w += to_winansi_bytes(word)
+        let space_w = entry.space_width() * eff_fs / 1000.0 * run.scale + run.char_spacing;
+        for (i, (spaces, word)) in split_words(&run.text).enumerate() {
+            let carried = if i == 0 { pending_gap } else { 0.0 };
+            w += carried + spaces as f32 * space_w;
+            w += word_pieces(word, run, entry, eff_fs)
                 .iter()
-                .filter(|&&b| b >= 32)
-                .map(|&b| entry.widths_1000[(b - 32) as usize] * eff_fs / 1000.0)
+                .map(|p| p.width)
                 .sum::<f32>();
-            first = false;
         }
+        pending_gap = carry_spaces(pending_gap, &run.text, space_w);
     }
     w
 }
 
-fn decimal_before_width(runs: &[&Run], seen_fonts: &HashMap<String, FontEntry>) -> f32 {
-    let full_text: String = runs.iter().map(|r| r.text.as_str()).collect();
-    let before = if let Some(dot_pos) = full_text.find('.') {
-        &full_text[..dot_pos]
-    } else {
-        &full_text
+/// Byte offset of the separator a decimal tab aligns on: the last '.' or ','
+/// next to a digit. When both kinds appear the later one is the decimal point
+/// ("1.234,56"); a lone one only counts if it is the document's `separator`,
+/// so "1,234" in an English document has no decimal part.
+fn decimal_separator_pos(text: &str, separator: char) -> Option<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let is_digit = |k: Option<usize>| {
+        k.and_then(|k| chars.get(k))
+            .is_some_and(|(_, c)| c.is_ascii_digit())
     };
-    let mut w: f32 = 0.0;
-    let mut chars_remaining = before.len();
+    let candidates: Vec<(usize, char)> = (0..chars.len())
+        .filter(|&k| matches!(chars[k].1, '.' | ','))
+        .filter(|&k| is_digit(k.checked_sub(1)) || is_digit(Some(k + 1)))
+        .map(|k| chars[k])
+        .collect();
+    let &(pos, last) = candidates.last()?;
+    let mixed = candidates.iter().any(|&(_, c)| c != last);
+    (mixed || last == separator).then_some(pos)
+}
+
+/// Width of a segment's text before its decimal separator, or None when it
+/// has no decimal part and is right-aligned at the stop instead.
+fn decimal_before_width(
+    runs: &[&Run],
+    seen_fonts: &HashMap<String, FontEntry>,
+    separator: char,
+) -> Option<f32> {
+    let runs: Vec<&Run> = runs.iter().copied().filter(|r| !r.hidden).collect();
+    let full_text: String = runs.iter().map(|r| r.text.as_str()).collect();
+    let mut remaining = decimal_separator_pos(&full_text, separator)?;
+    // The runs before the separator, the last one cut short, measured the
+    // way layout measures the segment
+    let mut before: Vec<&Run> = Vec::new();
+    let mut cut = None;
     for run in runs {
-        let key = font_key(run);
-        let entry = seen_fonts.get(&key).expect("font registered");
-        let eff_fs = effective_font_size(run);
-        let text_to_measure = if run.text.len() <= chars_remaining {
-            chars_remaining -= run.text.len();
-            &run.text
+        if run.text.len() <= remaining {
+            remaining -= run.text.len();
+            before.push(run);
         } else {
-            let s = &run.text[..chars_remaining];
-            chars_remaining = 0;
-            s
-        };
-        for &b in to_winansi_bytes(text_to_measure).iter().filter(|&&b| b >= 32) {
-            w += entry.widths_1000[(b - 32) as usize] * eff_fs / 1000.0;
-        }
-        if chars_remaining == 0 {
+            cut = Some(Run {
+                text: run.text[..remaining].to_string(),
+                ..run.clone()
+            });
             break;
         }
     }
-    w
+    before.extend(cut.as_ref());
+    Some(segment_width(&before, seen_fonts))
 }
 
-/// Build a single TextLine for a paragraph that contains tab characters.
-fn build_tabbed_line(
-    runs: &[Run],
-    seen_fonts: &HashMap<String, FontEntry>,
-    tab_stops: &[TabStop],
+/// Leader characters filling a tab from line offset `from` to `to`, in the
+/// run's font. Each character sits on a grid of its own advance measured from
+/// the margin, so leaders on successive lines line up as they do in Word.
+fn tab_leader(
+    leader: char,
+    run: &Run,
+    entry: &FontEntry,
     indent_left: f32,
+    from: f32,
+    to: f32,
+) -> Option<WordChunk> {
+    let font_size = effective_font_size(run);
+    let char_w = text_width(&leader.to_string(), entry, font_size);
+    if char_w <= 0.0 {
+        return None;
+    }
+    let first = ((from + indent_left) / char_w).ceil();
+    let last = ((to + indent_left) / char_w).floor();
+    if last - first < 1.0 {
+        return None;
+    }
+    let count = (last - first) as usize;
+    let encoded = entry.encode(&leader.to_string().repeat(count));
+    Some(WordChunk {
+        pdf_font: entry.pdf_font(encoded.composite).to_string(),
+        space_width: (!encoded.composite).then(|| entry.space_width()),
+        text: encoded,
+        font_size,
+        color: run.color,
+        x_offset: first * char_w - indent_left,
+        width: count as f32 * char_w,
+        underline: UnderlineStyle::None,
+        underline_color: None,
+        underline_position: DEFAULT_UNDERLINE_POSITION,
+        underline_thickness: DEFAULT_UNDERLINE_THICKNESS,
+        strikethrough: false,
+        strikeout_position: DEFAULT_STRIKEOUT_POSITION,
+        strikeout_thickness: DEFAULT_STRIKEOUT_THICKNESS,
+        y_offset: 0.0,
+        char_spacing: 0.0,
+        scale: 1.0,
+        synthetic: entry.synthetic,
+        link: None,
+    })
+}
+
+/// What tabs in a paragraph are laid out against.
+struct TabSettings<'a> {
+    stops: &'a [TabStop],
+    indent_left: f32, // tab positions are measured from the margin, lines from the indent
+    decimal_separator: char,
+}
+
+/// Build the lines of a paragraph (or the part between two line breaks) that
+/// contains tab characters. Text starts at `start_x`, the first-line indent on
+/// a paragraph's first line. A segment too long for `max_width` wraps, with its
+/// continuation lines starting where the segment started after its tab.
+fn build_tabbed_line<R: Borrow<Run>>(
+    runs: &[R],
+    seen_fonts: &HashMap<String, FontEntry>,
+    tabs: &TabSettings,
+    max_width: f32,
+    start_x: f32,
 ) -> Vec<TextLine> {
-    // Split runs into segments at tab markers
-    let mut segments: Vec<(Vec<&Run>, Option<TabStop>)> = Vec::new();
+    let (tab_stops, indent_left) = (tabs.stops, tabs.indent_left);
+    // Split runs into segments at tab markers; each tab's stop is resolved during layout
+    let mut segments: Vec<Vec<&Run>> = Vec::new();
     let mut current_seg: Vec<&Run> = Vec::new();
-    let mut pending_tab: Option<TabStop> = None;
 
-    for run in runs {
+    for run in runs.iter().map(Borrow::borrow).filter(|r| !r.hidden) {
         if run.is_tab {
-            segments.push((std::mem::take(&mut current_seg), pending_tab.take()));
-            // Find which tab stop this tab activates — we'll resolve position during layout
-            pending_tab = Some(TabStop {
-                position: 0.0, // placeholder, resolved below
-                alignment: TabAlignment::Left,
-                leader: None,
-            });
+            segments.push(std::mem::take(&mut current_seg));
         } else {
             current_seg.push(run);
         }
     }
-    segments.push((std::mem::take(&mut current_seg), pending_tab.take()));
+    segments.push(current_seg);
 
+    let mut lines: Vec<TextLine> = Vec::new();
     let mut all_chunks: Vec<WordChunk> = Vec::new();
-    let mut current_x: f32 = 0.0;
+    let mut current_x: f32 = start_x;
 
-    for (seg_idx, (seg_runs, tab_before)) in segments.iter().enumerate() {
+    for (seg_idx, seg_runs) in segments.iter().enumerate() {
         if seg_idx > 0 {
             let stop = find_next_tab_stop(current_x, tab_stops, indent_left);
             let tab_target = stop.position - indent_left;
 
             // Calculate where segment text will start based on alignment
             let seg_start = match stop.alignment {
-                TabAlignment::Left => tab_target.max(current_x),
+                // Bar and clear entries are never returned as the stop
+                TabAlignment::Left | TabAlignment::Bar | TabAlignment::Clear => {
+                    tab_target.max(current_x)
+                }
                 TabAlignment::Center => {
                     let sw = segment_width(seg_runs, seen_fonts);
                     (tab_target - sw / 2.0).max(current_x)
@@ -260,112 +615,118 @@ fn build_tabbed_line(
                     (tab_target - sw).max(current_x)
                 }
                 TabAlignment::Decimal => {
-                    let bw = decimal_before_width(seg_runs, seen_fonts);
+                    let bw = decimal_before_width(seg_runs, seen_fonts, tabs.decimal_separator)
+                        .unwrap_or_else(|| segment_width(seg_runs, seen_fonts));
                     (tab_target - bw).max(current_x)
                 }
             };
 
-            // Draw leader fill between end of previous text and start of aligned text
-            if let Some(_) = tab_before {
-                let abs_x = current_x + indent_left;
-                let leader = tab_stops
-                    .iter()
-                    .find(|s| s.position > abs_x + 0.5)
-                    .and_then(|s| s.leader);
-
-                if let Some(leader_char) = leader {
-                    let font_run = seg_runs.first().or_else(|| {
-                        segments[..seg_idx]
-                            .iter()
-                            .rev()
-                            .flat_map(|(r, _)| r.last())
-                            .next()
-                    });
-                    if let Some(run) = font_run {
-                        let key = font_key(run);
-                        let entry = seen_fonts.get(&key).expect("font registered");
-                        let eff_fs = effective_font_size(run);
-                        let leader_bytes = to_winansi_bytes(&leader_char.to_string());
-                        if let Some(&byte) = leader_bytes.first() {
-                            if byte >= 32 {
-                                let char_w =
-                                    entry.widths_1000[(byte - 32) as usize] * eff_fs / 1000.0;
-                                let leader_gap = seg_start - current_x;
-                                if char_w > 0.0 && leader_gap > char_w * 2.0 {
-                                    let count =
-                                        ((leader_gap - char_w) / char_w).floor() as usize;
-                                    if count > 0 {
-                                        let leader_text: String = std::iter::repeat(leader_char)
-                                            .take(count)
-                                            .collect();
-                                        let leader_w = count as f32 * char_w;
-                                        let leader_start = seg_start - leader_w;
-                                        all_chunks.push(WordChunk {
-                                            pdf_font: entry.pdf_name.clone(),
-                                            text: leader_text,
-                                            font_size: eff_fs,
-                                            color: run.color,
-                                            x_offset: leader_start,
-                                            width: leader_w,
-                                            underline: false,
-                                            strikethrough: false,
-                                            y_offset: 0.0,
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            // Fill the tab with the stop's leader, in the font of the text before it
+            let font_run = segments[..seg_idx]
+                .iter()
+                .rev()
+                .flat_map(|r| r.last())
+                .next()
+                .or_else(|| seg_runs.first());
+            if let (Some(leader), Some(run)) = (stop.leader, font_run) {
+                let entry = seen_fonts.get(&font_key(run)).expect("font registered");
+                let fill = tab_leader(leader, run, entry, indent_left, current_x, seg_start);
+                all_chunks.extend(fill);
             }
 
             current_x = seg_start;
         }
 
-        // Layout text in this segment from current_x
-        let mut prev_ws = false;
+        // Layout text in this segment from current_x, wrapping back to where it started
+        let wrap_x = if seg_idx > 0 { current_x } else { 0.0 };
+        let mut seg_on_line = false; // a word of this segment is already on the line
+        let mut pending_gap: f32 = 0.0;
         for run in seg_runs {
             let key = font_key(run);
             let entry = seen_fonts.get(&key).expect("font registered");
             let eff_fs = effective_font_size(run);
-            let space_w = entry.widths_1000[0] * eff_fs / 1000.0;
-            let y_off = vert_y_offset(run);
-
-            for (i, word) in run.text.split_whitespace().enumerate() {
-                let ww: f32 = to_winansi_bytes(word)
-                    .iter()
-                    .filter(|&&b| b >= 32)
-                    .map(|&b| entry.widths_1000[(b - 32) as usize] * eff_fs / 1000.0)
-                    .sum();
-                if !all_chunks.is_empty() && (i > 0 || prev_ws || run.text.starts_with(char::is_whitespace)) {
-                    current_x += space_w;
+            let space_w = entry.space_width() * eff_fs / 1000.0 * run.scale + run.char_spacing;
+
+            for (i, (spaces, word)) in split_words(&run.text).enumerate() {
+                let pieces = word_pieces(word, run, entry, eff_fs);
+                let ww: f32 = pieces.iter().map(|p| p.width).sum();
+                let carried = if i == 0 { pending_gap } else { 0.0 };
+                let gap = carried + spaces as f32 * space_w;
+                if seg_on_line && current_x + gap + ww > max_width {
+                    lines.push(finish_line(&mut all_chunks));
+                    current_x = wrap_x;
+                } else {
+                    current_x += gap;
                 }
-                all_chunks.push(WordChunk {
-                    pdf_font: entry.pdf_name.clone(),
-                    text: word.to_string(),
-                    font_size: eff_fs,
-                    color: run.color,
-                    x_offset: current_x,
-                    width: ww,
-                    underline: run.underline,
-                    strikethrough: run.strikethrough,
-                    y_offset: y_off,
-                });
-                current_x += ww;
+                current_x = push_word_chunks(&mut all_chunks, pieces, run, entry, current_x);
+                seg_on_line = true;
             }
-            prev_ws = run.text.ends_with(char::is_whitespace);
+            pending_gap = carry_spaces(pending_gap, &run.text, space_w);
         }
     }
 
-    let total_width = all_chunks.last().map(|c| c.x_offset + c.width).unwrap_or(0.0);
-    vec![TextLine {
-        chunks: all_chunks,
-        total_width,
-    }]
+    lines.push(finish_line(&mut all_chunks));
+    lines
+}
+
+/// Set the fill color for text, black when automatic.
+fn set_text_fill(content: &mut Content, color: Option<[u8; 3]>) {
+    if let Some([r, g, b]) = color {
+        content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    } else {
+        content.set_fill_gray(0.0);
+    }
+}
+
+/// A stretch of one line drawn with a single underline or strikethrough,
+/// styled after its first chunk.
+struct DecorationSpan {
+    first: usize,
+    last: usize,
+    x1: f32,
+    x2: f32,
+}
+
+/// Add chunk `idx`, drawn from `x`, to a line's decoration spans. It extends
+/// the last span, gaps included, when it directly follows it and `joins` the
+/// span's first chunk.
+fn add_to_span(
+    spans: &mut Vec<DecorationSpan>,
+    chunks: &[WordChunk],
+    idx: usize,
+    x: f32,
+    joins: impl Fn(&WordChunk, &WordChunk) -> bool,
+) {
+    let x2 = x + chunks[idx].width;
+    match spans.last_mut() {
+        Some(span) if span.last + 1 == idx && joins(&chunks[span.first], &chunks[idx]) => {
+            span.last = idx;
+            span.x2 = x2;
+        }
+        _ => spans.push(DecorationSpan {
+            first: idx,
+            last: idx,
+            x1: x,
+            x2,
+        }),
+    }
+}
+
+/// Whether two chunks of a line can be shown as one string.
+fn same_string(a: &WordChunk, b: &WordChunk) -> bool {
+    a.pdf_font == b.pdf_font
+        && a.font_size == b.font_size
+        && a.color == b.color
+        && a.scale == b.scale
+        && a.char_spacing == b.char_spacing
+        && a.y_offset == b.y_offset
+        && a.synthetic == b.synthetic
 }
 
-/// Render pre-built lines applying the paragraph alignment.
+/// Render pre-built lines applying the paragraph alignment, one text object
+/// per line.
 /// `total_line_count` is the full paragraph line count (for justify: last line stays left-aligned).
+/// Returns the link areas covered by hyperlinked chunks, one per link per line.
 fn render_paragraph_lines(
     content: &mut Content,
     lines: &[TextLine],
@@ -376,17 +737,27 @@ fn render_paragraph_lines(
     line_pitch: f32,
     total_line_count: usize,
     first_line_index: usize,
-) {
+) -> Vec<LinkArea> {
+    let mut link_areas: Vec<LinkArea> = Vec::new();
     let mut current_color: Option<[u8; 3]> = None;
+    let mut current_char_spacing: f32 = 0.0;
+    let mut current_word_spacing: f32 = 0.0;
+    let mut current_scale: f32 = 1.0;
 
     let last_line_idx = total_line_count.saturating_sub(1);
     for (line_num, line) in lines.iter().enumerate() {
         let y = first_baseline_y - line_num as f32 * line_pitch;
         let global_line_idx = first_line_index + line_num;
 
-        let is_justified = *alignment == Alignment::Justify
-            && global_line_idx != last_line_idx
-            && line.chunks.len() > 1;
+        // Justification widens the spaces between words, not the joins
+        // between pieces of one word
+        let is_space_gap = |i: usize| {
+            let prev = &line.chunks[i - 1];
+            line.chunks[i].x_offset > prev.x_offset + prev.width + 0.001
+        };
+        let space_gaps = (1..line.chunks.len()).filter(|&i| is_space_gap(i)).count();
+        let is_justified =
+            *alignment == Alignment::Justify && global_line_idx != last_line_idx && space_gaps > 0;
 
         let line_start_x = match alignment {
             Alignment::Center => margin_left + (text_width - line.total_width) / 2.0,
@@ -395,73 +766,384 @@ fn render_paragraph_lines(
         };
 
         let extra_per_gap = if is_justified {
-            (text_width - line.total_width) / (line.chunks.len() - 1) as f32
+            (text_width - line.total_width) / space_gaps as f32
         } else {
             0.0
         };
 
+        let mut underlines: Vec<DecorationSpan> = Vec::new();
+        let mut strikes: Vec<DecorationSpan> = Vec::new();
+        let mut gaps_before = 0;
+        let xs: Vec<f32> = (0..line.chunks.len())
+            .map(|i| {
+                if i > 0 && is_space_gap(i) {
+                    gaps_before += 1;
+                }
+                line_start_x + line.chunks[i].x_offset + gaps_before as f32 * extra_per_gap
+            })
+            .collect();
         for (chunk_idx, chunk) in line.chunks.iter().enumerate() {
-            let x = line_start_x + chunk.x_offset + chunk_idx as f32 * extra_per_gap;
-            if chunk.color != current_color {
-                if let Some([r, g, b]) = chunk.color {
-                    content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
-                } else {
-                    content.set_fill_gray(0.0);
+            let x = xs[chunk_idx];
+            if let Some(target) = &chunk.link {
+                let (bottom, top) = (y - chunk.font_size * 0.25, y + chunk.font_size * 0.9);
+                let prev_same_link = chunk_idx > 0 && line.chunks[chunk_idx - 1].link == chunk.link;
+                match link_areas.last_mut() {
+                    Some(area) if prev_same_link => {
+                        area.rect.x2 = x + chunk.width;
+                        area.rect.y1 = area.rect.y1.min(bottom);
+                        area.rect.y2 = area.rect.y2.max(top);
+                    }
+                    _ => link_areas.push(LinkArea {
+                        rect: Rect::new(x, bottom, x + chunk.width, top),
+                        target: target.clone(),
+                        tag: None,
+                    }),
+                }
+            }
+
+            // Decorations run on across spaces, except for words-only underlines
+            if chunk.underline != UnderlineStyle::None {
+                add_to_span(&mut underlines, &line.chunks, chunk_idx, x, |a, b| {
+                    a.underline != UnderlineStyle::Words
+                        && a.underline == b.underline
+                        && a.underline_color == b.underline_color
+                        && a.color == b.color
+                });
+            }
+            if chunk.strikethrough {
+                // A change of size, font or baseline shift moves the rule, so it starts anew
+                add_to_span(&mut strikes, &line.chunks, chunk_idx, x, |a, b| {
+                    a.color == b.color
+                        && a.font_size == b.font_size
+                        && a.y_offset == b.y_offset
+                        && a.strikeout_position == b.strikeout_position
+                        && a.strikeout_thickness == b.strikeout_thickness
+                });
+            }
+        }
+
+        // Synthesized bold changes the graphics state, which a text object
+        // can't save, so the whole line is wrapped instead
+        let bold_line = line.chunks.iter().any(|c| c.synthetic.bold);
+        let state_before = (
+            current_color,
+            current_char_spacing,
+            current_word_spacing,
+            current_scale,
+        );
+        if bold_line {
+            content.save_state();
+        }
+        content.begin_text();
+        let mut font: Option<(&str, f32)> = None;
+        let mut origin = (0.0, 0.0);
+        let mut skewed = false;
+        let mut stroking = false;
+        let mut start = 0;
+        while start < line.chunks.len() {
+            let first = &line.chunks[start];
+            let end = (start + 1..line.chunks.len())
+                .find(|&i| !same_string(first, &line.chunks[i]))
+                .unwrap_or(line.chunks.len());
+
+            if first.color != current_color {
+                set_text_fill(content, first.color);
+                current_color = first.color;
+            }
+            if first.scale != current_scale {
+                content.set_horizontal_scaling(first.scale * 100.0);
+                current_scale = first.scale;
+            }
+            // Tc is scaled by Tz in PDF, but Word applies spacing after scaling
+            let char_spacing = first.char_spacing / first.scale;
+            if char_spacing != current_char_spacing {
+                content.set_char_spacing(char_spacing);
+                current_char_spacing = char_spacing;
+            }
+            // Tw widens the space codes of simple fonts; gaps in other fonts
+            // are widened by offsets
+            let word_spacing = extra_per_gap / first.scale;
+            if word_spacing != current_word_spacing {
+                content.set_word_spacing(word_spacing);
+                current_word_spacing = word_spacing;
+            }
+            if font != Some((first.pdf_font.as_str(), first.font_size)) {
+                content.set_font(Name(first.pdf_font.as_bytes()), first.font_size);
+                font = Some((first.pdf_font.as_str(), first.font_size));
+            }
+            if first.synthetic.bold {
+                set_synthetic_bold_stroke(content, first.color, first.font_size);
+                stroking = true;
+            } else if stroking {
+                content.set_text_rendering_mode(TextRenderingMode::Fill);
+                stroking = false;
+            }
+
+            // Td moves relative to the last string's start; a skewed matrix
+            // for synthesized italic is set, and left, absolutely
+            let (x, y) = (xs[start], y + first.y_offset);
+            if first.synthetic.italic {
+                content.set_text_matrix([1.0, 0.0, SYNTHETIC_ITALIC_SKEW, 1.0, x, y]);
+                skewed = true;
+            } else if skewed {
+                content.set_text_matrix([1.0, 0.0, 0.0, 1.0, x, y]);
+                skewed = false;
+            } else {
+                content.next_line(x - origin.0, y - origin.1);
+            }
+            origin = (x, y);
+
+            // Words are joined by a space where the font has one, and offsets
+            // make up the rest of each gap
+            let unit = first.font_size * first.scale / 1000.0;
+            let mut shown = content.show_positioned();
+            let mut items = shown.items();
+            for i in start..end {
+                let chunk = &line.chunks[i];
+                if i > start {
+                    let prev = &line.chunks[i - 1];
+                    let mut gap = xs[i] - (xs[i - 1] + prev.width);
+                    if let Some(space_w) = chunk.space_width {
+                        let space_advance = space_w * unit + chunk.char_spacing + extra_per_gap;
+                        if gap > space_advance / 2.0 {
+                            items.show(Str(b" "));
+                            gap -= space_advance;
+                        }
+                    }
+                    if gap.abs() > 0.001 {
+                        items.adjust(-gap / unit);
+                    }
                 }
+                let mut from = 0;
+                for &(offset, adjust) in &chunk.text.kerning {
+                    items
+                        .show(Str(&chunk.text.bytes[from..offset]))
+                        .adjust(-adjust);
+                    from = offset;
+                }
+                items.show(Str(&chunk.text.bytes[from..]));
+            }
+            // A space before a change of style keeps the words apart for text
+            // extraction; the next string is positioned on its own
+            let last = &line.chunks[end - 1];
+            if let (Some(space_w), Some(&next_x)) = (last.space_width, xs.get(end))
+                && next_x - (xs[end - 1] + last.width) > space_w * unit / 2.0
+            {
+                items.show(Str(b" "));
+            }
+            drop(items);
+            drop(shown);
+            start = end;
+        }
+        content.end_text();
+        if bold_line {
+            content.restore_state();
+            (
+                current_color,
+                current_char_spacing,
+                current_word_spacing,
+                current_scale,
+            ) = state_before;
+        }
+
+        for span in &underlines {
+            let chunk = &line.chunks[span.first];
+            if chunk.color != current_color {
+                set_text_fill(content, chunk.color);
+                current_color = chunk.color;
+            }
+            draw_underline(content, chunk, span.x1, y, span.x2 - span.x1);
+        }
+        for span in &strikes {
+            let chunk = &line.chunks[span.first];
+            if chunk.color != current_color {
+                set_text_fill(content, chunk.color);
                 current_color = chunk.color;
             }
-            let text_bytes = to_winansi_bytes(&chunk.text);
+            let thick = (chunk.font_size * chunk.strikeout_thickness).max(0.5);
+            let st_top = y + chunk.y_offset + chunk.font_size * chunk.strikeout_position;
             content
-                .begin_text()
-                .set_font(Name(chunk.pdf_font.as_bytes()), chunk.font_size)
-                .next_line(x, y + chunk.y_offset)
-                .show(Str(&text_bytes))
-                .end_text();
-
-            if chunk.underline {
-                let thick = (chunk.font_size * 0.05).max(0.5);
-                let ul_y = y - chunk.font_size * 0.12;
+                .rect(span.x1, st_top - thick, span.x2 - span.x1, thick)
+                .fill_nonzero();
+        }
+    }
+    if current_color.is_some() {
+        content.set_fill_gray(0.0);
+    }
+    if current_char_spacing != 0.0 {
+        content.set_char_spacing(0.0);
+    }
+    if current_word_spacing != 0.0 {
+        content.set_word_spacing(0.0);
+    }
+    if current_scale != 1.0 {
+        content.set_horizontal_scaling(100.0);
+    }
+    link_areas
+}
+
+// Stroke width of synthesized bold and skew of synthesized italic, in em
+const SYNTHETIC_BOLD_STROKE: f32 = 0.03;
+const SYNTHETIC_ITALIC_SKEW: f32 = 0.21;
+
+/// Stroke glyph outlines in the text color, thickening them into bold.
+fn set_synthetic_bold_stroke(content: &mut Content, color: Option<[u8; 3]>, font_size: f32) {
+    if let Some([r, g, b]) = color {
+        content.set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    } else {
+        content.set_stroke_gray(0.0);
+    }
+    content.set_line_width(font_size * SYNTHETIC_BOLD_STROKE);
+    content.set_text_rendering_mode(TextRenderingMode::FillStroke);
+}
+
+/// Show encoded text with its baseline starting at `origin`, kerned by TJ
+/// offsets. Styles the font lacks are synthesized: bold by also stroking the
+/// glyph outlines in the text color, italic by skewing the text matrix.
+fn show_text(
+    content: &mut Content,
+    pdf_font: &str,
+    font_size: f32,
+    synthetic: Synthetic,
+    color: Option<[u8; 3]>,
+    (x, y): (f32, f32),
+    (bytes, kerning): (&[u8], &[(usize, f32)]),
+) {
+    if synthetic.bold {
+        content.save_state();
+        set_synthetic_bold_stroke(content, color, font_size);
+    }
+    content
+        .begin_text()
+        .set_font(Name(pdf_font.as_bytes()), font_size);
+    if synthetic.italic {
+        content.set_text_matrix([1.0, 0.0, SYNTHETIC_ITALIC_SKEW, 1.0, x, y]);
+    } else {
+        content.next_line(x, y);
+    }
+    if kerning.is_empty() {
+        content.show(Str(bytes));
+    } else {
+        let mut shown = content.show_positioned();
+        let mut items = shown.items();
+        let mut start = 0;
+        for &(offset, adjust) in kerning {
+            items.show(Str(&bytes[start..offset])).adjust(-adjust);
+            start = offset;
+        }
+        items.show(Str(&bytes[start..]));
+    }
+    content.end_text();
+    if synthetic.bold {
+        content.restore_state();
+    }
+}
+
+/// Draw the underline of `chunk`'s style over `width` from (x, y) on the baseline,
+/// placed by its font's underline metrics.
+/// The fill color is expected to already be the chunk's text color.
+fn draw_underline(content: &mut Content, chunk: &WordChunk, x: f32, y: f32, width: f32) {
+    let thick = (chunk.font_size * chunk.underline_thickness).max(0.5);
+    let ul_y = y + chunk.font_size * chunk.underline_position;
+
+    if let Some([r, g, b]) = chunk.underline_color {
+        content.save_state();
+        content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        content.set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    }
+
+    // Repeating pattern of (on, off) lengths, filled as short rects
+    let dash_pattern = |content: &mut Content, pattern: &[f32]| {
+        let mut pos = 0.0;
+        let mut i = 0;
+        while pos < width {
+            let len = pattern[i % pattern.len()];
+            if i % 2 == 0 {
                 content
-                    .rect(x, ul_y - thick, chunk.width, thick)
+                    .rect(x + pos, ul_y - thick, len.min(width - pos), thick)
                     .fill_nonzero();
             }
-            if chunk.strikethrough {
-                let thick = (chunk.font_size * 0.05).max(0.5);
-                let st_y = y + chunk.font_size * 0.3;
-                content
-                    .rect(x, st_y, chunk.width, thick)
-                    .fill_nonzero();
+            pos += len;
+            i += 1;
+        }
+    };
+
+    match chunk.underline {
+        UnderlineStyle::None => {}
+        UnderlineStyle::Single | UnderlineStyle::Words => {
+            content.rect(x, ul_y - thick, width, thick).fill_nonzero();
+        }
+        UnderlineStyle::Thick => {
+            content
+                .rect(x, ul_y - thick * 2.0, width, thick * 2.0)
+                .fill_nonzero();
+        }
+        UnderlineStyle::Double => {
+            let thin = thick * 0.7;
+            content.rect(x, ul_y - thin, width, thin).fill_nonzero();
+            content
+                .rect(x, ul_y - thin * 3.0, width, thin)
+                .fill_nonzero();
+        }
+        UnderlineStyle::Dotted => dash_pattern(content, &[thick, thick]),
+        UnderlineStyle::Dash => dash_pattern(content, &[thick * 4.0, thick * 2.0]),
+        UnderlineStyle::DotDash => {
+            dash_pattern(content, &[thick * 4.0, thick * 2.0, thick, thick * 2.0])
+        }
+        UnderlineStyle::Wave => {
+            let amp = thick;
+            let period = thick * 4.0;
+            let mid = ul_y - thick;
+            content.save_state();
+            content.set_line_width(thick * 0.8);
+            if chunk.underline_color.is_none()
+                && let Some([r, g, b]) = chunk.color
+            {
+                content.set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            }
+            content.move_to(x, mid);
+            let mut pos = 0.0;
+            let mut up = true;
+            while pos < width {
+                let next = (pos + period / 2.0).min(width);
+                let peak = if up { mid + amp } else { mid - amp };
+                content.cubic_to(x + pos, peak, x + next, peak, x + next, mid);
+                pos = next;
+                up = !up;
             }
+            content.stroke();
+            content.restore_state();
         }
     }
-    if current_color.is_some() {
-        content.set_fill_gray(0.0);
+
+    if chunk.underline_color.is_some() {
+        content.restore_state();
     }
 }
 
-fn font_metric(
-    runs: &[Run],
+fn font_metric<R: Borrow<Run>>(
+    runs: &[R],
     seen_fonts: &HashMap<String, FontEntry>,
     get: impl Fn(&FontEntry) -> Option<f32>,
 ) -> Option<f32> {
     runs.first()
-        .map(font_key)
+        .map(|run| font_key(run.borrow()))
         .and_then(|k| seen_fonts.get(&k))
         .and_then(get)
 }
 
 /// Compute the effective font_size, line_h_ratio, and ascender_ratio for a set of runs
 /// by picking the run that produces the tallest visual ascent (font_size * ascender_ratio).
-fn tallest_run_metrics(
-    runs: &[Run],
+fn tallest_run_metrics<R: Borrow<Run>>(
+    runs: &[R],
     seen_fonts: &HashMap<String, FontEntry>,
 ) -> (f32, Option<f32>, Option<f32>) {
-    let mut best_font_size = runs.first().map_or(12.0, |r| r.font_size);
+    let mut best_font_size = runs.first().map_or(12.0, |r| r.borrow().font_size);
     let mut best_ascent = 0.0f32;
     let mut best_line_h_ratio: Option<f32> = None;
     let mut best_ascender_ratio: Option<f32> = None;
 
-    for run in runs {
+    for run in runs.iter().map(Borrow::borrow).filter(|r| !r.hidden) {
         let key = font_key(run);
         let entry = seen_fonts.get(&key);
         let ar = entry.and_then(|e| e.ascender_ratio).unwrap_or(0.75);
@@ -476,11 +1158,48 @@ fn tallest_run_metrics(
     (best_font_size, best_line_h_ratio, best_ascender_ratio)
 }
 
-const TABLE_CELL_PAD_LEFT: f32 = 5.4;
-const TABLE_CELL_PAD_TOP: f32 = 0.0;
-const TABLE_CELL_PAD_BOTTOM: f32 = 0.0;
+/// Advance of one line under `spacing`, for a line whose tallest run has
+/// `font_size` and the font's `line_h_ratio`.
+fn line_height(spacing: LineSpacing, font_size: f32, line_h_ratio: Option<f32>) -> f32 {
+    let natural = line_h_ratio.map_or(font_size * 1.2, |ratio| font_size * ratio);
+    match spacing {
+        LineSpacing::Auto(factor) => line_h_ratio.map_or(natural, |_| natural * factor),
+        // A tiny exact height still advances, so lines never stack up in one place
+        LineSpacing::Exact(pt) => pt.max(1.0),
+        LineSpacing::AtLeast(pt) => natural.max(pt),
+    }
+}
+
+/// Distance from the top of a line to its baseline. Fixed line heights put
+/// extra space above the text, and an exact height smaller than the font
+/// takes its shortfall out of the ascent so the baseline stays within the line.
+fn line_ascent(
+    spacing: LineSpacing,
+    line_h: f32,
+    font_size: f32,
+    line_h_ratio: Option<f32>,
+    ascender_ratio: Option<f32>,
+) -> f32 {
+    let ascent = font_size * ascender_ratio.unwrap_or(0.75);
+    match spacing {
+        LineSpacing::Auto(_) => ascent,
+        LineSpacing::Exact(_) | LineSpacing::AtLeast(_) => {
+            let natural = line_h_ratio.map_or(font_size * 1.2, |ratio| font_size * ratio);
+            (line_h - (natural - ascent)).clamp(0.0, line_h)
+        }
+    }
+}
+
 const TABLE_BORDER_WIDTH: f32 = 0.5;
 
+/// Width of a cell starting at grid column `grid_col`, including the columns it spans.
+fn cell_span_width(col_widths: &[f32], grid_col: usize, cell: &TableCell) -> f32 {
+    if grid_col >= col_widths.len() {
+        return cell.width;
+    }
+    col_widths[grid_col..].iter().take(cell.grid_span).sum()
+}
+
 /// Auto-fit column widths so that the longest non-breakable word in each column
 /// fits within the cell (including padding). Columns that need more space grow;
 /// other columns shrink proportionally. Total width is preserved.
@@ -494,32 +1213,42 @@ fn auto_fit_columns(
     }
 
     let mut min_widths = vec![0.0f32; ncols];
+    // Words wider than this are broken inside the cell rather than widening it
+    let total: f32 = table.col_widths.iter().sum();
+    let even_share = total / ncols as f32;
 
     for row in &table.rows {
-        for (ci, cell) in row.cells.iter().enumerate() {
+        let mut grid_col = 0;
+        for cell in &row.cells {
+            let ci = grid_col;
+            grid_col += cell.grid_span;
             if ci >= ncols {
                 break;
             }
+            // Merged cells share their width among several columns; leave them be
+            if cell.grid_span > 1 {
+                continue;
+            }
             for para in &cell.paragraphs {
-                for run in &para.runs {
+                for run in para.runs.iter().filter(|r| !r.hidden) {
                     let key = font_key(run);
                     let Some(entry) = seen_fonts.get(&key) else {
                         continue;
                     };
-                    for word in run.text.split_whitespace() {
-                        let ww: f32 = to_winansi_bytes(word)
+                    for (_, word) in split_words(&run.text) {
+                        let ww: f32 = word_pieces(word, run, entry, run.font_size)
                             .iter()
-                            .filter(|&&b| b >= 32)
-                            .map(|&b| entry.widths_1000[(b - 32) as usize] * run.font_size / 1000.0)
+                            .map(|p| p.width)
                             .sum();
-                        min_widths[ci] = min_widths[ci].max(ww);
+                        let needed = (ww + cell.margins.left + cell.margins.right)
+                            .min(table.col_widths[ci].max(even_share));
+                        min_widths[ci] = min_widths[ci].max(needed);
                     }
                 }
             }
         }
     }
 
-    let total: f32 = table.col_widths.iter().sum();
     let mut widths = table.col_widths.clone();
 
     // Expand columns that need it, track how much extra space is needed
@@ -555,359 +1284,1640 @@ fn auto_fit_columns(
     widths
 }
 
-struct RowLayout {
-    height: f32,
-    cell_lines: Vec<(Vec<TextLine>, f32, f32)>, // (lines, line_h, font_size) per cell
-}
-
+/// Column widths a table is laid out with: auto-fitted to the longest words,
+/// sized to a percentage `w:tblW`, and scaled down proportionally when wider
+/// than the space from the current column to the right margin.
+fn table_column_widths(
+    table: &Table,
+    flow: &Flow,
+    seen_fonts: &HashMap<String, FontEntry>,
+) -> Vec<f32> {
+    let mut widths = auto_fit_columns(table, seen_fonts);
+    let total: f32 = widths.iter().sum();
+    let sect = flow.sect;
+    let text_w = sect.page_width - sect.margin_left - sect.margin_right;
+    let (_, area_w) = flow.table_area(f32::INFINITY);
+    let target = table
+        .width_pct
+        .map_or(total, |pct| text_w * pct)
+        .min(area_w);
+    if total > 0.0 && (total - target).abs() > 0.01 {
+        let scale = target / total;
+        for w in &mut widths {
+            *w *= scale;
+        }
+    }
+    widths
+}
+
+struct RowLayout {
+    height: f32,
+    cell_lines: Vec<(Vec<TextLine>, f32, f32)>, // (lines, line_h, font_size) per cell
+    cell_heights: Vec<f32>,                     // content height per cell, padding included
+}
+
+/// The cell of `row` that starts at grid column `grid_col`.
+fn cell_at_grid(row: &TableRow, grid_col: usize) -> Option<&TableCell> {
+    let mut col = 0;
+    for cell in &row.cells {
+        if col == grid_col {
+            return Some(cell);
+        }
+        col += cell.grid_span;
+    }
+    None
+}
+
+/// Number of rows covered by a vertically merged cell starting at row `ri`.
+fn merged_row_count(table: &Table, ri: usize, grid_col: usize) -> usize {
+    1 + table.rows[ri + 1..]
+        .iter()
+        .take_while(|row| {
+            cell_at_grid(row, grid_col).is_some_and(|c| c.v_merge == VMerge::Continue)
+        })
+        .count()
+}
+
+/// Combined height of the rows covered by a merged cell starting at row `ri`.
+fn merged_height(table: &Table, layouts: &[RowLayout], ri: usize, grid_col: usize) -> f32 {
+    let n = merged_row_count(table, ri, grid_col);
+    layouts[ri..ri + n].iter().map(|l| l.height).sum()
+}
+
+/// Break the paragraphs of a cell `col_w` wide into lines. Returns the lines,
+/// the line height and font size of the first paragraph, and the height of
+/// the content with the cell's padding. Fields are measured by their cached
+/// results unless `fields` gives their values.
+fn layout_cell(
+    cell: &TableCell,
+    col_w: f32,
+    doc: &Document,
+    seen_fonts: &HashMap<String, FontEntry>,
+    fields: Option<&FieldValues>,
+) -> ((Vec<TextLine>, f32, f32), f32) {
+    let cell_text_w = (col_w - cell.margins.left - cell.margins.right).max(1.0);
+    let mut total_h: f32 = cell.margins.top + cell.margins.bottom;
+    let mut all_lines = Vec::new();
+    let mut first_font_size = 12.0f32;
+    let mut first_line_h = 14.4f32;
+
+    for para in &cell.paragraphs {
+        let font_size = para.runs.first().map_or(12.0, |r| r.font_size);
+        let line_h = line_height(
+            para.line_spacing.unwrap_or(doc.line_spacing),
+            font_size,
+            font_metric(&para.runs, seen_fonts, |e| e.line_h_ratio),
+        );
+
+        if all_lines.is_empty() {
+            first_font_size = font_size;
+            first_line_h = line_h;
+        }
+
+        if !para.runs.is_empty() {
+            let runs = match fields {
+                Some(fields) => substitute_fields(&para.runs, doc, fields),
+                None => para.runs.iter().map(Cow::Borrowed).collect(),
+            };
+            let lines =
+                build_paragraph_lines(&runs, seen_fonts, cell_text_w, para.indent_first_line);
+            total_h += lines.len() as f32 * line_h;
+            all_lines.extend(lines);
+        }
+    }
+    ((all_lines, first_line_h, first_font_size), total_h)
+}
+
 fn compute_row_layouts(
     table: &Table,
     col_widths: &[f32],
     doc: &Document,
     seen_fonts: &HashMap<String, FontEntry>,
+    fields: Option<&FieldValues>,
 ) -> Vec<RowLayout> {
-    table
+    let mut layouts: Vec<RowLayout> = table
         .rows
         .iter()
         .map(|row| {
             let mut max_h: f32 = 0.0;
+            let mut grid_col = 0;
+            let mut cell_heights = Vec::with_capacity(row.cells.len());
             let cell_lines: Vec<(Vec<TextLine>, f32, f32)> = row
                 .cells
                 .iter()
-                .enumerate()
-                .map(|(ci, cell)| {
-                    let col_w = col_widths.get(ci).copied().unwrap_or(cell.width);
-                    let cell_text_w = col_w;
-                    let mut total_h: f32 = TABLE_CELL_PAD_TOP + TABLE_CELL_PAD_BOTTOM;
-                    let mut all_lines = Vec::new();
-                    let mut first_font_size = 12.0f32;
-                    let mut first_line_h = 14.4f32;
-
-                    for para in &cell.paragraphs {
-                        let font_size = para.runs.first().map_or(12.0, |r| r.font_size);
-                        let effective_ls = para.line_spacing.unwrap_or(doc.line_spacing);
-                        let line_h = font_metric(&para.runs, seen_fonts, |e| e.line_h_ratio)
-                            .map(|ratio| font_size * ratio * effective_ls)
-                            .unwrap_or(font_size * 1.2);
-
-                        if all_lines.is_empty() {
-                            first_font_size = font_size;
-                            first_line_h = line_h;
-                        }
-
-                        if !para.runs.is_empty() {
-                            let lines = build_paragraph_lines(&para.runs, seen_fonts, cell_text_w);
-                            total_h += lines.len() as f32 * line_h;
-                            all_lines.extend(lines);
-                        }
+                .map(|cell| {
+                    let col_w = cell_span_width(col_widths, grid_col, cell);
+                    grid_col += cell.grid_span;
+                    let (lines, total_h) = layout_cell(cell, col_w, doc, seen_fonts, fields);
+
+                    // Merged cells are fitted across all their rows below
+                    if cell.v_merge == VMerge::None {
+                        max_h = max_h.max(total_h);
                     }
-
-                    max_h = max_h.max(total_h);
-                    (all_lines, first_line_h, first_font_size)
+                    cell_heights.push(total_h);
+                    lines
                 })
                 .collect();
 
+            let content_h = max_h + TABLE_BORDER_WIDTH;
+            let height = match row.height_rule {
+                RowHeightRule::Auto => content_h,
+                RowHeightRule::AtLeast => content_h.max(row.height),
+                RowHeightRule::Exact => row.height,
+            };
             RowLayout {
-                height: max_h + TABLE_BORDER_WIDTH,
+                height,
                 cell_lines,
+                cell_heights,
             }
         })
-        .collect()
+        .collect();
+
+    // A merged cell taller than the rows it covers grows the last of them
+    for ri in 0..table.rows.len() {
+        let mut grid_col = 0;
+        for (ci, cell) in table.rows[ri].cells.iter().enumerate() {
+            if cell.v_merge == VMerge::Restart {
+                let n = merged_row_count(table, ri, grid_col);
+                let covered: f32 = layouts[ri..ri + n].iter().map(|l| l.height).sum();
+                let needed = layouts[ri].cell_heights[ci] + TABLE_BORDER_WIDTH;
+                if needed > covered {
+                    layouts[ri + n - 1].height += needed - covered;
+                }
+            }
+            grid_col += cell.grid_span;
+        }
+    }
+    layouts
 }
 
+/// Draw a table at the current flow position. `total_pages` is the value
+/// used for NUMPAGES fields in its cells.
 fn render_table(
     table: &Table,
     doc: &Document,
     seen_fonts: &HashMap<String, FontEntry>,
-    content: &mut Content,
-    all_contents: &mut Vec<Content>,
-    slot_top: &mut f32,
-    prev_space_after: f32,
-) {
-    let col_widths = auto_fit_columns(table, seen_fonts);
-    let row_layouts = compute_row_layouts(table, &col_widths, doc, seen_fonts);
-
-    *slot_top -= prev_space_after;
+    total_pages: usize,
+    flow: &mut Flow,
+) -> Vec<(usize, LinkArea)> {
+    let mut links = Vec::new();
+    let col_widths = table_column_widths(table, flow, seen_fonts);
+    let page_label = flow.page_label();
+    let fields = FieldValues {
+        page_label: &page_label,
+        total_pages,
+        now: flow.now,
+    };
+    let mut row_layouts = compute_row_layouts(table, &col_widths, doc, seen_fonts, Some(&fields));
+    let header_h: f32 = row_layouts[..table.header_rows]
+        .iter()
+        .map(|l| l.height)
+        .sum();
+    // Page fields show the page their row is drawn on
+    let relayout_fields = |ri: usize, flow: &Flow, layout: &mut RowLayout| {
+        let row = &table.rows[ri];
+        if !row
+            .cells
+            .iter()
+            .any(|c| paragraphs_have_field(&c.paragraphs, |_| true))
+        {
+            return;
+        }
+        let page_label = flow.page_label();
+        let fields = FieldValues {
+            page_label: &page_label,
+            total_pages,
+            now: flow.now,
+        };
+        let mut grid_col = 0;
+        for (cell, lines) in row.cells.iter().zip(&mut layout.cell_lines) {
+            let col_w = cell_span_width(&col_widths, grid_col, cell);
+            grid_col += cell.grid_span;
+            *lines = layout_cell(cell, col_w, doc, seen_fonts, Some(&fields)).0;
+        }
+    };
 
-    for (ri, (row, layout)) in table.rows.iter().zip(row_layouts.iter()).enumerate() {
-        let row_h = layout.height;
+    flow.open_group(StructRole::Table);
+    for ri in 0..row_layouts.len() {
+        let row_h = row_layouts[ri].height;
         log::debug!(
             "TABLE row={} row_h={:.2} cells={} slot_top={:.2}",
             ri,
             row_h,
-            layout.cell_lines.len(),
-            *slot_top
+            row_layouts[ri].cell_lines.len(),
+            flow.slot_top
         );
-        let at_page_top = (*slot_top - (doc.page_height - doc.margin_top)).abs() < 1.0;
-
-        if !at_page_top && *slot_top - row_h < doc.margin_bottom {
-            all_contents.push(std::mem::replace(content, Content::new()));
-            *slot_top = doc.page_height - doc.margin_top;
+        // Header rows stay on the same page as the first body row
+        let needed = match row_layouts.get(table.header_rows) {
+            Some(first_body) if ri == 0 && table.header_rows > 0 => header_h + first_body.height,
+            _ => row_h,
+        };
+        let fits = flow.slot_top - needed >= flow.margin_bottom();
+        // Rows taller than a whole page are split rather than moved, unless
+        // they are marked cantSplit and overflow instead
+        let page_body_h = flow.page_top() - flow.margin_bottom();
+        if !fits && row_h > page_body_h && !table.rows[ri].cant_split {
+            relayout_fields(ri, flow, &mut row_layouts[ri]);
+            links.extend(render_split_row(
+                table,
+                ri,
+                &row_layouts,
+                &col_widths,
+                seen_fonts,
+                flow,
+            ));
+            continue;
+        }
+        if !fits && !flow.at_top() {
+            flow.next_table_column(col_widths.iter().sum());
+            if ri >= table.header_rows {
+                let headers =
+                    repeat_header_rows(table, &row_layouts, &col_widths, seen_fonts, flow);
+                links.extend(headers);
+            }
         }
+        relayout_fields(ri, flow, &mut row_layouts[ri]);
+        links.extend(render_table_row(
+            table,
+            ri,
+            &row_layouts,
+            &col_widths,
+            seen_fonts,
+            flow,
+            None,
+        ));
+    }
+    flow.end_tag();
+    links
+}
+
+/// Height of the start of a table that a preceding keepNext paragraph must
+/// stay with: the first row when it can't split, otherwise its first line.
+fn table_keep_height(
+    table: &Table,
+    doc: &Document,
+    flow: &Flow,
+    seen_fonts: &HashMap<String, FontEntry>,
+) -> f32 {
+    let col_widths = table_column_widths(table, flow, seen_fonts);
+    let row_layouts = compute_row_layouts(table, &col_widths, doc, seen_fonts, None);
+    let (Some(row), Some(layout)) = (table.rows.first(), row_layouts.first()) else {
+        return 0.0;
+    };
+    if row.cant_split {
+        return layout.height;
+    }
+    row.cells
+        .iter()
+        .zip(&layout.cell_lines)
+        .map(|(cell, (_, line_h, _))| cell.margins.top + line_h)
+        .fold(0.0, f32::max)
+        .min(layout.height)
+}
+
+/// Repeat the header rows at the top of a continuation page. The repeats are
+/// artifacts; the structure holds the header rows once.
+fn repeat_header_rows(
+    table: &Table,
+    row_layouts: &[RowLayout],
+    col_widths: &[f32],
+    seen_fonts: &HashMap<String, FontEntry>,
+    flow: &mut Flow,
+) -> Vec<(usize, LinkArea)> {
+    flow.begin_artifact();
+    let links = (0..table.header_rows)
+        .flat_map(|hi| render_table_row(table, hi, row_layouts, col_widths, seen_fonts, flow, None))
+        .collect();
+    flow.end_artifact();
+    links
+}
 
-        let row_top = *slot_top;
-        let row_bottom = row_top - row_h;
+/// Part of a table row placed on one page, when the row is split across pages.
+struct RowSlice {
+    lines: Vec<std::ops::Range<usize>>, // lines of each cell shown in this part
+    height: f32,
+    open_top: bool,    // continued from the previous page
+    open_bottom: bool, // continues on the next page
+}
 
-        // Render cell contents — text inset by cell padding
-        let mut cell_x = doc.margin_left;
-        for (ci, (cell, (lines, line_h, font_size))) in
-            row.cells.iter().zip(layout.cell_lines.iter()).enumerate()
+/// Render a row taller than a page in parts, breaking every cell's lines at
+/// the bottom of each page. Parts that continue on the next page leave the
+/// bottom border open, and the continuation leaves its top border open.
+fn render_split_row(
+    table: &Table,
+    ri: usize,
+    row_layouts: &[RowLayout],
+    col_widths: &[f32],
+    seen_fonts: &HashMap<String, FontEntry>,
+    flow: &mut Flow,
+) -> Vec<(usize, LinkArea)> {
+    let mut links = Vec::new();
+    let row = &table.rows[ri];
+    let layout = &row_layouts[ri];
+    let mut starts = vec![0; row.cells.len()];
+    let mut first = true;
+    loop {
+        let avail = flow.slot_top - flow.margin_bottom() - TABLE_BORDER_WIDTH;
+        let mut lines = Vec::with_capacity(row.cells.len());
+        let mut used_h: f32 = 0.0;
+        let mut progress = false;
+        for ((cell, (cell_lines, line_h, _)), &start) in
+            row.cells.iter().zip(&layout.cell_lines).zip(&starts)
         {
-            let col_w = col_widths.get(ci).copied().unwrap_or(cell.width);
-            let text_x = cell_x + TABLE_CELL_PAD_LEFT;
-            let text_w = col_w;
-
-            if !lines.is_empty() && !lines.iter().all(|l| l.chunks.is_empty()) {
-                let first_run = cell.paragraphs.first().and_then(|p| p.runs.first());
-                let ascender_ratio = first_run
-                    .map(font_key)
-                    .and_then(|k| seen_fonts.get(&k))
-                    .and_then(|e| e.ascender_ratio)
-                    .unwrap_or(0.75);
-                let baseline_y = row_top - TABLE_CELL_PAD_TOP - font_size * ascender_ratio;
-                let alignment = cell
-                    .paragraphs
-                    .first()
-                    .map(|p| p.alignment)
-                    .unwrap_or(Alignment::Left);
-
-                render_paragraph_lines(
-                    content,
-                    lines,
-                    &alignment,
-                    text_x,
-                    text_w,
-                    baseline_y,
-                    *line_h,
-                    lines.len(),
-                    0,
-                );
+            let text_h = avail - cell.margins.top - cell.margins.bottom;
+            let mut fit = (text_h / line_h).floor().max(0.0) as usize;
+            // A line taller than the whole page still has to go somewhere
+            if flow.at_top() {
+                fit = fit.max(1);
             }
-
-            cell_x += col_w;
+            let end = (start + fit).min(cell_lines.len());
+            progress |= end > start;
+            let cell_h = cell.margins.top + cell.margins.bottom + (end - start) as f32 * line_h;
+            used_h = used_h.max(cell_h);
+            lines.push(start..end);
+        }
+        let done = row
+            .cells
+            .iter()
+            .zip(&layout.cell_lines)
+            .zip(&lines)
+            .all(|((_, (cell_lines, _, _)), range)| range.end == cell_lines.len());
+        if !progress && !done {
+            flow.next_table_column(col_widths.iter().sum());
+            links.extend(repeat_header_rows(
+                table,
+                row_layouts,
+                col_widths,
+                seen_fonts,
+                flow,
+            ));
+            continue;
         }
 
-        // Draw cell borders — first cell extends left by pad_left,
-        // right border aligns with body text right edge.
-        content.save_state();
-        content.set_line_width(TABLE_BORDER_WIDTH);
-        let mut bx = doc.margin_left - TABLE_CELL_PAD_LEFT;
-        for (ci, cell) in row.cells.iter().enumerate() {
-            let col_w = col_widths.get(ci).copied().unwrap_or(cell.width);
-            let border_w = if ci == 0 {
-                col_w + TABLE_CELL_PAD_LEFT
-            } else {
-                col_w
-            };
-            content.rect(bx, row_bottom, border_w, row_h).stroke();
-            bx += border_w;
+        // Parts that continue fill the page down to the bottom margin
+        let height = if done {
+            used_h + TABLE_BORDER_WIDTH
+        } else {
+            avail + TABLE_BORDER_WIDTH
+        };
+        for (start, range) in starts.iter_mut().zip(&lines) {
+            *start = range.end;
         }
-        content.restore_state();
+        let slice = RowSlice {
+            lines,
+            height,
+            open_top: !first,
+            open_bottom: !done,
+        };
+        links.extend(render_table_row(
+            table,
+            ri,
+            row_layouts,
+            col_widths,
+            seen_fonts,
+            flow,
+            Some(&slice),
+        ));
+        if done {
+            break;
+        }
+        first = false;
+        flow.next_table_column(col_widths.iter().sum());
+        links.extend(repeat_header_rows(
+            table,
+            row_layouts,
+            col_widths,
+            seen_fonts,
+            flow,
+        ));
+    }
+    links
+}
 
-        *slot_top = row_bottom;
+/// How far the table's first cell border sticks out left of its text: the
+/// left margin of the first cell in the first row.
+fn table_outdent(table: &Table) -> f32 {
+    table
+        .rows
+        .first()
+        .and_then(|r| r.cells.first())
+        .map_or(0.0, |c| c.margins.left)
+}
+
+/// X where the text of the table's first column starts. Centered and
+/// right-aligned tables position their whole border box, outdent included.
+fn table_left(table: &Table, col_widths: &[f32], flow: &Flow) -> f32 {
+    let table_w: f32 = col_widths.iter().sum();
+    let (area_left, area_w) = flow.table_area(table_w);
+    let outdent = table_outdent(table);
+    let box_w = table_w + outdent;
+    match table.alignment {
+        Alignment::Center => area_left + (area_w - box_w) / 2.0 + outdent,
+        Alignment::Right => area_left + area_w - box_w + outdent,
+        Alignment::Left | Alignment::Justify => area_left + table.indent,
     }
 }
 
-fn render_header_footer(
-    content: &mut Content,
-    hf: &HeaderFooter,
+/// Draw row `ri` of a table, or one `slice` of it, at the current flow
+/// position and move below it.
+fn render_table_row(
+    table: &Table,
+    ri: usize,
+    row_layouts: &[RowLayout],
+    col_widths: &[f32],
     seen_fonts: &HashMap<String, FontEntry>,
-    doc: &Document,
-    is_header: bool,
-    page_num: usize,
-    total_pages: usize,
-) {
-    let text_width = doc.page_width - doc.margin_left - doc.margin_right;
-
-    for para in &hf.paragraphs {
-        if para.runs.is_empty() {
-            continue;
+    flow: &mut Flow,
+    slice: Option<&RowSlice>,
+) -> Vec<(usize, LinkArea)> {
+    let mut links = Vec::new();
+    let row = &table.rows[ri];
+    let layout = &row_layouts[ri];
+    let row_h = slice.map_or(layout.height, |s| s.height);
+    let row_top = flow.slot_top;
+    let row_bottom = row_top - row_h;
+    let table_left = table_left(table, col_widths, flow);
+    let content = &mut flow.content;
+    let tags = &mut flow.tags;
+
+    // The row's structure element is opened by its first part and closed by
+    // its last, with a cell element for every cell that isn't merged into the
+    // one above
+    if let Some(tags) = tags.as_mut()
+        && slice.is_none_or(|s| !s.open_top)
+    {
+        tags.open(StructRole::TR, None);
+        let role = if ri < table.header_rows {
+            StructRole::TH
+        } else {
+            StructRole::TD
+        };
+        for _ in row.cells.iter().filter(|c| c.v_merge != VMerge::Continue) {
+            tags.add(role, None);
         }
-
-        // Substitute field codes with actual values
-        let substituted_runs: Vec<Run> = para
-            .runs
-            .iter()
-            .map(|run| {
-                if let Some(ref fc) = run.field_code {
-                    let text = match fc {
-                        FieldCode::Page => page_num.to_string(),
-                        FieldCode::NumPages => total_pages.to_string(),
-                    };
-                    Run {
-                        text,
-                        font_size: run.font_size,
-                        font_name: run.font_name.clone(),
-                        bold: run.bold,
-                        italic: run.italic,
-                        underline: run.underline,
-                        strikethrough: run.strikethrough,
-                        color: run.color,
-                        is_tab: false,
-                        vertical_align: run.vertical_align,
-                        field_code: None,
-                    }
-                } else {
-                    Run {
-                        text: run.text.clone(),
-                        font_size: run.font_size,
-                        font_name: run.font_name.clone(),
-                        bold: run.bold,
-                        italic: run.italic,
-                        underline: run.underline,
-                        strikethrough: run.strikethrough,
-                        color: run.color,
-                        is_tab: run.is_tab,
-                        vertical_align: run.vertical_align,
-                        field_code: None,
-                    }
+    }
+    let mut cell_tags = tags
+        .as_ref()
+        .map(|t| t.children())
+        .unwrap_or_default()
+        .into_iter();
+
+    // Cell rectangles — first cell extends left by its left margin, right border
+    // aligns with body text right edge. A merged cell covers its restart row
+    // and the continuation rows below; continuation cells get no rectangle.
+    let mut cell_rects = Vec::with_capacity(row.cells.len());
+    let outdent = table_outdent(table);
+    let mut bx = table_left - outdent;
+    let mut grid_col = 0;
+    for (ci, cell) in row.cells.iter().enumerate() {
+        let col_w = cell_span_width(col_widths, grid_col, cell);
+        let border_w = if ci == 0 { col_w + outdent } else { col_w };
+        cell_rects.push(match cell.v_merge {
+            VMerge::None => Some((bx, row_bottom, border_w, row_h, cell.borders)),
+            // Merges aren't carried across the parts of a split row
+            VMerge::Restart if slice.is_some() => {
+                Some((bx, row_bottom, border_w, row_h, cell.borders))
+            }
+            VMerge::Restart => {
+                let merged_h = merged_height(table, row_layouts, ri, grid_col);
+                // The merged cell's bottom edge is that of the last row it covers
+                let n = merged_row_count(table, ri, grid_col);
+                let mut borders = cell.borders;
+                if let Some(last) = cell_at_grid(&table.rows[ri + n - 1], grid_col) {
+                    borders.bottom = last.borders.bottom;
                 }
-            })
-            .collect();
-
-        let lines = build_paragraph_lines(&substituted_runs, seen_fonts, text_width);
+                Some((bx, row_top - merged_h, border_w, merged_h, borders))
+            }
+            VMerge::Continue => None,
+        });
+        grid_col += cell.grid_span;
+        bx += border_w;
+    }
+    if let Some(slice) = slice {
+        for (_, _, _, _, borders) in cell_rects.iter_mut().flatten() {
+            if slice.open_top {
+                borders.top = None;
+            }
+            if slice.open_bottom {
+                borders.bottom = None;
+            }
+        }
+    }
 
-        let (font_size, _, tallest_ar) = tallest_run_metrics(&substituted_runs, seen_fonts);
-        let ascender_ratio = tallest_ar.unwrap_or(0.75);
+    // Cell shading goes underneath the text and borders
+    if let Some(tags) = tags.as_mut() {
+        tags.begin_artifact(content);
+    }
+    for (cell, rect) in row.cells.iter().zip(&cell_rects) {
+        if let (Some([r, g, b]), Some((x, y, w, h, _))) = (cell.shading, rect) {
+            content.save_state();
+            content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+            content.rect(*x, *y, *w, *h).fill_nonzero();
+            content.restore_state();
+        }
+    }
+    if let Some(tags) = tags.as_mut() {
+        tags.end_artifact(content);
+    }
 
-        let baseline_y = if is_header {
-            doc.page_height - doc.header_margin - font_size * ascender_ratio
+    // Render cell contents — text inset by the cell margins
+    let mut cell_x = table_left;
+    let mut grid_col = 0;
+    for (ci, (cell, (lines, line_h, font_size))) in
+        row.cells.iter().zip(layout.cell_lines.iter()).enumerate()
+    {
+        let col_w = cell_span_width(col_widths, grid_col, cell);
+        let text_x = cell_x + cell.margins.left;
+        let text_w = (col_w - cell.margins.left - cell.margins.right).max(1.0);
+        // Shift the text down into the slack below it; merged cells measure
+        // the slack across all the rows they cover and center by default.
+        // Parts of a split row are filled from the top.
+        let (cell_h, default_align) = if cell.v_merge == VMerge::Restart {
+            (
+                merged_height(table, row_layouts, ri, grid_col),
+                CellVAlign::Center,
+            )
+        } else {
+            (row_h, CellVAlign::Top)
+        };
+        let slack = (cell_h - TABLE_BORDER_WIDTH - layout.cell_heights[ci]).max(0.0);
+        let v_offset = match cell.v_align.unwrap_or(default_align) {
+            _ if slice.is_some() => 0.0,
+            CellVAlign::Top => 0.0,
+            CellVAlign::Center => slack / 2.0,
+            CellVAlign::Bottom => slack,
+        };
+        grid_col += cell.grid_span;
+        let range = slice.map_or(0..lines.len(), |s| s.lines[ci].clone());
+        let shown = &lines[range.clone()];
+        let cell_tag = if cell.v_merge == VMerge::Continue {
+            None
         } else {
-            doc.footer_margin + font_size * (1.0 - ascender_ratio)
+            cell_tags.next()
         };
 
-        let effective_ls = para.line_spacing.unwrap_or(doc.line_spacing);
-        let line_h = font_metric(&substituted_runs, seen_fonts, |e| e.line_h_ratio)
-            .map(|ratio| font_size * ratio * effective_ls)
-            .unwrap_or(font_size * 1.2);
+        if cell.v_merge != VMerge::Continue
+            && !shown.is_empty()
+            && !shown.iter().all(|l| l.chunks.is_empty())
+        {
+            let first_run = cell.paragraphs.first().and_then(|p| p.runs.first());
+            let ascender_ratio = first_run
+                .map(font_key)
+                .and_then(|k| seen_fonts.get(&k))
+                .and_then(|e| e.ascender_ratio)
+                .unwrap_or(0.75);
+            let baseline_y = row_top - v_offset - cell.margins.top - font_size * ascender_ratio;
+            let alignment = cell
+                .paragraphs
+                .first()
+                .map(|p| p.alignment)
+                .unwrap_or(Alignment::Left);
+
+            let page_idx = flow.pages;
+            if let (Some(tags), Some(elem)) = (tags.as_mut(), cell_tag) {
+                tags.mark(content, page_idx, elem);
+            }
+            let mut areas = render_paragraph_lines(
+                content,
+                shown,
+                &alignment,
+                text_x,
+                text_w,
+                baseline_y,
+                *line_h,
+                lines.len(),
+                range.start,
+            );
+            tag_links(tags, &mut areas);
+            if let Some(tags) = tags.as_mut() {
+                tags.unmark(content);
+            }
+            links.extend(areas.into_iter().map(|a| (page_idx, a)));
+        }
 
-        render_paragraph_lines(
-            content,
-            &lines,
-            &para.alignment,
-            doc.margin_left,
-            text_width,
-            baseline_y,
-            line_h,
-            lines.len(),
-            0,
-        );
+        cell_x += col_w;
     }
-}
-
-pub fn render(doc: &Document) -> Result<Vec<u8>, Error> {
-    let mut pdf = Pdf::new();
-    let mut next_id = 1i32;
-    let mut alloc = || {
-        let r = Ref::new(next_id);
-        next_id += 1;
-        r
-    };
 
-    let catalog_id = alloc();
-    let pages_id = alloc();
+    // Draw cell borders, each edge with its own width and color
+    if let Some(tags) = tags.as_mut() {
+        tags.begin_artifact(content);
+    }
+    content.save_state();
+    for (x, y, w, h, borders) in cell_rects.into_iter().flatten() {
+        let edges = [
+            (borders.top, (x, y + h), (x + w, y + h)),
+            (borders.bottom, (x, y), (x + w, y)),
+            (borders.left, (x, y), (x, y + h)),
+            (borders.right, (x + w, y), (x + w, y + h)),
+        ];
+        for (border, (x0, y0), (x1, y1)) in edges {
+            let Some(b) = border else {
+                continue;
+            };
+            let [r, g, bl] = b.color;
+            content
+                .set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, bl as f32 / 255.0)
+                .set_line_width(b.width_pt)
+                .move_to(x0, y0)
+                .line_to(x1, y1)
+                .stroke();
+        }
+    }
+    content.restore_state();
+    if let Some(tags) = tags.as_mut() {
+        tags.end_artifact(content);
+        if slice.is_none_or(|s| !s.open_bottom) {
+            tags.close(content);
+        }
+    }
 
-    // Phase 1: collect unique font names (with variant) and embed them
-    let mut seen_fonts: HashMap<String, FontEntry> = HashMap::new();
-    let mut font_order: Vec<String> = Vec::new();
+    flow.slot_top = row_bottom;
+    links
+}
 
-    // Collect all runs from all blocks (paragraphs, table cells, headers/footers)
-    let hf_options = [
-        &doc.header_default,
-        &doc.header_first,
-        &doc.footer_default,
-        &doc.footer_first,
+/// Format a date-time with a Word field picture such as `dd.MM.yyyy` or `h:mm am/pm`.
+fn format_date_picture(now: &jiff::Zoned, picture: &str) -> String {
+    const MONTHS: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
     ];
-    let hf_runs = hf_options
-        .iter()
-        .filter_map(|hf| hf.as_ref())
-        .flat_map(|hf| hf.paragraphs.iter())
-        .flat_map(|p| p.runs.iter());
-
-    let all_runs: Vec<&Run> = doc
-        .blocks
-        .iter()
-        .flat_map(|block| -> Box<dyn Iterator<Item = &Run> + '_> {
-            match block {
-                Block::Paragraph(para) => Box::new(para.runs.iter()),
-                Block::Table(table) => Box::new(
-                    table
-                        .rows
-                        .iter()
-                        .flat_map(|row| row.cells.iter())
-                        .flat_map(|cell| cell.paragraphs.iter())
-                        .flat_map(|para| para.runs.iter()),
-                ),
+    const WEEKDAYS: [&str; 7] = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+    let month = MONTHS[now.month() as usize - 1];
+    let weekday = WEEKDAYS[now.weekday().to_monday_zero_offset() as usize];
+    let hour12 = (now.hour() + 11) % 12 + 1;
+
+    let chars: Vec<char> = picture.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let ahead: String = chars[i..].iter().take(5).collect();
+        if ahead.eq_ignore_ascii_case("am/pm") {
+            let marker = if now.hour() >= 12 { "PM" } else { "AM" };
+            if c == 'a' {
+                out.push_str(&marker.to_lowercase());
+            } else {
+                out.push_str(marker);
             }
-        })
-        .chain(hf_runs)
-        .collect();
-
-    for run in &all_runs {
-        let key = font_key(run);
-        if !seen_fonts.contains_key(&key) {
-            let base = primary_font_name(&run.font_name);
-            let pdf_name = format!("F{}", font_order.len() + 1);
-            let entry = register_font(
-                &mut pdf,
-                base,
-                run.bold,
-                run.italic,
-                pdf_name,
-                &mut alloc,
-                &doc.embedded_fonts,
-            );
-            seen_fonts.insert(key.clone(), entry);
-            font_order.push(key);
+            i += 5;
+            continue;
+        }
+        if c == '\'' {
+            // Quoted literal text
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&q| q == '\'')
+                .map_or(chars.len(), |p| i + 1 + p);
+            out.extend(&chars[i + 1..end]);
+            i = end + 1;
+            continue;
         }
+        let count = chars[i..].iter().take_while(|&&x| x == c).count();
+        match (c, count) {
+            ('d', 1) => out.push_str(&now.day().to_string()),
+            ('d', 2) => out.push_str(&format!("{:02}", now.day())),
+            ('d', 3) => out.push_str(&weekday[..3]),
+            ('d', _) => out.push_str(weekday),
+            ('M', 1) => out.push_str(&now.month().to_string()),
+            ('M', 2) => out.push_str(&format!("{:02}", now.month())),
+            ('M', 3) => out.push_str(&month[..3]),
+            ('M', _) => out.push_str(month),
+            ('y', 1 | 2) => out.push_str(&format!("{:02}", now.year() % 100)),
+            ('y', _) => out.push_str(&now.year().to_string()),
+            ('h', 1) => out.push_str(&hour12.to_string()),
+            ('h', _) => out.push_str(&format!("{:02}", hour12)),
+            ('H', 1) => out.push_str(&now.hour().to_string()),
+            ('H', _) => out.push_str(&format!("{:02}", now.hour())),
+            ('m', 1) => out.push_str(&now.minute().to_string()),
+            ('m', _) => out.push_str(&format!("{:02}", now.minute())),
+            ('s', 1) => out.push_str(&now.second().to_string()),
+            ('s', _) => out.push_str(&format!("{:02}", now.second())),
+            _ => out.extend(std::iter::repeat_n(c, count)),
+        }
+        i += count;
     }
+    out
+}
 
-    if seen_fonts.is_empty() {
-        let pdf_name = "F1".to_string();
-        let entry = register_font(
-            &mut pdf,
-            "Helvetica",
-            false,
-            false,
-            pdf_name,
-            &mut alloc,
-            &doc.embedded_fonts,
-        );
-        seen_fonts.insert("Helvetica".to_string(), entry);
-        font_order.push("Helvetica".to_string());
-    }
+/// The number shown for a page: numbering restarts at the nearest section
+/// with a `w:pgNumType` start value, otherwise it counts physical pages.
+fn page_number(doc: &Document, section_first_pages: &[usize], page_idx: usize) -> usize {
+    let sect_idx = section_first_pages
+        .iter()
+        .rposition(|&first| first <= page_idx)
+        .unwrap_or(0);
+    (0..=sect_idx)
+        .rev()
+        .find_map(|s| {
+            doc.sections[s]
+                .page_number_start
+                .map(|start| start + page_idx - section_first_pages[s])
+        })
+        .unwrap_or(page_idx + 1)
+}
 
-    let text_width = doc.page_width - doc.margin_left - doc.margin_right;
+/// Whether any run of `paragraphs` is a field that `is_kind` picks. Table
+/// cells hold only paragraphs, so this covers every field of a table.
+fn paragraphs_have_field(paragraphs: &[Paragraph], is_kind: impl Fn(&FieldCode) -> bool) -> bool {
+    paragraphs
+        .iter()
+        .flat_map(|p| &p.runs)
+        .any(|r| r.field_code.as_ref().is_some_and(&is_kind))
+}
 
-    // Phase 1b: embed images
-    let mut image_pdf_names: HashMap<usize, String> = HashMap::new();
-    let mut image_xobjects: Vec<(String, Ref)> = Vec::new();
-    for (block_idx, block) in doc.blocks.iter().enumerate() {
-        if let Block::Paragraph(para) = block
-            && let Some(img) = &para.image
-        {
-            let xobj_ref = alloc();
-            let pdf_name = format!("Im{}", image_xobjects.len() + 1);
+/// What fields show where a paragraph is drawn.
+struct FieldValues<'a> {
+    page_label: &'a str, // the page number, formatted for its section
+    total_pages: usize,
+    now: &'a jiff::Zoned, // for DATE and TIME
+}
 
-            let mut xobj = pdf.image_xobject(xobj_ref, &img.data);
-            xobj.filter(Filter::DctDecode);
-            xobj.width(img.pixel_width as i32);
-            xobj.height(img.pixel_height as i32);
-            xobj.color_space().device_rgb();
-            xobj.bits_per_component(8);
+/// Replace field runs (PAGE, NUMPAGES, DATE, ...) with runs of their values.
+/// Other runs are borrowed, so headers and footers drawn on every page only
+/// copy their field runs.
+fn substitute_fields<'a>(
+    runs: &'a [Run],
+    doc: &Document,
+    fields: &FieldValues,
+) -> Vec<Cow<'a, Run>> {
+    runs.iter()
+        .map(|run| {
+            let Some(fc) = &run.field_code else {
+                return Cow::Borrowed(run);
+            };
+            let text = match fc {
+                FieldCode::Page => fields.page_label.to_string(),
+                FieldCode::NumPages => fields.total_pages.to_string(),
+                FieldCode::Date(picture) => {
+                    format_date_picture(fields.now, picture.as_deref().unwrap_or("M/d/yyyy"))
+                }
+                FieldCode::Time(picture) => {
+                    format_date_picture(fields.now, picture.as_deref().unwrap_or("h:mm am/pm"))
+                }
+                FieldCode::Filename => doc.file_name.clone(),
+            };
+            Cow::Owned(Run {
+                text,
+                is_tab: false,
+                is_line_break: false,
+                is_page_break: false,
+                field_code: None,
+                ..run.clone()
+            })
+        })
+        .collect()
+}
 
-            image_xobjects.push((pdf_name.clone(), xobj_ref));
-            image_pdf_names.insert(block_idx, pdf_name);
-        }
+fn page_border_thickness(b: &PageBorder) -> f32 {
+    match b.style {
+        BorderStyle::Single => b.width_pt,
+        BorderStyle::Double => 3.0 * b.width_pt, // two lines with a gap of the same width
     }
+}
 
-    // Phase 2: build multi-page content streams
-    let mut all_contents: Vec<Content> = Vec::new();
-    let mut current_content = Content::new();
-    let mut slot_top = doc.page_height - doc.margin_top;
-    let mut prev_space_after: f32 = 0.0;
+/// Stroke the `w:pgBorders` frame. With offsetFrom="text" the space is
+/// measured outward from the margins, otherwise inward from the page edge.
+fn draw_page_borders(content: &mut Content, sect: &Section, borders: &PageBorders) {
+    // Distance from the reference edge to the centre of each edge's stroke
+    let inset = |b: &Option<PageBorder>| {
+        b.as_ref()
+            .map_or(0.0, |b| b.space_pt + page_border_thickness(b) / 2.0)
+    };
+    let (left, right, top, bottom) = if borders.offset_from_page {
+        (
+            inset(&borders.left),
+            sect.page_width - inset(&borders.right),
+            sect.page_height - inset(&borders.top),
+            inset(&borders.bottom),
+        )
+    } else {
+        (
+            sect.margin_left - inset(&borders.left),
+            sect.page_width - sect.margin_right + inset(&borders.right),
+            sect.page_height - sect.margin_top + inset(&borders.top),
+            sect.margin_bottom - inset(&borders.bottom),
+        )
+    };
+
+    // (border, start, end, outward normal); edges run left-to-right or bottom-to-top
+    let edges = [
+        (&borders.top, (left, top), (right, top), (0.0_f32, 1.0_f32)),
+        (
+            &borders.bottom,
+            (left, bottom),
+            (right, bottom),
+            (0.0, -1.0),
+        ),
+        (&borders.left, (left, bottom), (left, top), (-1.0, 0.0)),
+        (&borders.right, (right, bottom), (right, top), (1.0, 0.0)),
+    ];
+    content
+        .save_state()
+        .set_line_cap(LineCapStyle::ProjectingSquareCap);
+    for (border, (x0, y0), (x1, y1), (nx, ny)) in edges {
+        let Some(b) = border else {
+            continue;
+        };
+        let [r, g, bl] = b.color;
+        content
+            .set_stroke_rgb(r as f32 / 255.0, g as f32 / 255.0, bl as f32 / 255.0)
+            .set_line_width(b.width_pt);
+        // Double borders: the outer line grows past the corners, the inner one shrinks
+        let offsets: &[f32] = match b.style {
+            BorderStyle::Single => &[0.0],
+            BorderStyle::Double => &[b.width_pt, -b.width_pt],
+        };
+        let (dx, dy) = (ny.abs(), nx.abs());
+        for &o in offsets {
+            content
+                .move_to(x0 + nx * o - dx * o, y0 + ny * o - dy * o)
+                .line_to(x1 + nx * o + dx * o, y1 + ny * o + dy * o)
+                .stroke();
+        }
+    }
+    content.restore_state();
+}
+
+/// Page-level values used when rendering headers and footers.
+struct PageContext<'a> {
+    sect: &'a Section,
+    page_num: usize, // displayed number, before formatting
+    total_pages: usize,
+    now: &'a jiff::Zoned,
+}
+
+/// A header, footer or text box paragraph broken into lines.
+struct StackedPara<'a> {
+    para: &'a Paragraph,
+    lines: Vec<TextLine>,
+    height: f32, // of the lines, or of the image
+    font_size: f32,
+    ascender_ratio: f32,
+    line_h: f32,
+}
+
+/// Tab stops of Word's Header and Footer styles, for paragraphs without their
+/// own: centered in the text area and right-aligned at the right margin.
+fn header_footer_tab_stops(text_width: f32) -> Vec<TabStop> {
+    vec![
+        TabStop {
+            position: text_width / 2.0,
+            alignment: TabAlignment::Center,
+            leader: None,
+        },
+        TabStop {
+            position: text_width,
+            alignment: TabAlignment::Right,
+            leader: None,
+        },
+    ]
+}
+
+/// Break non-empty paragraphs into lines, with fields substituted for the
+/// given page. Image paragraphs have no lines. `default_tab_stops` apply to
+/// paragraphs without their own.
+fn stacked_paras<'a>(
+    paragraphs: &'a [Paragraph],
+    seen_fonts: &HashMap<String, FontEntry>,
+    doc: &Document,
+    text_width: f32,
+    default_tab_stops: &[TabStop],
+    fields: &FieldValues,
+) -> Vec<StackedPara<'a>> {
+    paragraphs
+        .iter()
+        .filter(|para| !para.runs.is_empty() || para.image.is_some())
+        .map(|para| {
+            // Fields are substituted first so tab alignment measures their values
+            let runs = substitute_fields(&para.runs, doc, fields);
+            let lines = if para.image.is_some() {
+                vec![]
+            } else if runs.iter().any(|r| r.is_tab && !r.hidden) {
+                let tabs = TabSettings {
+                    stops: if para.tab_stops.is_empty() {
+                        default_tab_stops
+                    } else {
+                        &para.tab_stops
+                    },
+                    indent_left: 0.0,
+                    decimal_separator: doc.decimal_separator,
+                };
+                runs.split(|r| r.is_line_break && !r.hidden)
+                    .enumerate()
+                    .flat_map(|(i, seg)| {
+                        let start_x = if i == 0 { para.indent_first_line } else { 0.0 };
+                        build_tabbed_line(seg, seen_fonts, &tabs, text_width, start_x)
+                    })
+                    .collect()
+            } else {
+                build_paragraph_lines(&runs, seen_fonts, text_width, para.indent_first_line)
+            };
+            let (font_size, _, tallest_ar) = tallest_run_metrics(&runs, seen_fonts);
+            let line_h = line_height(
+                para.line_spacing.unwrap_or(doc.line_spacing),
+                font_size,
+                font_metric(&runs, seen_fonts, |e| e.line_h_ratio),
+            );
+            let height = match &para.image {
+                Some(img) => img.display_height,
+                None => lines.len() as f32 * line_h,
+            };
+            StackedPara {
+                para,
+                lines,
+                height,
+                font_size,
+                ascender_ratio: tallest_ar.unwrap_or(0.75),
+                line_h,
+            }
+        })
+        .collect()
+}
+
+/// Draw a stacked paragraph whose top edge is at `top`.
+fn draw_stacked_para(
+    content: &mut Content,
+    p: &StackedPara,
+    x: f32,
+    width: f32,
+    top: f32,
+    image_pdf_names: &HashMap<*const EmbeddedImage, String>,
+) -> Vec<LinkArea> {
+    let Some(img) = &p.para.image else {
+        return render_paragraph_lines(
+            content,
+            &p.lines,
+            &p.para.alignment,
+            x,
+            width,
+            top - p.font_size * p.ascender_ratio,
+            p.line_h,
+            p.lines.len(),
+            0,
+        );
+    };
+    if let Some(pdf_name) = image_pdf_names.get(&(img as *const EmbeddedImage)) {
+        let free = (width - img.display_width).max(0.0);
+        let img_x = x + match p.para.alignment {
+            Alignment::Center => free / 2.0,
+            Alignment::Right => free,
+            Alignment::Left | Alignment::Justify => 0.0,
+        };
+        content.save_state();
+        content.transform([
+            img.display_width,
+            0.0,
+            0.0,
+            img.display_height,
+            img_x,
+            top - img.display_height,
+        ]);
+        content.x_object(Name(pdf_name.as_bytes()));
+        content.restore_state();
+    }
+    Vec::new()
+}
+
+/// Draw paragraphs one below the other, starting at `top`.
+fn draw_stacked_down(
+    content: &mut Content,
+    paras: &[StackedPara],
+    x: f32,
+    width: f32,
+    mut top: f32,
+    image_pdf_names: &HashMap<*const EmbeddedImage, String>,
+) -> Vec<LinkArea> {
+    let mut links = Vec::new();
+    let mut prev_space_after = None;
+    for p in paras {
+        if let Some(after) = prev_space_after {
+            top -= f32::max(after, p.para.space_before);
+        }
+        links.extend(draw_stacked_para(
+            content,
+            p,
+            x,
+            width,
+            top,
+            image_pdf_names,
+        ));
+        top -= p.height;
+        prev_space_after = Some(p.para.space_after);
+    }
+    links
+}
+
+/// Headers stack their paragraphs down from the header distance; footers stack
+/// them up from the footer distance so the last paragraph sits at the bottom.
+fn render_header_footer(
+    content: &mut Content,
+    hf: &HeaderFooter,
+    is_header: bool,
+    seen_fonts: &HashMap<String, FontEntry>,
+    image_pdf_names: &HashMap<*const EmbeddedImage, String>,
+    doc: &Document,
+    page: &PageContext,
+) -> Vec<LinkArea> {
+    let sect = page.sect;
+    let text_width = sect.page_width - sect.margin_left - sect.margin_right;
+    let page_label = format_number(page.page_num, &sect.page_number_format);
+    let paras = stacked_paras(
+        &hf.paragraphs,
+        seen_fonts,
+        doc,
+        text_width,
+        &header_footer_tab_stops(text_width),
+        &FieldValues {
+            page_label: &page_label,
+            total_pages: page.total_pages,
+            now: page.now,
+        },
+    );
+    let x = sect.margin_left;
+
+    if is_header {
+        let top = sect.page_height - sect.header_margin;
+        return draw_stacked_down(content, &paras, x, text_width, top, image_pdf_names);
+    }
+    let mut links = Vec::new();
+    let mut bottom = sect.footer_margin;
+    let mut next_space_before = None;
+    for p in paras.iter().rev() {
+        if let Some(before) = next_space_before {
+            bottom += f32::max(p.para.space_after, before);
+        }
+        // The last line's descent rests on the footer distance
+        let top = if p.para.image.is_some() {
+            bottom + p.height
+        } else {
+            bottom + p.font_size + p.lines.len().saturating_sub(1) as f32 * p.line_h
+        };
+        links.extend(draw_stacked_para(
+            content,
+            p,
+            x,
+            text_width,
+            top,
+            image_pdf_names,
+        ));
+        bottom = top;
+        next_space_before = Some(p.para.space_before);
+    }
+    links
+}
+
+/// Height of a header or footer as `render_header_footer` stacks it. Fields
+/// are measured with placeholder page numbers.
+fn header_footer_height(
+    hf: &HeaderFooter,
+    seen_fonts: &HashMap<String, FontEntry>,
+    doc: &Document,
+    sect: &Section,
+    now: &jiff::Zoned,
+) -> f32 {
+    let text_width = sect.page_width - sect.margin_left - sect.margin_right;
+    let page_label = format_number(1, &sect.page_number_format);
+    let paras = stacked_paras(
+        &hf.paragraphs,
+        seen_fonts,
+        doc,
+        text_width,
+        &header_footer_tab_stops(text_width),
+        &FieldValues {
+            page_label: &page_label,
+            total_pages: 1,
+            now,
+        },
+    );
+    let lines_h: f32 = paras.iter().map(|p| p.height).sum();
+    let gaps: f32 = paras
+        .windows(2)
+        .map(|w| f32::max(w[0].para.space_after, w[1].para.space_before))
+        .sum();
+    lines_h + gaps
+}
+
+/// Draw the text boxes anchored in `para`, whose top is at `para_top` on the
+/// current page. Text is clipped to the box.
+fn draw_text_boxes(
+    flow: &mut Flow,
+    para: &Paragraph,
+    para_top: f32,
+    seen_fonts: &HashMap<String, FontEntry>,
+    image_pdf_names: &HashMap<*const EmbeddedImage, String>,
+    total_pages: usize,
+) -> Vec<LinkArea> {
+    let sect = flow.sect;
+    let page_num = page_number(flow.doc, &flow.section_first_pages, flow.page_index());
+    let page_label = format_number(page_num, &sect.page_number_format);
+    let mut links = Vec::new();
+    for tb in &para.text_boxes {
+        let (ref_x, ref_w) = match tb.h_relative {
+            FloatRelative::Page => (0.0, sect.page_width),
+            FloatRelative::Margin => (
+                sect.margin_left,
+                sect.page_width - sect.margin_left - sect.margin_right,
+            ),
+            FloatRelative::Paragraph => (flow.column_left(), flow.column_width()),
+        };
+        let (ref_top, ref_h) = match tb.v_relative {
+            FloatRelative::Page => (sect.page_height, sect.page_height),
+            FloatRelative::Margin => (
+                sect.page_height - sect.margin_top,
+                sect.page_height - sect.margin_top - sect.margin_bottom,
+            ),
+            FloatRelative::Paragraph => (para_top, 0.0),
+        };
+        let along = |pos: FloatPosition, free: f32| match pos {
+            FloatPosition::Offset(o) => o,
+            FloatPosition::Start => 0.0,
+            FloatPosition::Center => free / 2.0,
+            FloatPosition::End => free,
+        };
+        let x = ref_x + along(tb.h_position, ref_w - tb.width);
+        let top = ref_top - along(tb.v_position, ref_h - tb.height);
+
+        let [inset_left, inset_top, inset_right, _] = tb.insets;
+        let text_width = (tb.width - inset_left - inset_right).max(1.0);
+        let paras = stacked_paras(
+            &tb.paragraphs,
+            seen_fonts,
+            flow.doc,
+            text_width,
+            &[],
+            &FieldValues {
+                page_label: &page_label,
+                total_pages,
+                now: flow.now,
+            },
+        );
+        flow.begin_float_tag(StructRole::Div);
+        flow.content.save_state();
+        flow.content
+            .rect(x, top - tb.height, tb.width, tb.height)
+            .clip_nonzero()
+            .end_path();
+        let mut areas = draw_stacked_down(
+            &mut flow.content,
+            &paras,
+            x + inset_left,
+            text_width,
+            top - inset_top,
+            image_pdf_names,
+        );
+        tag_links(&mut flow.tags, &mut areas);
+        links.extend(areas);
+        flow.content.restore_state();
+        flow.end_tag();
+    }
+    links
+}
+
+/// The header or footer shown on a page of `sect`.
+fn page_header_footer(
+    sect: &Section,
+    is_header: bool,
+    is_first: bool,
+    is_even: bool,
+) -> Option<&HeaderFooter> {
+    let (first, even, default) = if is_header {
+        (&sect.header_first, &sect.header_even, &sect.header_default)
+    } else {
+        (&sect.footer_first, &sect.footer_even, &sect.footer_default)
+    };
+    if is_first && sect.different_first_page {
+        first.as_deref()
+    } else if is_even {
+        even.as_deref()
+    } else {
+        default.as_deref()
+    }
+}
+
+/// Distances from the page edges to the body on one page.
+#[derive(Clone, Copy)]
+struct PageMargins {
+    top: f32,
+    bottom: f32,
+}
+
+/// Body margins of a section's first, even and other pages. Each is the page
+/// margin, or further in when the page's header or footer reaches past it.
+struct SectionMargins {
+    first: PageMargins,
+    even: PageMargins,
+    default: PageMargins,
+}
+
+impl SectionMargins {
+    fn measure(
+        sect: &Section,
+        seen_fonts: &HashMap<String, FontEntry>,
+        doc: &Document,
+        now: &jiff::Zoned,
+    ) -> Self {
+        let page = |is_first: bool, is_even: bool| {
+            // Space after the last header line (before the first footer line)
+            // also separates it from the body
+            let header_bottom = page_header_footer(sect, true, is_first, is_even).map(|hf| {
+                let gap = hf.paragraphs.last().map_or(0.0, |p| p.space_after);
+                sect.header_margin + header_footer_height(hf, seen_fonts, doc, sect, now) + gap
+            });
+            let footer_top = page_header_footer(sect, false, is_first, is_even).map(|hf| {
+                let gap = hf.paragraphs.first().map_or(0.0, |p| p.space_before);
+                sect.footer_margin + header_footer_height(hf, seen_fonts, doc, sect, now) + gap
+            });
+            PageMargins {
+                top: header_bottom.map_or(sect.margin_top, |b| b.max(sect.margin_top)),
+                bottom: footer_top.map_or(sect.margin_bottom, |t| t.max(sect.margin_bottom)),
+            }
+        };
+        SectionMargins {
+            first: page(true, false),
+            even: page(false, true),
+            default: page(false, false),
+        }
+    }
+}
+
+/// Body margins of page `page_idx`, from the header and footer shown on it.
+fn page_margins(
+    doc: &Document,
+    margins: &[SectionMargins],
+    section_first_pages: &[usize],
+    page_idx: usize,
+) -> PageMargins {
+    let sect_idx = section_first_pages
+        .iter()
+        .rposition(|&first| first <= page_idx)
+        .unwrap_or(0);
+    let m = &margins[sect_idx];
+    if page_idx == section_first_pages[sect_idx] && doc.sections[sect_idx].different_first_page {
+        m.first
+    } else if doc.even_and_odd_headers
+        && page_number(doc, section_first_pages, page_idx).is_multiple_of(2)
+    {
+        m.even
+    } else {
+        m.default
+    }
+}
+
+/// Receives each page as layout finishes it: its index, the first page of
+/// each section so far, and the body content drawn on it.
+type WritePage<'w> = dyn FnMut(usize, &[usize], Content) + 'w;
+
+/// Position in the body flow: finished pages, the page being filled, and the
+/// column and vertical slot on it.
+struct Flow<'a, 'w> {
+    doc: &'a Document,
+    sect: &'a Section,
+    pages: usize, // pages finished so far
+    content: Content,
+    column: usize,
+    slot_top: f32,
+    margins: &'a [SectionMargins],
+    section_first_pages: Vec<usize>, // index of the first page of each section
+    v_offsets: &'a [f32],            // per-page shift of the content start, for w:vAlign
+    page_bottom: f32,                // lowest slot_top reached on the current page
+    content_bottoms: Vec<f32>,
+    tags: Option<Tags>,   // structure collected for tagged PDF
+    now: &'a jiff::Zoned, // for DATE and TIME fields
+    write_page: Option<&'w mut WritePage<'w>>, // None when only counting pages
+}
+
+impl<'a, 'w> Flow<'a, 'w> {
+    fn new(
+        doc: &'a Document,
+        margins: &'a [SectionMargins],
+        v_offsets: &'a [f32],
+        tagged: bool,
+        now: &'a jiff::Zoned,
+        write_page: Option<&'w mut WritePage<'w>>,
+    ) -> Self {
+        let mut flow = Flow {
+            doc,
+            sect: &doc.sections[0],
+            pages: 0,
+            content: Content::new(),
+            column: 0,
+            slot_top: 0.0,
+            margins,
+            section_first_pages: vec![0],
+            v_offsets,
+            page_bottom: 0.0,
+            content_bottoms: Vec::new(),
+            tags: tagged.then(Tags::default),
+            now,
+            write_page,
+        };
+        flow.slot_top = flow.page_top();
+        flow.page_bottom = flow.slot_top;
+        flow
+    }
+
+    fn page_margins(&self) -> PageMargins {
+        page_margins(
+            self.doc,
+            self.margins,
+            &self.section_first_pages,
+            self.pages,
+        )
+    }
+
+    fn page_top(&self) -> f32 {
+        let offset = self.v_offsets.get(self.pages).copied().unwrap_or(0.0);
+        self.sect.page_height - self.page_margins().top - offset
+    }
+
+    /// Lowest y body content may reach on the current page.
+    fn margin_bottom(&self) -> f32 {
+        self.page_margins().bottom
+    }
+
+    /// True at the top of a column, where nothing has been placed yet.
+    fn at_top(&self) -> bool {
+        (self.slot_top - self.page_top()).abs() < 1.0
+    }
+
+    fn page_index(&self) -> usize {
+        self.pages
+    }
+
+    /// The number of the current page, formatted for its section.
+    fn page_label(&self) -> String {
+        let page_num = page_number(self.doc, &self.section_first_pages, self.pages);
+        format_number(page_num, &self.sect.page_number_format)
+    }
+
+    fn column_width(&self) -> f32 {
+        let sect = self.sect;
+        let cols = sect.columns.max(1) as f32;
+        let text_width = sect.page_width - sect.margin_left - sect.margin_right;
+        ((text_width - sect.column_space * (cols - 1.0)) / cols).max(1.0)
+    }
+
+    fn column_left(&self) -> f32 {
+        self.sect.margin_left + self.column as f32 * (self.column_width() + self.sect.column_space)
+    }
+
+    /// Start a new page, as for explicit page and section breaks. Marked
+    /// content open at the break continues on the new page.
+    fn new_page(&mut self) {
+        let marked = self.tags.as_mut().and_then(|t| t.unmark(&mut self.content));
+        self.finish_page();
+        self.column = 0;
+        self.slot_top = self.page_top();
+        self.page_bottom = self.slot_top;
+        if let (Some(tags), Some(elem)) = (&mut self.tags, marked) {
+            tags.mark(&mut self.content, self.pages, elem);
+        }
+    }
+
+    /// Hand the content of the current page to `write_page`, or drop it when
+    /// only counting pages.
+    fn finish_page(&mut self) {
+        self.content_bottoms
+            .push(self.page_bottom.min(self.slot_top));
+        let content = std::mem::replace(&mut self.content, Content::new());
+        if let Some(write_page) = &mut self.write_page {
+            write_page(self.pages, &self.section_first_pages, content);
+        }
+        self.pages += 1;
+    }
+
+    /// Whether a table `table_w` wide is too wide for its column. Tables scaled
+    /// down to the column can come out a rounding error wider.
+    fn spans_columns(&self, table_w: f32) -> bool {
+        table_w > self.column_width() + 0.01
+    }
+
+    /// The left edge and width of the space a table `table_w` wide is placed
+    /// in: its column, or when wider, the columns from it to the right margin.
+    fn table_area(&self, table_w: f32) -> (f32, f32) {
+        let left = self.column_left();
+        if self.spans_columns(table_w) {
+            let sect = self.sect;
+            (left, sect.page_width - sect.margin_right - left)
+        } else {
+            (left, self.column_width())
+        }
+    }
+
+    /// Continue a table `table_w` wide on the next column, or on a new page
+    /// when it spans columns, so it doesn't cover the ones beside it.
+    fn next_table_column(&mut self, table_w: f32) {
+        if self.spans_columns(table_w) {
+            self.new_page();
+        } else {
+            self.next_column();
+        }
+    }
+
+    /// Continue at the top of the next column, or of a new page after the last one.
+    fn next_column(&mut self) {
+        if self.column + 1 < self.sect.columns {
+            self.page_bottom = self.page_bottom.min(self.slot_top);
+            self.column += 1;
+            self.slot_top = self.page_top();
+        } else {
+            self.new_page();
+        }
+    }
+
+    /// Open structure element `role` and mark the content drawn until
+    /// `end_tag` as its own.
+    fn begin_tag(&mut self, role: StructRole, alt: Option<&str>) {
+        let page = self.pages;
+        if let Some(tags) = &mut self.tags
+            && let Some(elem) = tags.open(role, alt)
+        {
+            tags.mark(&mut self.content, page, elem);
+        }
+    }
+
+    /// Open structure element `role` for content floating beside the body
+    /// text, outside any list the text is in.
+    fn begin_float_tag(&mut self, role: StructRole) {
+        let page = self.pages;
+        if let Some(tags) = &mut self.tags
+            && let Some(elem) = tags.open_outside_lists(role)
+        {
+            tags.mark(&mut self.content, page, elem);
+        }
+    }
+
+    fn end_tag(&mut self) {
+        if let Some(tags) = &mut self.tags {
+            tags.close(&mut self.content);
+        }
+    }
+
+    /// Open a grouping element such as a list or table row, which holds
+    /// other elements rather than content.
+    fn open_group(&mut self, role: StructRole) {
+        if let Some(tags) = &mut self.tags {
+            tags.open(role, None);
+        }
+    }
+
+    /// Close the open list, if the innermost open element is one.
+    fn close_list(&mut self) {
+        if let Some(tags) = &mut self.tags
+            && tags.innermost() == Some(StructRole::L)
+        {
+            tags.close(&mut self.content);
+        }
+    }
+
+    fn begin_artifact(&mut self) {
+        if let Some(tags) = &mut self.tags {
+            tags.begin_artifact(&mut self.content);
+        }
+    }
+
+    fn end_artifact(&mut self) {
+        if let Some(tags) = &mut self.tags {
+            tags.end_artifact(&mut self.content);
+        }
+    }
+
+    /// Place the structure of a body paragraph about to be drawn: list items
+    /// go into a list item of the open list, or of a new one; anything else
+    /// ends the list.
+    fn enter_paragraph(&mut self, para: &Paragraph) {
+        if paragraph_role(para) != StructRole::LBody {
+            self.close_list();
+            return;
+        }
+        if self
+            .tags
+            .as_ref()
+            .is_some_and(|t| t.innermost() != Some(StructRole::L))
+        {
+            self.open_group(StructRole::L);
+        }
+        self.open_group(StructRole::LI);
+    }
+
+    /// Mark the content of a body paragraph. A list label set off by a tab is
+    /// marked on its own until `end_label_tag`.
+    fn begin_paragraph_tag(&mut self, para: &Paragraph) {
+        let role = paragraph_role(para);
+        let tab_label = para
+            .list_label
+            .as_ref()
+            .is_some_and(|l| l.suffix == LabelSuffix::Tab);
+        if role == StructRole::LBody && tab_label {
+            self.begin_tag(StructRole::Lbl, None);
+        } else {
+            let alt = para.image.as_ref().and_then(|img| img.alt_text.as_deref());
+            self.begin_tag(role, alt);
+        }
+    }
+
+    /// Switch from a list label to the body of its list item.
+    fn end_label_tag(&mut self) {
+        if self
+            .tags
+            .as_ref()
+            .is_some_and(|t| t.innermost() == Some(StructRole::Lbl))
+        {
+            self.end_tag();
+            self.begin_tag(StructRole::LBody, None);
+        }
+    }
+
+    /// End the content of a body paragraph, and the list item it is in.
+    fn end_paragraph_tag(&mut self) {
+        self.end_tag();
+        if self
+            .tags
+            .as_ref()
+            .is_some_and(|t| t.innermost() == Some(StructRole::LI))
+        {
+            self.end_tag();
+        }
+    }
+}
+
+/// Give each of `areas` a Link structure element in the element being drawn.
+fn tag_links(tags: &mut Option<Tags>, areas: &mut [LinkArea]) {
+    if let Some(tags) = tags {
+        for area in areas {
+            area.tag = tags.add_link();
+        }
+    }
+}
+
+/// Structure element for a body paragraph: a heading for outline levels 0-5,
+/// a figure for pictures, the body of a list item, otherwise a paragraph.
+fn paragraph_role(para: &Paragraph) -> StructRole {
+    match para.outline_level {
+        Some(0) => StructRole::H1,
+        Some(1) => StructRole::H2,
+        Some(2) => StructRole::H3,
+        Some(3) => StructRole::H4,
+        Some(4) => StructRole::H5,
+        Some(5..) => StructRole::H6,
+        None if para.image.is_some() => StructRole::Figure,
+        None if para.list_label.is_some() => StructRole::LBody,
+        None => StructRole::P,
+    }
+}
+
+/// What laying out the document body (and endnotes) found, besides the
+/// pages themselves.
+struct BodyLayout<'a> {
+    pages: usize,
+    // Index of the first page of each section
+    section_first_pages: Vec<usize>,
+    // Link annotations, tagged with the index of the page they land on
+    links: Vec<(usize, LinkArea)>,
+    // Bookmark name -> (page index, y of the paragraph top)
+    bookmark_positions: HashMap<&'a str, (usize, f32)>,
+    // (block index, page index, y of the paragraph top) of each heading, in order
+    heading_positions: Vec<(usize, usize, f32)>,
+    // Lowest y reached by body content on each page
+    content_bottoms: Vec<f32>,
+    // Structure of the body, for tagged PDF
+    tags: Option<Tags>,
+}
+
+/// Lay out all body blocks onto pages, starting from an empty `flow`.
+/// `total_pages` is the value used for NUMPAGES fields in body text.
+fn layout_body<'a>(
+    doc: &'a Document,
+    seen_fonts: &HashMap<String, FontEntry>,
+    image_pdf_names: &HashMap<usize, String>,
+    float_image_pdf_names: &HashMap<*const EmbeddedImage, String>,
+    total_pages: usize,
+    mut flow: Flow<'a, '_>,
+) -> BodyLayout<'a> {
+    let mut sect_idx = 0;
+    let mut links: Vec<(usize, LinkArea)> = Vec::new();
+    let mut bookmark_positions: HashMap<&str, (usize, f32)> = HashMap::new();
+    let mut heading_positions: Vec<(usize, usize, f32)> = Vec::new();
+    let mut prev_space_after: f32 = 0.0;
 
-    let adjacent_para = |idx: usize| -> Option<&crate::model::Paragraph> {
+    let adjacent_para = |idx: usize| -> Option<&Paragraph> {
         match doc.blocks.get(idx)? {
             Block::Paragraph(p) => Some(p),
             Block::Table(_) => None,
@@ -915,25 +2925,107 @@ pub fn render(doc: &Document) -> Result<Vec<u8>, Error> {
     };
 
     for (block_idx, block) in doc.blocks.iter().enumerate() {
+        // Next-page sections start on a fresh page with their own geometry;
+        // continuous ones keep flowing on the current page, unless they change
+        // the column count.
+        while let Some(next) = doc.sections.get(sect_idx + 1)
+            && next.first_block <= block_idx
+        {
+            sect_idx += 1;
+            let at_top = flow.at_top() && flow.column == 0;
+            let new_page =
+                next.start == SectionBreak::NextPage || next.columns != flow.sect.columns;
+            if new_page && !at_top {
+                flow.new_page();
+            }
+            flow.sect = next;
+            if new_page || at_top {
+                flow.section_first_pages.push(flow.page_index());
+                flow.column = 0;
+                flow.slot_top = flow.page_top();
+                flow.page_bottom = flow.slot_top;
+                prev_space_after = 0.0;
+            } else {
+                flow.section_first_pages.push(flow.page_index() + 1);
+            }
+        }
+
+        // Endnotes follow the body under a short separator rule
+        if doc.endnotes_start == Some(block_idx) {
+            let sep_h = flow.sect.line_pitch;
+            flow.slot_top -= prev_space_after;
+            if flow.slot_top - 2.0 * sep_h < flow.margin_bottom() {
+                flow.next_column();
+            }
+            let rule_x = flow.column_left();
+            let rule_y = flow.slot_top - sep_h / 2.0;
+            flow.begin_artifact();
+            flow.content
+                .save_state()
+                .set_line_width(0.5)
+                .move_to(rule_x, rule_y)
+                .line_to(rule_x + 144.0, rule_y)
+                .stroke()
+                .restore_state();
+            flow.end_artifact();
+            flow.slot_top -= sep_h;
+            prev_space_after = 0.0;
+        }
+
         match block {
             Block::Paragraph(para) => {
                 // Handle explicit page breaks
                 if para.page_break_before {
-                    let at_top = (slot_top - (doc.page_height - doc.margin_top)).abs() < 1.0;
-                    if !at_top {
-                        all_contents
-                            .push(std::mem::replace(&mut current_content, Content::new()));
-                        slot_top = doc.page_height - doc.margin_top;
+                    if !(flow.at_top() && flow.column == 0) {
+                        flow.new_page();
                     }
                     prev_space_after = 0.0;
                     // If the paragraph only contains the break (no text), skip rendering
                     if para.runs.is_empty()
-                        || para.runs.iter().all(|r| r.is_tab || r.text.is_empty())
+                        || para
+                            .runs
+                            .iter()
+                            .all(|r| r.is_tab || (r.text.is_empty() && r.field_code.is_none()))
                     {
                         continue;
                     }
                 }
 
+                // Page fields take the number of the page the paragraph starts on
+                let field_runs;
+                let runs: Vec<&Run> = if para.runs.iter().any(|r| r.field_code.is_some()) {
+                    let page_label = flow.page_label();
+                    let fields = FieldValues {
+                        page_label: &page_label,
+                        total_pages,
+                        now: flow.now,
+                    };
+                    field_runs = substitute_fields(&para.runs, doc, &fields);
+                    field_runs.iter().map(|run| &**run).collect()
+                } else {
+                    para.runs.iter().collect()
+                };
+
+                // Labels followed by a space or nothing flow with the first line of
+                // text instead of sitting in the hanging indent
+                let label_run: Run;
+                let runs: Vec<&Run> = match &para.list_label {
+                    Some(label) if label.suffix != LabelSuffix::Tab => {
+                        let mut run = label.run.clone();
+                        if label.suffix == LabelSuffix::Space {
+                            run.text.push(' ');
+                        }
+                        label_run = run;
+                        std::iter::once(&label_run).chain(runs).collect()
+                    }
+                    _ => runs,
+                };
+
+                // Paragraphs consisting only of hidden text take up no space
+                if !runs.is_empty() && para.image.is_none() && runs.iter().all(|r| r.hidden) {
+                    continue;
+                }
+
                 let next_para = adjacent_para(block_idx + 1);
                 let prev_para = if block_idx > 0 {
                     adjacent_para(block_idx - 1)
@@ -956,143 +3048,304 @@ pub fn render(doc: &Document) -> Result<Vec<u8>, Error> {
 
                 let mut inter_gap = f32::max(prev_space_after, effective_space_before);
 
-                let (font_size, tallest_lhr, tallest_ar) =
-                    tallest_run_metrics(&para.runs, &seen_fonts);
-                let effective_line_spacing = para.line_spacing.unwrap_or(doc.line_spacing);
-                let line_h = tallest_lhr
-                    .map(|ratio| font_size * ratio * effective_line_spacing)
-                    .unwrap_or(font_size * 1.2);
-
-                let para_text_x = doc.margin_left + para.indent_left;
-                let para_text_width = (text_width - para.indent_left).max(1.0);
-                let label_x = doc.margin_left + (para.indent_left - para.indent_hanging).max(0.0);
+                let (font_size, tallest_lhr, tallest_ar) = tallest_run_metrics(&runs, seen_fonts);
+                let line_spacing = para.line_spacing.unwrap_or(doc.line_spacing);
+                let line_h = line_height(line_spacing, font_size, tallest_lhr);
+                let ascent = line_ascent(line_spacing, line_h, font_size, tallest_lhr, tallest_ar);
+
+                let text_width = flow.column_width();
+                let para_text_width = (text_width - para.indent_left - para.indent_right).max(1.0);
+                let label_indent = (para.indent_left - para.indent_hanging).max(0.0);
+
+                // `first_line_indent` is zero for groups continuing after a page break
+                let has_tabs = runs.iter().any(|r| r.is_tab && !r.hidden);
+                let tabs = TabSettings {
+                    stops: &para.tab_stops,
+                    indent_left: para.indent_left,
+                    decimal_separator: doc.decimal_separator,
+                };
+                let build_lines = |runs: &[&Run], first_line_indent: f32| -> Vec<TextLine> {
+                    if has_tabs {
+                        runs.split(|r| r.is_line_break && !r.hidden)
+                            .enumerate()
+                            .flat_map(|(i, seg)| {
+                                let start_x = if i == 0 { first_line_indent } else { 0.0 };
+                                build_tabbed_line(seg, seen_fonts, &tabs, para_text_width, start_x)
+                            })
+                            .collect()
+                    } else {
+                        build_paragraph_lines(runs, seen_fonts, para_text_width, first_line_indent)
+                    }
+                };
 
-                let has_tabs = para.runs.iter().any(|r| r.is_tab);
-                let lines = if para.image.is_some() || para.runs.is_empty() {
+                // Explicit page breaks split the paragraph: the first group is laid out
+                // normally, each following group starts at the top of a new page.
+                let mut break_groups = runs.split(|r| r.is_page_break && !r.hidden);
+                let first_group = break_groups.next().unwrap_or_default();
+                let after_breaks: Vec<Vec<TextLine>> =
+                    break_groups.map(|group| build_lines(group, 0.0)).collect();
+
+                // An inline label starts the first line at the label position
+                let first_line_indent = match &para.list_label {
+                    Some(label) if label.suffix != LabelSuffix::Tab => {
+                        label_indent - para.indent_left
+                    }
+                    _ => para.indent_first_line,
+                };
+                let lines = if para.image.is_some() || runs.is_empty() {
                     vec![]
-                } else if has_tabs {
-                    build_tabbed_line(
-                        &para.runs,
-                        &seen_fonts,
-                        &para.tab_stops,
-                        para.indent_left,
-                    )
                 } else {
-                    build_paragraph_lines(&para.runs, &seen_fonts, para_text_width)
+                    build_lines(first_group, first_line_indent)
+                };
+
+                // Each group continues in the next column or on the next page
+                // once it reaches the bottom margin, at least one line per column
+                let render_after_breaks = |flow: &mut Flow, links: &mut Vec<(usize, LinkArea)>| {
+                    for group in &after_breaks {
+                        flow.new_page();
+                        let mut placed = 0;
+                        while placed < group.len() {
+                            let room = if line_h > 0.0 {
+                                ((flow.slot_top - flow.margin_bottom()) / line_h).floor() as usize
+                            } else {
+                                group.len()
+                            };
+                            let part = &group[placed..(placed + room.max(1)).min(group.len())];
+                            let column_left = flow.column_left();
+                            let mut areas = render_paragraph_lines(
+                                &mut flow.content,
+                                part,
+                                &para.alignment,
+                                column_left + para.indent_left,
+                                para_text_width,
+                                flow.slot_top - ascent,
+                                line_h,
+                                group.len(),
+                                placed,
+                            );
+                            tag_links(&mut flow.tags, &mut areas);
+                            let page_idx = flow.page_index();
+                            links.extend(areas.into_iter().map(|a| (page_idx, a)));
+                            let bottom = flow.slot_top - part.len() as f32 * line_h;
+                            draw_bar_tabs(
+                                &mut flow.content,
+                                &para.tab_stops,
+                                column_left,
+                                flow.slot_top,
+                                bottom,
+                            );
+                            flow.slot_top = bottom;
+                            placed += part.len();
+                            if placed < group.len() {
+                                flow.next_column();
+                            }
+                        }
+                    }
                 };
 
-                let content_h = if para.image.is_some() || para.runs.is_empty() {
-                    para.content_height.max(doc.line_pitch)
+                let content_h = if para.image.is_some() || runs.is_empty() {
+                    para.content_height.max(flow.sect.line_pitch)
                 } else {
                     lines.len() as f32 * line_h
                 };
 
                 let needed = inter_gap + content_h;
-                let at_page_top = (slot_top - (doc.page_height - doc.margin_top)).abs() < 1.0;
-
-                let keep_next_extra = if para.keep_next {
-                    next_para.map_or(0.0, |next| {
-                        let (nfs, nlhr, _) = tallest_run_metrics(&next.runs, &seen_fonts);
-                        let next_inter = f32::max(effective_space_after, next.space_before);
-                        let next_first_line_h = nlhr
-                            .map(|ratio| nfs * ratio)
-                            .unwrap_or(nfs * 1.2);
-                        next_inter + next_first_line_h
-                    })
+                let at_page_top = flow.at_top();
+
+                let keep_next_extra = if para.keep_next && after_breaks.is_empty() {
+                    match doc.blocks.get(block_idx + 1) {
+                        Some(Block::Paragraph(next)) => {
+                            let (nfs, nlhr, _) = tallest_run_metrics(&next.runs, seen_fonts);
+                            let next_inter = f32::max(effective_space_after, next.space_before);
+                            // A keepLines paragraph can only follow as a whole
+                            let next_h = if next.keep_lines && next.image.is_none() {
+                                let next_width =
+                                    (text_width - next.indent_left - next.indent_right).max(1.0);
+                                let next_lines = build_paragraph_lines(
+                                    &next.runs,
+                                    seen_fonts,
+                                    next_width,
+                                    next.indent_first_line,
+                                );
+                                let spacing = next.line_spacing.unwrap_or(doc.line_spacing);
+                                next_lines.len() as f32 * line_height(spacing, nfs, nlhr)
+                            } else {
+                                nlhr.map(|ratio| nfs * ratio).unwrap_or(nfs * 1.2)
+                            };
+                            next_inter + next_h
+                        }
+                        Some(Block::Table(next)) => {
+                            effective_space_after + table_keep_height(next, doc, &flow, seen_fonts)
+                        }
+                        None => 0.0,
+                    }
                 } else {
                     0.0
                 };
 
-                if !at_page_top && slot_top - needed - keep_next_extra < doc.margin_bottom {
-                    let available = slot_top - inter_gap - doc.margin_bottom;
-                    let first_line_h = tallest_lhr
-                        .map(|ratio| font_size * ratio)
-                        .unwrap_or(font_size);
+                if !at_page_top && flow.slot_top - needed - keep_next_extra < flow.margin_bottom() {
+                    let available = flow.slot_top - inter_gap - flow.margin_bottom();
+                    let first_line_h = match line_spacing {
+                        LineSpacing::Auto(_) => tallest_lhr
+                            .map(|ratio| font_size * ratio)
+                            .unwrap_or(font_size),
+                        LineSpacing::Exact(_) | LineSpacing::AtLeast(_) => line_h,
+                    };
                     let mut lines_that_fit = if line_h > 0.0 && available >= first_line_h {
                         1 + ((available - first_line_h) / line_h).floor() as usize
                     } else {
                         0
                     };
 
-                    // Reduce to ensure at least 2 lines remain on next page (orphan control)
-                    if lines_that_fit > 0 && lines.len().saturating_sub(lines_that_fit) < 2 {
-                        lines_that_fit = lines.len().saturating_sub(2);
+                    // Widow/orphan control keeps at least 2 lines on each side of the
+                    // break; without it the page is simply filled
+                    let min_lines = if para.widow_control { 2 } else { 1 };
+                    if lines_that_fit > 0 && lines.len().saturating_sub(lines_that_fit) < min_lines
+                    {
+                        lines_that_fit = lines.len().saturating_sub(min_lines);
+                    }
+
+                    // keepLines moves the whole paragraph on, unless no page could hold it
+                    if para.keep_lines && content_h <= flow.page_top() - flow.margin_bottom() {
+                        lines_that_fit = 0;
                     }
 
-                    if lines_that_fit >= 2 && lines_that_fit < lines.len() {
+                    if lines_that_fit >= min_lines && lines_that_fit < lines.len() {
                         let first_part = &lines[..lines_that_fit];
-                        slot_top -= inter_gap;
-                        let ascender_ratio = tallest_ar.unwrap_or(0.75);
-                        let baseline_y = slot_top - font_size * ascender_ratio;
-
-                        if !para.list_label.is_empty() {
-                            let (label_font_name, label_bytes) =
-                                label_for_run(&para.runs[0], &seen_fonts, &para.list_label);
-                            current_content
-                                .begin_text()
-                                .set_font(Name(label_font_name.as_bytes()), font_size)
-                                .next_line(label_x, baseline_y)
-                                .show(Str(&label_bytes))
-                                .end_text();
+                        flow.slot_top -= inter_gap;
+                        for name in &para.bookmarks {
+                            bookmark_positions.insert(name, (flow.page_index(), flow.slot_top));
+                        }
+                        if para.outline_level.is_some() {
+                            heading_positions.push((block_idx, flow.page_index(), flow.slot_top));
+                        }
+                        let para_top = flow.slot_top;
+                        flow.enter_paragraph(para);
+                        let areas = draw_text_boxes(
+                            &mut flow,
+                            para,
+                            para_top,
+                            seen_fonts,
+                            float_image_pdf_names,
+                            total_pages,
+                        );
+                        let page_idx = flow.page_index();
+                        links.extend(areas.into_iter().map(|a| (page_idx, a)));
+                        let baseline_y = flow.slot_top - ascent;
+                        let column_left = flow.column_left();
+
+                        flow.begin_paragraph_tag(para);
+                        if let Some(label) = &para.list_label
+                            && label.suffix == LabelSuffix::Tab
+                        {
+                            draw_list_label(
+                                &mut flow.content,
+                                label,
+                                seen_fonts,
+                                column_left + label_indent,
+                                baseline_y,
+                            );
                         }
+                        flow.end_label_tag();
 
-                        render_paragraph_lines(
-                            &mut current_content,
+                        let mut areas = render_paragraph_lines(
+                            &mut flow.content,
                             first_part,
                             &para.alignment,
-                            para_text_x,
+                            column_left + para.indent_left,
                             para_text_width,
                             baseline_y,
                             line_h,
                             lines.len(),
                             0,
                         );
+                        tag_links(&mut flow.tags, &mut areas);
+                        let page_idx = flow.page_index();
+                        links.extend(areas.into_iter().map(|a| (page_idx, a)));
+                        draw_bar_tabs(
+                            &mut flow.content,
+                            &para.tab_stops,
+                            column_left,
+                            flow.slot_top,
+                            flow.slot_top - first_part.len() as f32 * line_h,
+                        );
 
-                        all_contents.push(std::mem::replace(&mut current_content, Content::new()));
-                        slot_top = doc.page_height - doc.margin_top;
+                        flow.next_column();
 
                         let rest = &lines[lines_that_fit..];
                         let rest_content_h = rest.len() as f32 * line_h;
-                        let baseline_y2 = slot_top - font_size * ascender_ratio;
+                        let baseline_y2 = flow.slot_top - ascent;
+                        let rest_left = flow.column_left();
+                        let rest_x = rest_left + para.indent_left;
 
-                        render_paragraph_lines(
-                            &mut current_content,
+                        let mut areas = render_paragraph_lines(
+                            &mut flow.content,
                             rest,
                             &para.alignment,
-                            para_text_x,
+                            rest_x,
                             para_text_width,
                             baseline_y2,
                             line_h,
                             lines.len(),
                             lines_that_fit,
                         );
+                        tag_links(&mut flow.tags, &mut areas);
+                        let page_idx = flow.page_index();
+                        links.extend(areas.into_iter().map(|a| (page_idx, a)));
+                        draw_bar_tabs(
+                            &mut flow.content,
+                            &para.tab_stops,
+                            rest_left,
+                            flow.slot_top,
+                            flow.slot_top - rest_content_h,
+                        );
 
-                        slot_top -= rest_content_h;
+                        flow.slot_top -= rest_content_h;
+                        render_after_breaks(&mut flow, &mut links);
+                        flow.end_paragraph_tag();
                         prev_space_after = effective_space_after;
                         continue;
                     }
 
-                    all_contents.push(std::mem::replace(&mut current_content, Content::new()));
-                    slot_top = doc.page_height - doc.margin_top;
+                    flow.next_column();
                     inter_gap = 0.0;
                 }
 
-                // Suppress space_before at the top of a page (after a page break, not first page)
-                let at_new_page_top = !all_contents.is_empty()
-                    && (slot_top - (doc.page_height - doc.margin_top)).abs() < 1.0;
+                // Suppress space_before at the top of a page or column (not the first page)
+                let at_new_page_top = (flow.page_index() > 0 || flow.column > 0) && flow.at_top();
+                let column_left = flow.column_left();
                 if at_new_page_top {
                     inter_gap = 0.0;
                 }
 
-                slot_top -= inter_gap;
-
-                if (para.image.is_some() || para.runs.is_empty()) && para.content_height > 0.0 {
+                flow.slot_top -= inter_gap;
+                for name in &para.bookmarks {
+                    bookmark_positions.insert(name, (flow.page_index(), flow.slot_top));
+                }
+                if para.outline_level.is_some() {
+                    heading_positions.push((block_idx, flow.page_index(), flow.slot_top));
+                }
+                let para_top = flow.slot_top;
+                flow.enter_paragraph(para);
+                let areas = draw_text_boxes(
+                    &mut flow,
+                    para,
+                    para_top,
+                    seen_fonts,
+                    float_image_pdf_names,
+                    total_pages,
+                );
+                let page_idx = flow.page_index();
+                links.extend(areas.into_iter().map(|a| (page_idx, a)));
+
+                flow.begin_paragraph_tag(para);
+                if (para.image.is_some() || runs.is_empty()) && para.content_height > 0.0 {
                     if let Some(pdf_name) = image_pdf_names.get(&block_idx) {
                         let img = para.image.as_ref().unwrap();
-                        let y_bottom = slot_top - img.display_height;
-                        let x = doc.margin_left + (text_width - img.display_width).max(0.0) / 2.0;
-                        current_content.save_state();
-                        current_content.transform([
+                        let y_bottom = flow.slot_top - img.display_height;
+                        let x = column_left + (text_width - img.display_width).max(0.0) / 2.0;
+                        flow.content.save_state();
+                        flow.content.transform([
                             img.display_width,
                             0.0,
                             0.0,
@@ -1100,153 +3353,717 @@ pub fn render(doc: &Document) -> Result<Vec<u8>, Error> {
                             x,
                             y_bottom,
                         ]);
-                        current_content.x_object(Name(pdf_name.as_bytes()));
-                        current_content.restore_state();
+                        flow.content.x_object(Name(pdf_name.as_bytes()));
+                        flow.content.restore_state();
                     } else {
-                        current_content
+                        flow.content
                             .set_fill_gray(0.5)
-                            .rect(doc.margin_left, slot_top - content_h, text_width, content_h)
+                            .rect(
+                                column_left,
+                                flow.slot_top - content_h,
+                                text_width,
+                                content_h,
+                            )
                             .fill_nonzero()
                             .set_fill_gray(0.0);
                     }
                 } else if !lines.is_empty() {
-                    let ascender_ratio = tallest_ar.unwrap_or(0.75);
-                    let baseline_y = slot_top - font_size * ascender_ratio;
-
-                    if !para.list_label.is_empty() {
-                        let (label_font_name, label_bytes) =
-                            label_for_run(&para.runs[0], &seen_fonts, &para.list_label);
-                        current_content
-                            .begin_text()
-                            .set_font(Name(label_font_name.as_bytes()), font_size)
-                            .next_line(label_x, baseline_y)
-                            .show(Str(&label_bytes))
-                            .end_text();
+                    let baseline_y = flow.slot_top - ascent;
+
+                    if let Some(label) = &para.list_label
+                        && label.suffix == LabelSuffix::Tab
+                    {
+                        draw_list_label(
+                            &mut flow.content,
+                            label,
+                            seen_fonts,
+                            column_left + label_indent,
+                            baseline_y,
+                        );
                     }
+                    flow.end_label_tag();
 
-                    render_paragraph_lines(
-                        &mut current_content,
+                    let mut areas = render_paragraph_lines(
+                        &mut flow.content,
                         &lines,
                         &para.alignment,
-                        para_text_x,
+                        column_left + para.indent_left,
                         para_text_width,
                         baseline_y,
                         line_h,
                         lines.len(),
                         0,
                     );
+                    tag_links(&mut flow.tags, &mut areas);
+                    let page_idx = flow.page_index();
+                    links.extend(areas.into_iter().map(|a| (page_idx, a)));
+                    draw_bar_tabs(
+                        &mut flow.content,
+                        &para.tab_stops,
+                        column_left,
+                        flow.slot_top,
+                        flow.slot_top - content_h,
+                    );
                 }
 
+                flow.slot_top -= content_h;
+                render_after_breaks(&mut flow, &mut links);
+                flow.end_paragraph_tag();
+
                 // Draw bottom border if present
                 if let Some(bdr) = &para.border_bottom {
-                    let line_y = slot_top - content_h - bdr.space_pt;
+                    let line_y = flow.slot_top - bdr.space_pt;
+                    let border_x = flow.column_left();
                     let [r, g, b] = bdr.color;
-                    current_content
+                    flow.begin_artifact();
+                    flow.content
                         .set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
-                        .rect(
-                            doc.margin_left,
-                            line_y - bdr.width_pt,
-                            text_width,
-                            bdr.width_pt,
-                        )
+                        .rect(border_x, line_y - bdr.width_pt, text_width, bdr.width_pt)
                         .fill_nonzero()
                         .set_fill_rgb(0.0, 0.0, 0.0);
+                    flow.end_artifact();
                 }
 
-                slot_top -= content_h;
                 prev_space_after = effective_space_after;
             }
 
             Block::Table(table) => {
-                render_table(
-                    table,
-                    doc,
-                    &seen_fonts,
-                    &mut current_content,
-                    &mut all_contents,
-                    &mut slot_top,
-                    prev_space_after,
-                );
+                flow.close_list();
+                flow.slot_top -= prev_space_after;
+                links.extend(render_table(table, doc, seen_fonts, total_pages, &mut flow));
                 prev_space_after = 0.0;
             }
         }
     }
-    all_contents.push(current_content);
+    flow.close_list();
+    flow.finish_page();
+    let Flow {
+        pages,
+        content_bottoms,
+        section_first_pages,
+        tags,
+        ..
+    } = flow;
+
+    BodyLayout {
+        pages,
+        section_first_pages,
+        links,
+        bookmark_positions,
+        heading_positions,
+        content_bottoms,
+        tags,
+    }
+}
 
-    // Phase 2b: render headers and footers on each page
-    let total_pages = all_contents.len();
-    let has_hf = doc.header_default.is_some()
-        || doc.header_first.is_some()
-        || doc.footer_default.is_some()
-        || doc.footer_first.is_some();
+/// Offsets that place the content of single-page sections with a `w:vAlign`
+/// of center or bottom, measured from a top-aligned layout.
+fn vertical_offsets(doc: &Document, body: &BodyLayout, margins: &[SectionMargins]) -> Vec<f32> {
+    let mut offsets = vec![0.0; body.pages];
+    for (sect_idx, &first) in body.section_first_pages.iter().enumerate() {
+        let sect = &doc.sections[sect_idx];
+        let end = body
+            .section_first_pages
+            .get(sect_idx + 1)
+            .copied()
+            .unwrap_or(body.pages);
+        // Longer sections would need their content redistributed across pages
+        if sect.v_align == PageVAlign::Top || end != first + 1 || first >= offsets.len() {
+            continue;
+        }
+        let margin_bottom = page_margins(doc, margins, &body.section_first_pages, first).bottom;
+        let free = (body.content_bottoms[first] - margin_bottom).max(0.0);
+        offsets[first] = match sect.v_align {
+            PageVAlign::Center => free / 2.0,
+            PageVAlign::Bottom => free,
+            PageVAlign::Top => 0.0,
+        };
+    }
+    offsets
+}
 
-    if has_hf {
-        for (page_idx, content) in all_contents.iter_mut().enumerate() {
-            let is_first = page_idx == 0;
-            let page_num = page_idx + 1;
+/// Every header and footer referenced by a section. Inherited ones appear once
+/// per section that uses them.
+fn all_header_footers(doc: &Document) -> impl Iterator<Item = &HeaderFooter> {
+    doc.sections
+        .iter()
+        .flat_map(|sect| {
+            [
+                &sect.header_default,
+                &sect.header_first,
+                &sect.header_even,
+                &sect.footer_default,
+                &sect.footer_first,
+                &sect.footer_even,
+            ]
+        })
+        .filter_map(|hf| hf.as_deref())
+}
 
-            // Header
-            let header = if is_first && doc.different_first_page {
-                doc.header_first.as_ref()
-            } else {
-                doc.header_default.as_ref()
+/// The paragraphs of every text box anchored in a body paragraph.
+fn text_box_paragraphs(doc: &Document) -> impl Iterator<Item = &Paragraph> {
+    doc.blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Paragraph(p) => Some(p),
+            Block::Table(_) => None,
+        })
+        .flat_map(|p| p.text_boxes.iter())
+        .flat_map(|tb| tb.paragraphs.iter())
+}
+
+/// Render `doc` to PDF bytes. The bytes of body and text box pictures are
+/// moved out of `doc` as they are embedded.
+pub fn render(
+    doc: &mut Document,
+    options: &ConversionOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<u8>, Error> {
+    let mut pdf = Pdf::new();
+    let mut next_id = 1i32;
+    let mut alloc = || {
+        let r = Ref::new(next_id);
+        next_id += 1;
+        r
+    };
+
+    let catalog_id = alloc();
+    let pages_id = alloc();
+
+    // Phase 1: collect unique font names (with variant) and embed them
+    let mut seen_fonts: HashMap<String, FontEntry> = HashMap::new();
+    let mut font_order: Vec<String> = Vec::new();
+
+    // Collect all runs from all blocks (paragraphs, table cells, headers/footers)
+    let hf_runs = all_header_footers(doc)
+        .flat_map(|hf| hf.paragraphs.iter())
+        .flat_map(|p| p.runs.iter());
+
+    let all_runs: Vec<&Run> = doc
+        .blocks
+        .iter()
+        .flat_map(|block| -> Box<dyn Iterator<Item = &Run> + '_> {
+            match block {
+                Block::Paragraph(para) => Box::new(
+                    para.runs
+                        .iter()
+                        .chain(para.list_label.iter().map(|l| &l.run))
+                        .chain(
+                            para.text_boxes
+                                .iter()
+                                .flat_map(|tb| tb.paragraphs.iter())
+                                .flat_map(|p| p.runs.iter()),
+                        ),
+                ),
+                Block::Table(table) => Box::new(
+                    table
+                        .rows
+                        .iter()
+                        .flat_map(|row| row.cells.iter())
+                        .flat_map(|cell| cell.paragraphs.iter())
+                        .flat_map(|para| para.runs.iter()),
+                ),
+            }
+        })
+        .chain(hf_runs)
+        .collect();
+
+    for run in all_runs.iter().filter(|r| !r.hidden) {
+        let key = font_key(run);
+        if !seen_fonts.contains_key(&key) {
+            let base = primary_font_name(&run.font_name);
+            let pdf_name = format!("F{}", font_order.len() + 1);
+            let entry = register_font(
+                &mut pdf,
+                base,
+                (run.bold, run.italic),
+                pdf_name,
+                &mut alloc,
+                &doc.embedded_fonts,
+                options.pdfa,
+            )?;
+            let warning = Warning::MissingFont {
+                name: base.to_string(),
+                bold: run.bold,
+                italic: run.italic,
             };
-            if let Some(hf) = header {
-                render_header_footer(
-                    content,
-                    hf,
-                    &seen_fonts,
-                    doc,
-                    true,
-                    page_num,
-                    total_pages,
-                );
+            if entry.missing && !warnings.contains(&warning) {
+                warnings.push(warning);
             }
+            seen_fonts.insert(key.clone(), entry);
+            font_order.push(key);
+        }
+    }
 
-            // Footer
-            let footer = if is_first && doc.different_first_page {
-                doc.footer_first.as_ref()
-            } else {
-                doc.footer_default.as_ref()
+    // Report the characters that a run's font can only show as '?'
+    let mut unmapped: BTreeMap<char, usize> = BTreeMap::new();
+    for run in all_runs.iter().filter(|r| !r.hidden) {
+        let entry = &seen_fonts[&font_key(run)];
+        for c in entry.unmappable_chars(&run.text) {
+            *unmapped.entry(c).or_default() += 1;
+        }
+    }
+    if !unmapped.is_empty() {
+        let count: usize = unmapped.values().sum();
+        let chars: String = unmapped
+            .keys()
+            .map(|c| format!("{c} (U+{:04X})", *c as u32))
+            .collect::<Vec<_>>()
+            .join(", ");
+        log::warn!("{count} characters missing from their fonts replaced with '?': {chars}");
+        warnings.push(Warning::DroppedCharacters {
+            chars: unmapped.into_keys().collect(),
+        });
+    }
+
+    if seen_fonts.is_empty() {
+        let pdf_name = "F1".to_string();
+        let entry = register_font(
+            &mut pdf,
+            "Helvetica",
+            (false, false),
+            pdf_name,
+            &mut alloc,
+            &doc.embedded_fonts,
+            options.pdfa,
+        )?;
+        seen_fonts.insert("Helvetica".to_string(), entry);
+        font_order.push("Helvetica".to_string());
+    }
+
+    // Phase 1b: embed images
+    let mut image_xobjects: Vec<(String, Ref)> = Vec::new();
+    // JPEGs are embedded as they are; PNGs are decoded, with any transparency
+    // going into a soft mask. Pictures that fail to decode are left out.
+    let mut embed_image = |img: &EmbeddedImage| -> Option<String> {
+        let decoded = match img.format {
+            ImageFormat::Png => {
+                let Some(decoded) = png::decode(&img.data) else {
+                    log::warn!("PNG image could not be decoded and was left out");
+                    return None;
+                };
+                Some(decoded)
+            }
+            _ => None,
+        };
+        let xobj_ref = alloc();
+        let pdf_name = format!("Im{}", image_xobjects.len() + 1);
+
+        let (width, height) = (img.pixel_width as i32, img.pixel_height as i32);
+        match decoded {
+            None => {
+                let mut xobj = pdf.image_xobject(xobj_ref, &img.data);
+                xobj.filter(Filter::DctDecode);
+                xobj.width(width);
+                xobj.height(height);
+                xobj.color_space().device_rgb();
+                xobj.bits_per_component(8);
+            }
+            Some(decoded) => {
+                let mask_ref = decoded.alpha.as_ref().map(|_| alloc());
+                let samples = deflate(&decoded.samples);
+                let mut xobj = pdf.image_xobject(xobj_ref, &samples);
+                xobj.filter(Filter::FlateDecode);
+                xobj.width(width);
+                xobj.height(height);
+                if decoded.rgb {
+                    xobj.color_space().device_rgb();
+                } else {
+                    xobj.color_space().device_gray();
+                }
+                xobj.bits_per_component(8);
+                if let Some(mask_ref) = mask_ref {
+                    xobj.s_mask(mask_ref);
+                }
+                drop(xobj);
+                if let (Some(mask_ref), Some(alpha)) = (mask_ref, &decoded.alpha) {
+                    let alpha = deflate(alpha);
+                    let mut mask = pdf.image_xobject(mask_ref, &alpha);
+                    mask.filter(Filter::FlateDecode);
+                    mask.width(width);
+                    mask.height(height);
+                    mask.color_space().device_gray();
+                    mask.bits_per_component(8);
+                }
+            }
+        }
+
+        image_xobjects.push((pdf_name.clone(), xobj_ref));
+        Some(pdf_name)
+    };
+    let mut image_pdf_names: HashMap<usize, String> = HashMap::new();
+    // Header, footer and text box images are embedded once; headers are drawn
+    // on many pages and shared by the sections inheriting them, so key by address
+    let mut float_image_pdf_names: HashMap<*const EmbeddedImage, String> = HashMap::new();
+    {
+        let body_images = doc
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, block)| match block {
+                Block::Paragraph(p) => Some((Some(i), p.image.as_ref()?)),
+                Block::Table(_) => None,
+            });
+        let float_images = all_header_footers(doc)
+            .flat_map(|hf| hf.paragraphs.iter())
+            .chain(text_box_paragraphs(doc))
+            .filter_map(|p| Some((None, p.image.as_ref()?)));
+        // Identical images (a logo repeated in the body and a header, say)
+        // share one XObject, looked up by a hash of their bytes and then compared
+        let mut images_by_hash: HashMap<u64, Vec<(&[u8], String)>> = HashMap::new();
+        for (block_idx, img) in body_images.chain(float_images) {
+            if block_idx.is_none() && float_image_pdf_names.contains_key(&(img as *const _)) {
+                continue;
+            }
+            let mut hasher = DefaultHasher::new();
+            (&img.data, img.pixel_width, img.pixel_height).hash(&mut hasher);
+            let embedded = images_by_hash.entry(hasher.finish()).or_default();
+            let name = match embedded
+                .iter()
+                .find(|(data, _)| *data == img.data.as_slice())
+            {
+                Some((_, name)) => name.clone(),
+                None => {
+                    let Some(name) = embed_image(img) else {
+                        continue;
+                    };
+                    embedded.push((&img.data, name.clone()));
+                    name
+                }
+            };
+            match block_idx {
+                Some(block_idx) => image_pdf_names.insert(block_idx, name),
+                None => float_image_pdf_names.insert(img, name),
             };
-            if let Some(hf) = footer {
-                render_header_footer(
-                    content,
+        }
+    }
+    // Body and text box pictures give up their bytes once embedded, so that
+    // long illustrated documents don't hold them twice
+    for block in doc.blocks.iter_mut() {
+        if let Block::Paragraph(para) = block {
+            let text_box_paragraphs = para
+                .text_boxes
+                .iter_mut()
+                .flat_map(|tb| tb.paragraphs.iter_mut());
+            for img in std::iter::once(&mut para.image)
+                .chain(text_box_paragraphs.map(|p| &mut p.image))
+                .flatten()
+            {
+                img.data = Vec::new();
+            }
+        }
+    }
+    let doc: &Document = doc;
+
+    // Phase 2: lay out the body, finishing each page as layout leaves it:
+    // headers, footers and page borders are drawn around the body content and
+    // the content stream is written right away, so only one page is held at a
+    // time. NUMPAGES needs the final page count and vertically aligned
+    // sections need their content height, so such documents are first laid
+    // out only to count pages.
+    let now = field_time(&doc.metadata, options);
+    let margins: Vec<SectionMargins> = doc
+        .sections
+        .iter()
+        .map(|sect| SectionMargins::measure(sect, &seen_fonts, doc, &now))
+        .collect();
+    let is_numpages = |fc: &FieldCode| *fc == FieldCode::NumPages;
+    let body_has_numpages = doc.blocks.iter().any(|block| match block {
+        Block::Paragraph(p) => paragraphs_have_field(std::slice::from_ref(p), is_numpages),
+        Block::Table(t) => t
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .any(|cell| paragraphs_have_field(&cell.paragraphs, is_numpages)),
+    });
+    let header_footer_has_numpages = all_header_footers(doc)
+        .flat_map(|hf| hf.paragraphs.iter())
+        .flat_map(|p| p.runs.iter())
+        .any(|r| r.field_code == Some(FieldCode::NumPages));
+    let v_aligned = doc
+        .sections
+        .iter()
+        .any(|sect| sect.v_align != PageVAlign::Top);
+    let (counted_pages, v_offsets) = if body_has_numpages || header_footer_has_numpages || v_aligned
+    {
+        let counted = layout_body(
+            doc,
+            &seen_fonts,
+            &image_pdf_names,
+            &float_image_pdf_names,
+            0,
+            Flow::new(doc, &margins, &[], options.tagged, &now, None),
+        );
+        (counted.pages, vertical_offsets(doc, &counted, &margins))
+    } else {
+        (0, Vec::new())
+    };
+
+    // Original index, content stream and section of each page written
+    let mut kept: Vec<usize> = Vec::new();
+    let mut content_ids: Vec<Ref> = Vec::new();
+    let mut page_sections: Vec<usize> = Vec::new();
+    let mut header_footer_links: Vec<(usize, LinkArea)> = Vec::new();
+    // The trailer ID PDF/A asks for is derived from the page contents
+    let mut file_id = DefaultHasher::new();
+    let mut write_page = |page_idx: usize, section_first_pages: &[usize], mut content: Content| {
+        // Pages outside the requested range still count for the pagination
+        // of the others, but are not written
+        if options
+            .pages
+            .as_ref()
+            .is_some_and(|r| !r.contains(&(page_idx + 1)))
+        {
+            return;
+        }
+        let sect_idx = section_first_pages
+            .iter()
+            .rposition(|&first| first <= page_idx)
+            .unwrap_or(0);
+        let sect = &doc.sections[sect_idx];
+        let is_first = page_idx == section_first_pages[sect_idx];
+        let page = PageContext {
+            sect,
+            page_num: page_number(doc, section_first_pages, page_idx),
+            total_pages: counted_pages,
+            now: &now,
+        };
+        let is_even = doc.even_and_odd_headers && page.page_num.is_multiple_of(2);
+
+        // Headers and footers repeat on every page, outside the structure
+        if options.tagged {
+            content.begin_marked_content(Name(b"Artifact"));
+        }
+        for is_header in [true, false] {
+            if let Some(hf) = page_header_footer(sect, is_header, is_first, is_even) {
+                let areas = render_header_footer(
+                    &mut content,
                     hf,
+                    is_header,
                     &seen_fonts,
+                    &float_image_pdf_names,
                     doc,
-                    false,
-                    page_num,
-                    total_pages,
+                    &page,
                 );
+                header_footer_links.extend(areas.into_iter().map(|a| (page_idx, a)));
             }
         }
+        if options.tagged {
+            content.end_marked_content();
+        }
+
+        // Page borders go underneath everything else on the page
+        let bytes = if let Some(borders) = &sect.page_borders {
+            let mut frame = Content::new();
+            if options.tagged {
+                frame.begin_marked_content(Name(b"Artifact"));
+            }
+            draw_page_borders(&mut frame, sect, borders);
+            if options.tagged {
+                frame.end_marked_content();
+            }
+            let mut bytes = frame.finish().to_vec();
+            bytes.push(b'\n');
+            bytes.extend_from_slice(&content.finish());
+            bytes
+        } else {
+            content.finish().to_vec()
+        };
+        bytes.hash(&mut file_id);
+        let content_id = alloc();
+        pdf.stream(content_id, &deflate(&bytes))
+            .filter(Filter::FlateDecode);
+        kept.push(page_idx);
+        content_ids.push(content_id);
+        page_sections.push(sect_idx);
+    };
+    let body = layout_body(
+        doc,
+        &seen_fonts,
+        &image_pdf_names,
+        &float_image_pdf_names,
+        counted_pages,
+        Flow::new(
+            doc,
+            &margins,
+            &v_offsets,
+            options.tagged,
+            &now,
+            Some(&mut write_page),
+        ),
+    );
+    let BodyLayout {
+        pages: total_pages,
+        section_first_pages,
+        mut links,
+        mut bookmark_positions,
+        mut heading_positions,
+        mut tags,
+        ..
+    } = body;
+    links.extend(header_footer_links);
+
+    // Phase 2b: keep only the requested pages. Layout, page numbers and
+    // headers were done for the whole document, so they match the original
+    // pagination; `kept` holds the original index of each page written.
+    if let Some(range) = &options.pages
+        && kept.is_empty()
+    {
+        return Err(Error::Pdf(format!(
+            "pages {}-{} are outside the document's {total_pages} pages",
+            range.start(),
+            range.end()
+        )));
+    }
+    if kept.len() < total_pages {
+        let mut new_index: Vec<Option<usize>> = vec![None; total_pages];
+        for (new, &old) in kept.iter().enumerate() {
+            new_index[old] = Some(new);
+        }
+        links.retain_mut(|(page_idx, _)| new_index[*page_idx].map(|new| *page_idx = new).is_some());
+        bookmark_positions
+            .retain(|_, (page_idx, _)| new_index[*page_idx].map(|new| *page_idx = new).is_some());
+        heading_positions.retain_mut(|(_, page_idx, _)| {
+            new_index[*page_idx].map(|new| *page_idx = new).is_some()
+        });
+        if let Some(tags) = &mut tags {
+            tags.retain_pages(&kept);
+        }
     }
 
-    // Phase 3: allocate page and content IDs now that page count is known
-    let n = all_contents.len();
+    // Phase 3: allocate page IDs now that page count is known
+    let n = kept.len();
     let page_ids: Vec<Ref> = (0..n).map(|_| alloc()).collect();
-    let content_ids: Vec<Ref> = (0..n).map(|_| alloc()).collect();
 
-    for (i, c) in all_contents.into_iter().enumerate() {
-        pdf.stream(content_ids[i], &c.finish());
+    let metadata_id = write_metadata(&mut pdf, &mut alloc, &doc.metadata, options);
+    // PDF/A: DeviceRGB colors are declared sRGB through the output intent
+    let icc_id = options.pdfa.then(|| {
+        let icc_id = alloc();
+        pdf.icc_profile(icc_id, &deflate(SRGB_ICC))
+            .n(3)
+            .range([0.0, 1.0, 0.0, 1.0, 0.0, 1.0])
+            .filter(Filter::FlateDecode);
+        icc_id
+    });
+    let outline_id = write_outline(&mut pdf, &mut alloc, doc, &heading_positions, &page_ids);
+    // Anchors to bookmarks that were never laid out produce no annotation
+    let link_annots: Vec<(Ref, usize, &LinkArea)> = links
+        .iter()
+        .filter(|(_, area)| match &area.target {
+            LinkTarget::Anchor(name) => bookmark_positions.contains_key(name.as_str()),
+            LinkTarget::Url(_) => true,
+        })
+        .map(|(page_idx, area)| (alloc(), *page_idx, area))
+        .collect();
+    let tagged_links: Vec<(usize, usize, Ref)> = link_annots
+        .iter()
+        .filter_map(|&(annot_id, page_idx, area)| Some((area.tag?, page_idx, annot_id)))
+        .collect();
+    let struct_tree_id = tags
+        .as_ref()
+        .map(|tags| write_struct_tree(&mut pdf, &mut alloc, tags, &page_ids, &tagged_links));
+    let page_labels = write_page_labels(&mut pdf, &mut alloc, doc, &section_first_pages, &kept);
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog.pages(pages_id).metadata(metadata_id);
+    if let Some(outline_id) = outline_id {
+        catalog.outlines(outline_id);
+    }
+    if !page_labels.is_empty() {
+        let mut labels = catalog.page_labels();
+        let mut nums = labels.nums();
+        for (page_idx, label_id) in page_labels {
+            nums.insert(page_idx, label_id);
+        }
+    }
+    if let Some(struct_tree_id) = struct_tree_id {
+        catalog.pair(Name(b"StructTreeRoot"), struct_tree_id);
+        catalog.mark_info().marked(true);
+        if let Some(lang) = &doc.lang {
+            catalog.lang(TextStr(lang));
+        }
+        // Screen readers announce the title rather than the file name
+        if doc.metadata.title.is_some() {
+            catalog.viewer_preferences().display_doc_title(true);
+        }
+    }
+    if let Some(icc_id) = icc_id {
+        catalog
+            .output_intents()
+            .push()
+            .subtype(OutputIntentSubtype::PDFA)
+            .output_condition_identifier(TextStr("sRGB"))
+            .info(TextStr("sRGB IEC61966-2.1"))
+            .dest_output_profile(icc_id);
+    }
+    drop(catalog);
+    if options.pdfa {
+        let id = file_id.finish().to_be_bytes().to_vec();
+        pdf.set_file_id((id.clone(), id));
     }
-
-    pdf.catalog(catalog_id).pages(pages_id);
     pdf.pages(pages_id)
         .kids(page_ids.iter().copied())
         .count(n as i32);
 
-    let font_pairs: Vec<(String, Ref)> = font_order
+    let mut font_pairs: Vec<(String, Ref)> = font_order
         .iter()
         .map(|name| (seen_fonts[name].pdf_name.clone(), seen_fonts[name].font_ref))
         .collect();
+    // Type0 fonts for text beyond WinAnsi, the subset font files and the
+    // ToUnicode CMaps, now that layout has used their glyphs
+    for name in &font_order {
+        font_pairs.extend(write_composite_font(
+            &mut pdf,
+            &seen_fonts[name],
+            &mut alloc,
+        ));
+        write_font_file(&mut pdf, &seen_fonts[name]);
+        write_to_unicode(&mut pdf, &seen_fonts[name]);
+    }
+
+    let mut page_annots: Vec<Vec<Ref>> = vec![Vec::new(); n];
+    // Tagged annotations take the parent tree keys after the pages'
+    let mut struct_parent = n as i32;
+    for &(annot_id, page_idx, area) in &link_annots {
+        let mut annot = pdf.annotation(annot_id);
+        annot
+            .subtype(AnnotationType::Link)
+            .flags(AnnotationFlags::PRINT)
+            .rect(area.rect)
+            .border(0.0, 0.0, 0.0, None);
+        if struct_tree_id.is_some() && area.tag.is_some() {
+            annot.struct_parent(struct_parent);
+            struct_parent += 1;
+        }
+        let mut action = annot.action();
+        match &area.target {
+            LinkTarget::Url(url) => {
+                action.action_type(ActionType::Uri).uri(Str(url.as_bytes()));
+            }
+            LinkTarget::Anchor(name) => {
+                let (dest_page, top) = bookmark_positions[name.as_str()];
+                action
+                    .action_type(ActionType::GoTo)
+                    .destination()
+                    .page(page_ids[dest_page])
+                    .xyz(0.0, top, None);
+            }
+        }
+        page_annots[page_idx].push(annot_id);
+    }
 
     for i in 0..n {
         let mut page = pdf.page(page_ids[i]);
-        page.media_box(Rect::new(0.0, 0.0, doc.page_width, doc.page_height))
+        let sect = &doc.sections[page_sections[i]];
+        page.media_box(Rect::new(0.0, 0.0, sect.page_width, sect.page_height))
             .parent(pages_id)
             .contents(content_ids[i]);
+        if struct_tree_id.is_some() {
+            page.struct_parents(i as i32)
+                .tab_order(TabOrder::StructureOrder);
+        }
+        if !page_annots[i].is_empty() {
+            page.annotations(page_annots[i].iter().copied());
+        }
         {
             let mut resources = page.resources();
             {
@@ -1267,12 +4084,348 @@ pub fn render(doc: &Document) -> Result<Vec<u8>, Error> {
     Ok(pdf.finish())
 }
 
-fn label_for_run<'a>(
-    run: &Run,
-    seen_fonts: &'a HashMap<String, FontEntry>,
-    label: &str,
-) -> (&'a str, Vec<u8>) {
-    let key = font_key(run);
-    let entry = seen_fonts.get(&key).expect("font registered");
-    (entry.pdf_name.as_str(), to_winansi_bytes(label))
+/// Text of a heading for its outline entry: the list label, if any, and the
+/// visible run text on one line.
+fn outline_title(para: &Paragraph) -> String {
+    let mut title = String::new();
+    if let Some(label) = &para.list_label {
+        title.push_str(label.run.text.trim());
+        title.push(' ');
+    }
+    for run in para.runs.iter().filter(|r| !r.hidden) {
+        if run.is_tab || run.is_line_break {
+            title.push(' ');
+        } else {
+            title.push_str(&run.text);
+        }
+    }
+    title.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Write the document outline from the headings laid out at
+/// `heading_positions`, nesting each under the closest preceding heading of
+/// a higher level. Returns None for documents without headings.
+fn write_outline(
+    pdf: &mut Pdf,
+    alloc: &mut impl FnMut() -> Ref,
+    doc: &Document,
+    heading_positions: &[(usize, usize, f32)],
+    page_ids: &[Ref],
+) -> Option<Ref> {
+    if heading_positions.is_empty() {
+        return None;
+    }
+    let outline_id = alloc();
+    let item_ids: Vec<Ref> = heading_positions.iter().map(|_| alloc()).collect();
+    let levels: Vec<u8> = heading_positions
+        .iter()
+        .map(|&(block_idx, ..)| match &doc.blocks[block_idx] {
+            Block::Paragraph(p) => p.outline_level.unwrap_or(0),
+            Block::Table(_) => 0,
+        })
+        .collect();
+
+    // Parent of each item (None: the outline root) and the children of each
+    // item, with the root's children last
+    let n = heading_positions.len();
+    let mut parents: Vec<Option<usize>> = vec![None; n];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); n + 1];
+    let mut stack: Vec<usize> = Vec::new();
+    for i in 0..n {
+        while stack.last().is_some_and(|&top| levels[top] >= levels[i]) {
+            stack.pop();
+        }
+        parents[i] = stack.last().copied();
+        children[parents[i].unwrap_or(n)].push(i);
+        stack.push(i);
+    }
+    // Items are open, so every descendant counts as visible
+    let mut descendants = vec![0i32; n + 1];
+    for i in (0..n).rev() {
+        descendants[parents[i].unwrap_or(n)] += 1 + descendants[i];
+    }
+
+    let root_children = &children[n];
+    pdf.outline(outline_id)
+        .first(item_ids[root_children[0]])
+        .last(item_ids[*root_children.last().unwrap()])
+        .count(descendants[n]);
+
+    for (i, &(block_idx, page_idx, top)) in heading_positions.iter().enumerate() {
+        let Block::Paragraph(para) = &doc.blocks[block_idx] else {
+            continue;
+        };
+        let title = outline_title(para);
+        let siblings = &children[parents[i].unwrap_or(n)];
+        let pos = siblings.iter().position(|&s| s == i).unwrap();
+        let mut item = pdf.outline_item(item_ids[i]);
+        item.title(TextStr(&title))
+            .parent(parents[i].map_or(outline_id, |p| item_ids[p]));
+        if pos > 0 {
+            item.prev(item_ids[siblings[pos - 1]]);
+        }
+        if let Some(&next) = siblings.get(pos + 1) {
+            item.next(item_ids[next]);
+        }
+        if let (Some(&first), Some(&last)) = (children[i].first(), children[i].last()) {
+            item.first(item_ids[first])
+                .last(item_ids[last])
+                .count(descendants[i]);
+        }
+        item.dest().page(page_ids[page_idx]).xyz(0.0, top, None);
+    }
+    Some(outline_id)
+}
+
+/// Write the page labels viewers show for each page, from the page numbering
+/// of its section. `pages` holds the index in the laid-out document of each
+/// written page. Returns the first page index and label of each labelled
+/// range, or nothing when pages are numbered 1, 2, 3... throughout.
+fn write_page_labels(
+    pdf: &mut Pdf,
+    alloc: &mut impl FnMut() -> Ref,
+    doc: &Document,
+    section_first_pages: &[usize],
+    pages: &[usize],
+) -> Vec<(i32, Ref)> {
+    // (first page, style, number of the first page) of each range. Formats a
+    // PDF viewer can't count in, and page 0 (/St is at least 1), get a range
+    // per page with the number as prefix.
+    let mut ranges: Vec<(usize, Option<NumberingStyle>, usize)> = Vec::new();
+    let mut prefixes: HashMap<usize, String> = HashMap::new();
+    for (page_idx, &laid_out_idx) in pages.iter().enumerate() {
+        let sect_idx = section_first_pages
+            .iter()
+            .rposition(|&first| first <= laid_out_idx)
+            .unwrap_or(0);
+        let fmt = doc.sections[sect_idx].page_number_format.as_str();
+        let num = page_number(doc, section_first_pages, laid_out_idx);
+        let style = match fmt {
+            "lowerRoman" => Some(NumberingStyle::LowerRoman),
+            "upperRoman" => Some(NumberingStyle::UpperRoman),
+            "lowerLetter" => Some(NumberingStyle::LowerAlpha),
+            "upperLetter" => Some(NumberingStyle::UpperAlpha),
+            "decimalZero" | "ordinal" | "cardinalText" | "ordinalText" | "none" => None,
+            _ => Some(NumberingStyle::Arabic),
+        }
+        .filter(|_| num > 0);
+        let continues = ranges.last().is_some_and(|&(first, last_style, start)| {
+            style.is_some() && last_style == style && start + page_idx - first == num
+        });
+        if !continues {
+            if style.is_none() {
+                prefixes.insert(page_idx, format_number(num, fmt));
+            }
+            ranges.push((page_idx, style, num));
+        }
+    }
+    if ranges == [(0, Some(NumberingStyle::Arabic), 1)] {
+        return Vec::new();
+    }
+
+    ranges
+        .into_iter()
+        .map(|(first, style, start)| {
+            let id = alloc();
+            let mut label = pdf.indirect(id).start::<PageLabel>();
+            match style {
+                Some(style) => {
+                    label.style(style).offset(start as i32);
+                }
+                None => {
+                    let prefix = &prefixes[&first];
+                    if !prefix.is_empty() {
+                        label.prefix(TextStr(prefix));
+                    }
+                }
+            }
+            (first as i32, id)
+        })
+        .collect()
+}
+
+const PRODUCER: &str = concat!("docxside-pdf ", env!("CARGO_PKG_VERSION"));
+
+/// The ICC's sRGB v4 profile, the output intent of PDF/A output.
+const SRGB_ICC: &[u8] = include_bytes!("../assets/sRGB-v4.icc");
+
+fn pdf_date(ts: jiff::Timestamp) -> Date {
+    let utc = ts.to_zoned(jiff::tz::TimeZone::UTC);
+    Date::new(utc.year() as u16)
+        .month(utc.month() as u8)
+        .day(utc.day() as u8)
+        .hour(utc.hour() as u8)
+        .minute(utc.minute() as u8)
+        .second(utc.second() as u8)
+        .utc_offset_hour(0)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The time DATE and TIME fields show: the time of conversion, or with
+/// `omit_dates` the document's own last-saved (or creation) time in UTC, so
+/// that the output doesn't depend on when it is made.
+fn field_time(metadata: &Metadata, options: &ConversionOptions) -> jiff::Zoned {
+    if !options.omit_dates {
+        return jiff::Zoned::now();
+    }
+    metadata
+        .modified
+        .or(metadata.created)
+        .unwrap_or(jiff::Timestamp::UNIX_EPOCH)
+        .to_zoned(jiff::tz::TimeZone::UTC)
+}
+
+/// Write the document information dictionary and an XMP metadata stream
+/// carrying the same properties, returning the stream for the catalog. The
+/// creation date is the document's own, or the conversion time if it has none.
+fn write_metadata(
+    pdf: &mut Pdf,
+    alloc: &mut impl FnMut() -> Ref,
+    metadata: &Metadata,
+    options: &ConversionOptions,
+) -> Ref {
+    let (created, modified) = if options.omit_dates {
+        (None, None)
+    } else {
+        // Whole seconds, so the XMP dates match the Info dictionary's exactly
+        let now = || jiff::Timestamp::from_second(jiff::Timestamp::now().as_second()).unwrap();
+        let created = metadata.created.unwrap_or_else(now);
+        (Some(created), Some(metadata.modified.unwrap_or(created)))
+    };
+
+    let mut info = pdf.document_info(alloc());
+    if let Some(title) = &metadata.title {
+        info.title(TextStr(title));
+    }
+    if let Some(author) = &metadata.author {
+        info.author(TextStr(author));
+    }
+    if let Some(subject) = &metadata.subject {
+        info.subject(TextStr(subject));
+    }
+    if let Some(keywords) = &metadata.keywords {
+        info.keywords(TextStr(keywords));
+    }
+    info.producer(TextStr(PRODUCER));
+    if let Some(created) = created {
+        info.creation_date(pdf_date(created));
+    }
+    if let Some(modified) = modified {
+        info.modified_date(pdf_date(modified));
+    }
+    drop(info);
+
+    let mut xmp = String::from(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         <rdf:Description rdf:about=\"\"\n  \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n  \
+         xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n  \
+         xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"\n  \
+         xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">\n\
+         <dc:format>application/pdf</dc:format>\n",
+    );
+    if options.pdfa {
+        xmp += "<pdfaid:part>2</pdfaid:part>\n<pdfaid:conformance>B</pdfaid:conformance>\n";
+    }
+    let alt = |value: &str| {
+        format!(
+            "<rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt>",
+            xml_escape(value)
+        )
+    };
+    if let Some(title) = &metadata.title {
+        xmp += &format!("<dc:title>{}</dc:title>\n", alt(title));
+    }
+    if let Some(author) = &metadata.author {
+        xmp += &format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+            xml_escape(author)
+        );
+    }
+    if let Some(subject) = &metadata.subject {
+        xmp += &format!("<dc:description>{}</dc:description>\n", alt(subject));
+    }
+    if let Some(keywords) = &metadata.keywords {
+        xmp += &format!("<pdf:Keywords>{}</pdf:Keywords>\n", xml_escape(keywords));
+    }
+    xmp += &format!("<pdf:Producer>{PRODUCER}</pdf:Producer>\n");
+    if let Some(created) = created {
+        xmp += &format!("<xmp:CreateDate>{created}</xmp:CreateDate>\n");
+    }
+    if let Some(modified) = modified {
+        xmp += &format!("<xmp:ModifyDate>{modified}</xmp:ModifyDate>\n");
+    }
+    xmp += "</rdf:Description>\n</rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>";
+
+    // Left uncompressed so tools that scan for the packet can read it
+    let metadata_id = alloc();
+    pdf.metadata(metadata_id, xmp.as_bytes());
+    metadata_id
+}
+
+const BAR_TAB_WIDTH: f32 = 0.75;
+
+/// Draw the vertical rule of each bar tab in `tab_stops` down the lines of a
+/// paragraph, from `top` to `bottom`; positions are relative to `column_left`.
+fn draw_bar_tabs(
+    content: &mut Content,
+    tab_stops: &[TabStop],
+    column_left: f32,
+    top: f32,
+    bottom: f32,
+) {
+    for stop in tab_stops
+        .iter()
+        .filter(|s| s.alignment == TabAlignment::Bar)
+    {
+        let x = column_left + stop.position - BAR_TAB_WIDTH / 2.0;
+        content
+            .rect(x, bottom, BAR_TAB_WIDTH, top - bottom)
+            .fill_nonzero();
+    }
+}
+
+/// Draw a list label in its own font at the number position `x`. Right- and
+/// center-justified labels (`w:lvlJc`) end at or straddle that position, so
+/// wide labels like "viii." grow into the margin instead of into the text.
+fn draw_list_label(
+    content: &mut Content,
+    label: &ListLabel,
+    seen_fonts: &HashMap<String, FontEntry>,
+    x: f32,
+    baseline_y: f32,
+) {
+    let run = &label.run;
+    let entry = seen_fonts.get(&font_key(run)).expect("font registered");
+    let font_size = effective_font_size(run);
+    let encoded = entry.encode(&run.text);
+    let label_w = encoded.width_1000 * font_size / 1000.0;
+    let label_x = match label.alignment {
+        Alignment::Right => x - label_w,
+        Alignment::Center => x - label_w / 2.0,
+        Alignment::Left | Alignment::Justify => x,
+    };
+    if let Some([r, g, b]) = run.color {
+        content.set_fill_rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    }
+    show_text(
+        content,
+        entry.pdf_font(encoded.composite),
+        font_size,
+        entry.synthetic,
+        run.color,
+        (label_x, baseline_y),
+        (&encoded.bytes, &encoded.kerning),
+    );
+    if run.color.is_some() {
+        content.set_fill_gray(0.0);
+    }
 }
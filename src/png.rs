@@ -0,0 +1,213 @@
+/// A PNG decoded to what a PDF image XObject holds: 8-bit gray or RGB
+/// samples, and a separate alpha channel when the image has transparency.
+pub(crate) struct Png {
+    pub rgb: bool, // three samples per pixel, otherwise one
+    pub samples: Vec<u8>,
+    pub alpha: Option<Vec<u8>>,
+}
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1, // gray or palette index
+        }
+    }
+}
+
+/// The chunks of a PNG as (type, data), up to IEND or the end of the data.
+fn chunks(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut rest = data.strip_prefix(SIGNATURE).unwrap_or_default();
+    std::iter::from_fn(move || {
+        let len = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let kind = rest.get(4..8)?;
+        let body = rest.get(8..8usize.checked_add(len)?)?;
+        // Skip the CRC
+        rest = rest.get(8 + len + 4..)?;
+        (kind != b"IEND").then_some((kind, body))
+    })
+}
+
+/// The IHDR of a PNG we can decode: not interlaced, and a valid combination
+/// of color type and bit depth.
+fn header(data: &[u8]) -> Option<Header> {
+    let (kind, ihdr) = chunks(data).next()?;
+    if kind != b"IHDR" || ihdr.len() != 13 {
+        return None;
+    }
+    let header = Header {
+        width: u32::from_be_bytes(ihdr[0..4].try_into().ok()?),
+        height: u32::from_be_bytes(ihdr[4..8].try_into().ok()?),
+        bit_depth: ihdr[8],
+        color_type: ihdr[9],
+    };
+    let valid = matches!(
+        (header.color_type, header.bit_depth),
+        (0, 1 | 2 | 4 | 8 | 16) | (2 | 4 | 6, 8 | 16) | (3, 1 | 2 | 4 | 8)
+    );
+    let (compression, filter, interlace) = (ihdr[10], ihdr[11], ihdr[12]);
+    (valid
+        && header.width > 0
+        && header.height > 0
+        && (compression, filter, interlace) == (0, 0, 0))
+        .then_some(header)
+}
+
+/// Pixel size of a PNG, or None if it isn't one we can decode.
+pub(crate) fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    header(data).map(|h| (h.width, h.height))
+}
+
+/// Reverse the per-row filters of the decompressed image data. `bpp` is the
+/// number of bytes per pixel, at least one.
+fn unfilter(raw: &[u8], height: usize, stride: usize, bpp: usize) -> Option<Vec<u8>> {
+    let mut out = vec![0u8; height * stride];
+    for y in 0..height {
+        let line = raw.get(y * (stride + 1)..(y + 1) * (stride + 1))?;
+        let (filter, line) = (line[0], &line[1..]);
+        let (done, rest) = out.split_at_mut(y * stride);
+        let prev = (y > 0).then(|| &done[(y - 1) * stride..]);
+        let cur = &mut rest[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { cur[x - bpp] } else { 0 };
+            let b = prev.map_or(0, |p| p[x]);
+            let c = if x >= bpp {
+                prev.map_or(0, |p| p[x - bpp])
+            } else {
+                0
+            };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            cur[x] = line[x].wrapping_add(predicted);
+        }
+    }
+    Some(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decode a non-interlaced PNG. Palette images are expanded to RGB, 16-bit
+/// samples are cut to 8 bits, and a `tRNS` chunk becomes an alpha channel.
+pub(crate) fn decode(data: &[u8]) -> Option<Png> {
+    let header = header(data)?;
+    let mut palette: &[u8] = &[];
+    let mut transparency: Option<&[u8]> = None;
+    let mut compressed = Vec::new();
+    for (kind, body) in chunks(data) {
+        match kind {
+            b"PLTE" => palette = body,
+            b"tRNS" => transparency = Some(body),
+            b"IDAT" => compressed.extend_from_slice(body),
+            _ => {}
+        }
+    }
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed).ok()?;
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let depth = header.bit_depth as usize;
+    let channels = header.channels();
+    let stride = (width * channels * depth).div_ceil(8);
+    let bpp = (channels * depth / 8).max(1);
+    // Checked before allocating, since the header can claim any size
+    if raw.len() < height * (stride + 1) {
+        return None;
+    }
+    let pixels = unfilter(&raw, height, stride, bpp)?;
+
+    // Sample `i` of a row, at the image's bit depth
+    let sample = |row: &[u8], i: usize| -> u16 {
+        match depth {
+            16 => u16::from_be_bytes([row[2 * i], row[2 * i + 1]]),
+            8 => row[i] as u16,
+            _ => {
+                let bit = i * depth;
+                let shift = 8 - depth - bit % 8;
+                ((row[bit / 8] >> shift) & ((1 << depth) - 1) as u8) as u16
+            }
+        }
+    };
+    let to_8_bit = |v: u16| -> u8 {
+        match depth {
+            16 => (v >> 8) as u8,
+            8 => v as u8,
+            _ => (v * 255 / ((1 << depth) - 1)) as u8,
+        }
+    };
+    // The color a tRNS chunk makes transparent in gray and RGB images
+    let key: Option<Vec<u16>> = transparency
+        .filter(|_| matches!(header.color_type, 0 | 2))
+        .map(|t| {
+            t.chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect()
+        });
+
+    let rgb = matches!(header.color_type, 2 | 3 | 6);
+    let mut samples = Vec::with_capacity(width * height * if rgb { 3 } else { 1 });
+    let mut alpha = Vec::with_capacity(width * height);
+    for row in pixels.chunks_exact(stride) {
+        for x in 0..width {
+            let px: Vec<u16> = (0..channels)
+                .map(|c| sample(row, x * channels + c))
+                .collect();
+            let a = match header.color_type {
+                3 => {
+                    let idx = px[0] as usize;
+                    samples.extend_from_slice(palette.get(idx * 3..idx * 3 + 3)?);
+                    transparency
+                        .and_then(|t| t.get(idx).copied())
+                        .unwrap_or(255)
+                }
+                4 | 6 => {
+                    samples.extend(px[..channels - 1].iter().map(|&v| to_8_bit(v)));
+                    to_8_bit(px[channels - 1])
+                }
+                _ => {
+                    samples.extend(px.iter().map(|&v| to_8_bit(v)));
+                    if key.as_deref() == Some(&px[..]) {
+                        0
+                    } else {
+                        255
+                    }
+                }
+            };
+            alpha.push(a);
+        }
+    }
+    Some(Png {
+        rgb,
+        samples,
+        alpha: alpha.iter().any(|&a| a < 255).then_some(alpha),
+    })
+}
@@ -0,0 +1,130 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use ttf_parser::{Face, RawFaceTables};
+
+/// The tables of a face that its family name and style come from, read from
+/// a font file without loading the rest of it, which for large CJK fonts is
+/// tens of megabytes of outlines.
+pub(crate) struct StyleTables {
+    head: Vec<u8>,
+    hhea: Vec<u8>,
+    maxp: Vec<u8>,
+    name: Option<Vec<u8>>,
+    os2: Option<Vec<u8>>,
+    post: Option<Vec<u8>>,
+}
+
+/// `len` bytes at `offset`, or None if the file ends before them. The length
+/// comes from the file's own table records, so it is checked against the file
+/// size before anything is allocated.
+fn read_at<R: Read + Seek>(file: &mut R, offset: u64, len: usize) -> Option<Vec<u8>> {
+    let size = file.seek(SeekFrom::End(0)).ok()?;
+    if offset.checked_add(len as u64)? > size {
+        return None;
+    }
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = vec![0; len];
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Number of faces in a font file: the count of a TrueType collection, or 1.
+pub(crate) fn face_count<R: Read + Seek>(file: &mut R) -> u32 {
+    read_at(file, 0, 12)
+        .filter(|header| header.starts_with(b"ttcf"))
+        .and_then(|header| be_u32(&header, 8))
+        .unwrap_or(1)
+}
+
+/// The table records of face `face_index`, from the table directory at the
+/// start of the file or the one a collection's header points to.
+fn table_records<R: Read + Seek>(file: &mut R, face_index: u32) -> Option<Vec<u8>> {
+    let header = read_at(file, 0, 12)?;
+    let offset = if header.starts_with(b"ttcf") {
+        if face_index >= be_u32(&header, 8)? {
+            return None;
+        }
+        let entry = read_at(file, 12 + 4 * face_index as u64, 4)?;
+        be_u32(&entry, 0)? as u64
+    } else {
+        0
+    };
+    let header = read_at(file, offset, 12)?;
+    if !matches!(&header[..4], [0, 1, 0, 0] | b"OTTO" | b"true") {
+        return None;
+    }
+    let num_tables = u16::from_be_bytes([header[4], header[5]]) as usize;
+    read_at(file, offset + 12, num_tables * 16)
+}
+
+/// Table `tag` of the face with `records`, cut to `max_len` bytes if given.
+fn read_table<R: Read + Seek>(
+    file: &mut R,
+    records: &[u8],
+    tag: &[u8; 4],
+    max_len: Option<usize>,
+) -> Option<Vec<u8>> {
+    let record = records.chunks_exact(16).find(|r| &r[..4] == tag)?;
+    let len = be_u32(record, 12)? as usize;
+    read_at(
+        file,
+        be_u32(record, 8)? as u64,
+        max_len.map_or(len, |m| len.min(m)),
+    )
+}
+
+/// The `cmap` table of face `face_index`, which maps characters to glyphs.
+/// The outer None is a file that isn't a font; the inner one a font without
+/// a `cmap`.
+pub(crate) fn read_cmap<R: Read + Seek>(file: &mut R, face_index: u32) -> Option<Option<Vec<u8>>> {
+    let records = table_records(file, face_index)?;
+    Some(read_table(file, &records, b"cmap", None))
+}
+
+impl StyleTables {
+    /// Read the tables of face `face_index` through the table directory.
+    pub(crate) fn read<R: Read + Seek>(file: &mut R, face_index: u32) -> Option<Self> {
+        let records = table_records(file, face_index)?;
+        let mut table = |tag: &[u8; 4], max_len: Option<usize>| -> Option<Vec<u8>> {
+            read_table(file, &records, tag, max_len)
+        };
+        let head = table(b"head", None)?;
+        let hhea = table(b"hhea", None)?;
+        let maxp = table(b"maxp", None)?;
+        let name = table(b"name", None);
+        let os2 = table(b"OS/2", None);
+        // Only the fixed header of `post` is needed. Glyph names follow it in
+        // version 2, so the truncated table is marked version 3, which has none.
+        let post = table(b"post", Some(32)).map(|mut post| {
+            if post.starts_with(&[0, 2, 0, 0]) {
+                post[..4].copy_from_slice(&[0, 3, 0, 0]);
+            }
+            post
+        });
+        Some(StyleTables {
+            head,
+            hhea,
+            maxp,
+            name,
+            os2,
+            post,
+        })
+    }
+
+    pub(crate) fn face(&self) -> Option<Face<'_>> {
+        Face::from_raw_tables(RawFaceTables {
+            head: &self.head,
+            hhea: &self.hhea,
+            maxp: &self.maxp,
+            name: self.name.as_deref(),
+            os2: self.os2.as_deref(),
+            post: self.post.as_deref(),
+            ..Default::default()
+        })
+        .ok()
+    }
+}
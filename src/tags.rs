@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+
+use pdf_writer::types::StructRole;
+use pdf_writer::writers::StructTreeRoot;
+use pdf_writer::{Content, Name, Pdf, Ref, TextStr};
+
+/// Something a structure element contains: a marked-content sequence on a
+/// page, or another element.
+enum Kid {
+    Content { page: usize, mcid: i32 },
+    Element(usize),
+}
+
+struct Element {
+    role: StructRole,
+    parent: Option<usize>, // None for children of the document element
+    alt: Option<String>,
+    kids: Vec<Kid>,
+}
+
+/// Logical structure of the body, collected while it is laid out. Elements
+/// are opened and closed around blocks; content drawn while an element is
+/// marked goes into a marked-content sequence carrying an MCID of its page.
+#[derive(Default)]
+pub(crate) struct Tags {
+    elements: Vec<Element>,
+    open: Vec<usize>,            // open elements, innermost last
+    marked: Option<usize>,       // element whose marked-content sequence is open
+    page_mcids: Vec<Vec<usize>>, // element of each MCID, per page
+    artifact_depth: usize,       // nesting of open artifacts, which take no elements
+}
+
+impl Tags {
+    /// Add a child element to the innermost open element, without opening it.
+    /// Returns None inside an artifact.
+    pub(crate) fn add(&mut self, role: StructRole, alt: Option<&str>) -> Option<usize> {
+        self.add_to(self.open.last().copied(), role, alt)
+    }
+
+    fn add_to(
+        &mut self,
+        parent: Option<usize>,
+        role: StructRole,
+        alt: Option<&str>,
+    ) -> Option<usize> {
+        if self.artifact_depth > 0 {
+            return None;
+        }
+        let idx = self.elements.len();
+        self.elements.push(Element {
+            role,
+            parent,
+            alt: alt.filter(|a| !a.is_empty()).map(str::to_string),
+            kids: Vec::new(),
+        });
+        if let Some(parent) = parent {
+            self.elements[parent].kids.push(Kid::Element(idx));
+        }
+        Some(idx)
+    }
+
+    /// Add a child element and make it the innermost open one.
+    pub(crate) fn open(&mut self, role: StructRole, alt: Option<&str>) -> Option<usize> {
+        let idx = self.add(role, alt)?;
+        self.open.push(idx);
+        Some(idx)
+    }
+
+    /// Open a child element of the innermost open element outside any list,
+    /// for content such as a text box that floats beside a list item.
+    pub(crate) fn open_outside_lists(&mut self, role: StructRole) -> Option<usize> {
+        let list_roles = [
+            StructRole::L,
+            StructRole::LI,
+            StructRole::Lbl,
+            StructRole::LBody,
+        ];
+        let parent = self
+            .open
+            .iter()
+            .rev()
+            .find(|&&idx| !list_roles.contains(&self.elements[idx].role))
+            .copied();
+        let idx = self.add_to(parent, role, None)?;
+        self.open.push(idx);
+        Some(idx)
+    }
+
+    /// Add a Link element for a link annotation to the element whose content
+    /// is being marked, or else to the innermost open one.
+    pub(crate) fn add_link(&mut self) -> Option<usize> {
+        self.add_to(
+            self.marked.or(self.open.last().copied()),
+            StructRole::Link,
+            None,
+        )
+    }
+
+    /// Close the innermost open element, ending its marked content.
+    pub(crate) fn close(&mut self, content: &mut Content) {
+        if self.artifact_depth > 0 {
+            return;
+        }
+        self.unmark(content);
+        self.open.pop();
+    }
+
+    /// Role of the innermost open element.
+    pub(crate) fn innermost(&self) -> Option<StructRole> {
+        self.open.last().map(|&idx| self.elements[idx].role)
+    }
+
+    /// Child elements of the innermost open element, in order. Empty inside
+    /// an artifact.
+    pub(crate) fn children(&self) -> Vec<usize> {
+        let Some(&idx) = self.open.last().filter(|_| self.artifact_depth == 0) else {
+            return Vec::new();
+        };
+        self.elements[idx]
+            .kids
+            .iter()
+            .filter_map(|kid| match kid {
+                Kid::Element(child) => Some(*child),
+                Kid::Content { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Start a marked-content sequence for `elem` on page `page`, ending any
+    /// open one first.
+    pub(crate) fn mark(&mut self, content: &mut Content, page: usize, elem: usize) {
+        self.unmark(content);
+        if self.page_mcids.len() <= page {
+            self.page_mcids.resize_with(page + 1, Vec::new);
+        }
+        let mcids = &mut self.page_mcids[page];
+        let mcid = mcids.len() as i32;
+        mcids.push(elem);
+        self.elements[elem].kids.push(Kid::Content { page, mcid });
+        content
+            .begin_marked_content_with_properties(self.elements[elem].role.to_name())
+            .properties()
+            .identify(mcid);
+        self.marked = Some(elem);
+    }
+
+    /// End the open marked-content sequence, if any, returning its element.
+    pub(crate) fn unmark(&mut self, content: &mut Content) -> Option<usize> {
+        let elem = self.marked.take()?;
+        content.end_marked_content();
+        Some(elem)
+    }
+
+    /// Drop the content of pages left out of the output and renumber the
+    /// rest; `kept` holds the original index of each remaining page.
+    pub(crate) fn retain_pages(&mut self, kept: &[usize]) {
+        let mut page_mcids = Vec::with_capacity(kept.len());
+        for &old in kept {
+            page_mcids.push(self.page_mcids.get(old).cloned().unwrap_or_default());
+        }
+        self.page_mcids = page_mcids;
+        for elem in &mut self.elements {
+            elem.kids.retain_mut(|kid| match kid {
+                Kid::Content { page, .. } => match kept.iter().position(|&old| old == *page) {
+                    Some(new) => {
+                        *page = new;
+                        true
+                    }
+                    None => false,
+                },
+                Kid::Element(_) => true,
+            });
+        }
+    }
+
+    /// Mark what follows as an artifact: decoration outside the structure tree.
+    pub(crate) fn begin_artifact(&mut self, content: &mut Content) {
+        if self.artifact_depth == 0 {
+            content.begin_marked_content(Name(b"Artifact"));
+        }
+        self.artifact_depth += 1;
+    }
+
+    pub(crate) fn end_artifact(&mut self, content: &mut Content) {
+        self.artifact_depth -= 1;
+        if self.artifact_depth == 0 {
+            content.end_marked_content();
+        }
+    }
+}
+
+/// Write the structure tree under a single Document element, with a parent
+/// tree entry for the marked content of each page and one for each link
+/// annotation after them. Pages must be written with `/StructParents` set to
+/// their index, and the annotation of `links[i]`, given as (Link element,
+/// page index, annotation), with `/StructParent` set to the page count plus `i`.
+/// Returns the StructTreeRoot.
+pub(crate) fn write_struct_tree(
+    pdf: &mut Pdf,
+    alloc: &mut impl FnMut() -> Ref,
+    tags: &Tags,
+    page_ids: &[Ref],
+    links: &[(usize, usize, Ref)],
+) -> Ref {
+    let root_id = alloc();
+    let document_id = alloc();
+    let ids: Vec<Ref> = tags.elements.iter().map(|_| alloc()).collect();
+    let page_array_ids: Vec<Ref> = page_ids.iter().map(|_| alloc()).collect();
+
+    let mut root = pdf.indirect(root_id).start::<StructTreeRoot>();
+    root.child(document_id);
+    {
+        let mut parent_tree = root.parent_tree();
+        let mut nums = parent_tree.nums();
+        for (page, &array_id) in page_array_ids.iter().enumerate() {
+            nums.insert(page as i32, array_id);
+        }
+        for (i, &(elem, _, _)) in links.iter().enumerate() {
+            nums.insert((page_ids.len() + i) as i32, ids[elem]);
+        }
+    }
+    root.parent_tree_next_key((page_ids.len() + links.len()) as i32);
+    drop(root);
+
+    for (page, &array_id) in page_array_ids.iter().enumerate() {
+        let elems = tags.page_mcids.get(page).map_or(&[][..], |m| &m[..]);
+        pdf.indirect(array_id)
+            .array()
+            .items(elems.iter().map(|&elem| ids[elem]));
+    }
+
+    let mut document = pdf.struct_element(document_id);
+    document.kind(StructRole::Document).parent(root_id);
+    let mut children = document.children();
+    for (elem, &id) in tags.elements.iter().zip(&ids) {
+        if elem.parent.is_none() {
+            children.struct_element(id);
+        }
+    }
+    drop(children);
+    drop(document);
+
+    let annots: HashMap<usize, (usize, Ref)> = links
+        .iter()
+        .map(|&(elem, page, annot)| (elem, (page, annot)))
+        .collect();
+    for (idx, (elem, &id)) in tags.elements.iter().zip(&ids).enumerate() {
+        let mut writer = pdf.struct_element(id);
+        writer
+            .kind(elem.role)
+            .parent(elem.parent.map_or(document_id, |p| ids[p]));
+        if let Some(alt) = &elem.alt {
+            writer.alt(TextStr(alt));
+        }
+        // Content on other pages than the element's own takes a reference
+        // naming its page
+        let own_page = elem.kids.iter().find_map(|kid| match kid {
+            Kid::Content { page, .. } => Some(*page),
+            Kid::Element(_) => None,
+        });
+        if let Some(page) = own_page {
+            writer.page(page_ids[page]);
+        }
+        let mut children = writer.children();
+        for kid in &elem.kids {
+            match *kid {
+                Kid::Element(child) => {
+                    children.struct_element(ids[child]);
+                }
+                Kid::Content { page, mcid } if Some(page) == own_page => {
+                    children.marked_content_id(mcid);
+                }
+                Kid::Content { page, mcid } => {
+                    children
+                        .marked_content_ref()
+                        .page(page_ids[page])
+                        .marked_content_id(mcid);
+                }
+            }
+        }
+        if let Some(&(page, annot)) = annots.get(&idx) {
+            children.object_ref().page(page_ids[page]).object(annot);
+        }
+    }
+    root_id
+}
@@ -0,0 +1,83 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Jobs run in parallel but their results come back in order, with a
+/// failing job not affecting the others.
+#[test]
+fn convert_many_keeps_job_order() {
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let dir = std::env::temp_dir().join("docxside-pdf-batch");
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut jobs: Vec<(PathBuf, PathBuf)> = fixtures
+        .iter()
+        .map(|fixture_dir| {
+            let name = fixture_dir
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            (
+                fixture_dir.join("input.docx"),
+                dir.join(format!("{name}.pdf")),
+            )
+        })
+        .collect();
+    jobs.insert(1, (dir.join("missing.docx"), dir.join("missing.pdf")));
+    for (_, output) in &jobs {
+        let _ = fs::remove_file(output);
+    }
+
+    let results = docxside_pdf::convert_many(&jobs, 3);
+    assert_eq!(results.len(), jobs.len());
+    assert!(results[1].is_err(), "a missing input should fail");
+    for ((input, output), result) in jobs.iter().zip(&results) {
+        if result.is_ok() {
+            let pdf = fs::read(output).unwrap();
+            assert!(pdf.starts_with(b"%PDF-"), "{}: not a PDF", input.display());
+        }
+    }
+    assert!(results.iter().filter(|r| r.is_ok()).count() >= jobs.len() - 1);
+}
+
+/// A second path that isn't a DOCX is the output file, and `--output` names
+/// the directory for the PDFs of several inputs.
+#[cfg(feature = "cli")]
+#[test]
+fn cli_output_paths() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join("docxside-pdf-batch-cli");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let run = |args: &[&std::ffi::OsStr]| {
+        let status = Command::new(env!("CARGO_BIN_EXE_docxside-pdf"))
+            .arg("--no-dates")
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    let case1 = PathBuf::from("tests/fixtures/case1/input.docx");
+    let case7 = PathBuf::from("tests/fixtures/case7/input.docx");
+
+    let single = dir.join("single.out");
+    run(&[case1.as_os_str(), single.as_os_str()]);
+    assert!(fs::read(&single).unwrap().starts_with(b"%PDF-"));
+
+    run(&[
+        "-o".as_ref(),
+        dir.as_os_str(),
+        case1.as_os_str(),
+        case7.as_os_str(),
+    ]);
+    for name in ["input.pdf", "input(2).pdf"] {
+        assert!(
+            fs::read(dir.join(name)).unwrap().starts_with(b"%PDF-"),
+            "{name}"
+        );
+    }
+}
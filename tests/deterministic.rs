@@ -0,0 +1,35 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+
+use docxside_pdf::ConversionOptions;
+
+/// With dates omitted, converting the same document twice gives the same
+/// bytes, whatever order the converter's hash maps happen to iterate in.
+#[test]
+fn repeated_conversions_are_identical() {
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let options = ConversionOptions {
+        omit_dates: true,
+        tagged: true,
+        ..Default::default()
+    };
+
+    for fixture_dir in &fixtures {
+        let name = fixture_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let input = fs::read(fixture_dir.join("input.docx")).unwrap();
+        let Ok(first) = docxside_pdf::convert_docx_bytes_with_options(&input, &options) else {
+            continue;
+        };
+        let second = docxside_pdf::convert_docx_bytes_with_options(&input, &options).unwrap();
+        assert!(
+            first == second,
+            "{name}: output differs between conversions"
+        );
+    }
+}
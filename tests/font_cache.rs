@@ -0,0 +1,42 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn convert(cache: &Path, output: &Path) {
+    let _ = fs::remove_file(output);
+    let status = Command::new(env!("CARGO_BIN_EXE_docxside-pdf"))
+        .env("DOCXSIDE_FONT_CACHE", cache)
+        .arg("--no-dates")
+        .arg("tests/fixtures/case7/input.docx")
+        .arg(output)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+/// The first run writes the font index to the cache, later runs read it, and
+/// a cache in an unknown format is scanned again and replaced.
+#[test]
+fn font_index_is_cached_between_runs() {
+    let dir = std::env::temp_dir().join("docxside-pdf-font-cache");
+    fs::create_dir_all(&dir).unwrap();
+    let cache = dir.join("font-index");
+    let _ = fs::remove_file(&cache);
+
+    convert(&cache, &dir.join("scanned.pdf"));
+    let index = fs::read_to_string(&cache).expect("no font cache written");
+    assert!(index.starts_with("docxside-pdf font index "));
+
+    convert(&cache, &dir.join("cached.pdf"));
+    assert_eq!(fs::read_to_string(&cache).unwrap(), index);
+
+    fs::write(&cache, "not a font index\n").unwrap();
+    convert(&cache, &dir.join("rescanned.pdf"));
+    assert_eq!(fs::read_to_string(&cache).unwrap(), index);
+
+    let scanned = fs::read(dir.join("scanned.pdf")).unwrap();
+    assert!(scanned == fs::read(dir.join("cached.pdf")).unwrap());
+    assert!(scanned == fs::read(dir.join("rescanned.pdf")).unwrap());
+}
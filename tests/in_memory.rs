@@ -0,0 +1,161 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+
+use docxside_pdf::model::Block;
+use docxside_pdf::{ConversionOptions, Error};
+
+fn page_count(pdf: &[u8]) -> usize {
+    pdf.windows(b"/Type /Page\n".len())
+        .filter(|w| w == b"/Type /Page\n")
+        .count()
+}
+
+/// Converting the bytes of a DOCX gives the same pages as converting the file.
+#[test]
+fn bytes_convert_like_files() {
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let dir = std::env::temp_dir().join("docxside-pdf-in-memory");
+    fs::create_dir_all(&dir).unwrap();
+
+    for fixture_dir in &fixtures {
+        let name = fixture_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let input = fixture_dir.join("input.docx");
+        let from_file = dir.join(format!("{name}.pdf"));
+        let _ = fs::remove_file(&from_file);
+        if docxside_pdf::convert_docx_to_pdf(&input, &from_file).is_err() {
+            continue;
+        }
+        let from_file = fs::read(&from_file).unwrap();
+
+        let from_bytes = docxside_pdf::convert_docx_bytes(&fs::read(&input).unwrap())
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(from_bytes.starts_with(b"%PDF-"), "{name}: not a PDF");
+        assert_eq!(
+            page_count(&from_bytes),
+            page_count(&from_file),
+            "{name}: page count"
+        );
+    }
+}
+
+#[test]
+fn bytes_that_are_not_a_zip_are_rejected() {
+    let result = docxside_pdf::convert_docx_bytes(b"not a docx");
+    assert!(matches!(result, Err(Error::InvalidDocx(_))));
+}
+
+#[test]
+fn malformed_xml_names_the_part() {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    zip.start_file(
+        "word/document.xml",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    let xml = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:body></w:document>"#;
+    std::io::Write::write_all(&mut zip, xml.as_bytes()).unwrap();
+    let input = zip.finish().unwrap().into_inner();
+
+    let error = docxside_pdf::convert_docx_bytes(&input).unwrap_err();
+    assert_eq!(error.part(), Some("word/document.xml"));
+    assert!(std::error::Error::source(&error).is_some());
+    let message = error.to_string();
+    assert!(
+        message.contains("word/document.xml") && message.contains("2:"),
+        "{message}"
+    );
+    assert!(!message.contains('\n'), "{message}");
+}
+
+#[test]
+fn malformed_xml_in_other_parts_names_the_part() {
+    let document = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body/></w:document>"#;
+    for part in [
+        "word/styles.xml",
+        "word/numbering.xml",
+        "word/_rels/document.xml.rels",
+    ] {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, xml) in [("word/document.xml", document), (part, "<broken>")] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, xml.as_bytes()).unwrap();
+        }
+        let input = zip.finish().unwrap().into_inner();
+
+        let error = docxside_pdf::convert_docx_bytes(&input).unwrap_err();
+        assert!(matches!(error, Error::Xml { .. }), "{error}");
+        assert_eq!(error.part(), Some(part));
+    }
+}
+
+/// A part that is there but cannot be read is an error naming it, rather than
+/// being left out as if the document had none.
+#[test]
+fn unreadable_parts_are_named() {
+    let document = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body/></w:document>"#;
+    for part in [
+        "word/styles.xml",
+        "word/numbering.xml",
+        "word/settings.xml",
+        "word/fontTable.xml",
+        "docProps/core.xml",
+    ] {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data) in [
+            ("word/document.xml", document.as_bytes()),
+            (part, b"\xff\xfe not UTF-8"),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, data).unwrap();
+        }
+        let input = zip.finish().unwrap().into_inner();
+
+        let error = docxside_pdf::convert_docx_bytes(&input).unwrap_err();
+        assert!(matches!(error, Error::Part { .. }), "{part}: {error}");
+        assert_eq!(error.part(), Some(part));
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Pictures and fonts embedded in the DOCX are read from their parts.
+#[test]
+fn media_and_font_parts_are_embedded() {
+    let convert = |fixture: &str| {
+        let input = fs::read(format!("tests/fixtures/{fixture}/input.docx")).unwrap();
+        docxside_pdf::convert_docx_bytes(&input).unwrap()
+    };
+    assert!(contains(&convert("sample100kB"), b"/Subtype /Image"));
+    assert!(contains(&convert("case8"), b"+PressStart2P"));
+}
+
+/// Rendering moves picture bytes into the PDF, but only out of its own copy
+/// of the document: the caller's can be rendered again.
+#[test]
+fn rendering_leaves_the_pictures_of_the_document() {
+    let doc = docxside_pdf::parse_docx("tests/fixtures/sample100kB/input.docx".as_ref()).unwrap();
+    let options = ConversionOptions {
+        omit_dates: true,
+        ..Default::default()
+    };
+    let first = docxside_pdf::render_pdf_with_options(&doc, &options).unwrap();
+    assert!(contains(&first, b"/Subtype /Image"));
+    let has_picture = doc.blocks.iter().any(|block| match block {
+        Block::Paragraph(p) => p.image.as_ref().is_some_and(|img| !img.data.is_empty()),
+        _ => false,
+    });
+    assert!(has_picture, "the picture bytes are gone");
+    let second = docxside_pdf::render_pdf_with_options(&doc, &options).unwrap();
+    assert!(first == second, "rendering again gave a different PDF");
+}
@@ -0,0 +1,788 @@
+use std::fs;
+use std::io::{Cursor, Write};
+
+use docxside_pdf::model::Block;
+use docxside_pdf::{ConversionOptions, Document};
+
+const WML_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Letter pages with one-inch margins.
+const LETTER: &str = r#"<w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/>"#;
+
+/// A DOCX with `body` as the content of its body, ending in a section with
+/// `sect_pr`, and `parts` (name, XML) added beside the document.
+fn docx(body: &str, sect_pr: &str, parts: &[(&str, &[u8])]) -> Vec<u8> {
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="{WML_NS}"><w:body>{body}<w:sectPr>{sect_pr}</w:sectPr></w:body></w:document>"#
+    );
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in [("word/document.xml", document.as_bytes())]
+        .into_iter()
+        .chain(parts.iter().copied())
+    {
+        zip.start_file(name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// The document model of `docx`, parsed from a file named after `name`.
+fn parse(name: &str, docx: &[u8]) -> Document {
+    let dir = std::env::temp_dir().join("docxside-pdf-layout");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{name}.docx"));
+    fs::write(&path, docx).unwrap();
+    docxside_pdf::parse_docx(&path).unwrap()
+}
+
+fn convert(docx: &[u8]) -> Vec<u8> {
+    let options = ConversionOptions {
+        omit_dates: true,
+        ..Default::default()
+    };
+    docxside_pdf::convert_docx_bytes_with_options(docx, &options).unwrap()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The dictionary of object `id`, up to its stream or the end of the object.
+fn object(pdf: &[u8], id: &str) -> String {
+    let start = find(pdf, format!("\n{id} 0 obj\n").as_bytes()).unwrap();
+    let rest = &pdf[start..];
+    let end = find(rest, b"stream\n")
+        .into_iter()
+        .chain(find(rest, b"endobj"))
+        .min()
+        .unwrap();
+    String::from_utf8_lossy(&rest[..end]).into_owned()
+}
+
+/// The first reference following `key` in `dict`.
+fn reference<'a>(dict: &'a str, key: &str) -> &'a str {
+    let at = dict.find(key).unwrap() + key.len();
+    dict[at..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .trim_start_matches('[')
+}
+
+/// The page dictionaries and decompressed content streams, in page order.
+fn pages(pdf: &[u8]) -> Vec<(String, String)> {
+    let catalog = String::from_utf8_lossy(pdf);
+    let kids_at = catalog.find("/Kids [").unwrap() + "/Kids [".len();
+    let kids = &catalog[kids_at..kids_at + catalog[kids_at..].find(']').unwrap()];
+    kids.split(" 0 R")
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            let page = object(pdf, id);
+            let contents = reference(&page, "/Contents");
+            let start = find(pdf, format!("\n{contents} 0 obj\n").as_bytes()).unwrap();
+            let data = &pdf[start..];
+            let data = &data[find(data, b"stream\n").unwrap() + b"stream\n".len()..];
+            let data = &data[..find(data, b"\nendstream").unwrap()];
+            let content = miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap();
+            (page, String::from_utf8_lossy(&content).into_owned())
+        })
+        .collect()
+}
+
+/// The image XObjects of a PDF: (dictionary, decompressed samples).
+fn images(pdf: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let text = String::from_utf8_lossy(pdf);
+    text.match_indices("/Subtype /Image")
+        .map(|(at, _)| {
+            let start = text[..at].rfind(" obj\n").unwrap();
+            let id = text[..start]
+                .rsplit('\n')
+                .next()
+                .unwrap()
+                .trim_end_matches(" 0");
+            let dict = object(pdf, id);
+            let data = &pdf[find(pdf, dict.as_bytes()).unwrap() + dict.len()..];
+            let data = &data[b"stream\n".len()..];
+            let data = &data[..find(data, b"\nendstream").unwrap()];
+            (
+                dict,
+                miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// The fonts of a page's resources: (resource name, dictionary).
+fn page_fonts(pdf: &[u8], page: &str) -> Vec<(String, String)> {
+    let at = page.find("/Font <<").unwrap() + "/Font <<".len();
+    let fonts = &page[at..at + page[at..].find(">>").unwrap()];
+    fonts
+        .lines()
+        .filter_map(|line| {
+            let (name, id) = line.trim().split_once(' ')?;
+            let id = id.strip_suffix(" 0 R")?;
+            Some((name.trim_start_matches('/').to_string(), object(pdf, id)))
+        })
+        .collect()
+}
+
+/// A line of text shown by a content stream: where it starts, and its text.
+#[derive(Debug)]
+struct Shown {
+    x: f32,
+    y: f32,
+    text: String,
+}
+
+/// Text shown with `Td` and `TJ`, in drawing order. Only the literal strings
+/// of simple fonts are read.
+fn shown_text(content: &str) -> Vec<Shown> {
+    let mut shown = Vec::new();
+    let (mut x, mut y) = (0.0, 0.0);
+    for line in content.lines() {
+        let ops: Vec<&str> = line.split_whitespace().collect();
+        if let ["BT"] = ops[..] {
+            (x, y) = (0.0, 0.0);
+        } else if let [dx, dy, "Td"] = ops[..] {
+            x += dx.parse::<f32>().unwrap();
+            y += dy.parse::<f32>().unwrap();
+        } else if line.ends_with("TJ") || line.ends_with("Tj") {
+            let mut text = String::new();
+            let mut chars = line.chars();
+            while let Some(c) = chars.next() {
+                if c != '(' {
+                    continue;
+                }
+                while let Some(c) = chars.next() {
+                    match c {
+                        ')' => break,
+                        '\\' => text.extend(chars.next()),
+                        c => text.push(c),
+                    }
+                }
+            }
+            shown.push(Shown { x, y, text });
+        }
+    }
+    shown
+}
+
+/// About `count` words of filler text.
+fn filler(count: usize) -> String {
+    (0..count)
+        .map(|i| format!("word{i}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Text after a page break inside a paragraph continues on further pages
+/// rather than running off the bottom of the page.
+#[test]
+fn text_after_a_page_break_is_paginated() {
+    let body = format!(
+        r#"<w:p><w:r><w:t>Before the break</w:t></w:r><w:r><w:br w:type="page"/><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+        filler(1500)
+    );
+    let pages = pages(&convert(&docx(&body, LETTER, &[])));
+    assert!(pages.len() >= 3, "only {} pages", pages.len());
+    for (i, (_, content)) in pages.iter().enumerate() {
+        for line in shown_text(content) {
+            assert!(
+                line.y >= 71.0,
+                "page {}: text at y {} below the margin",
+                i + 1,
+                line.y
+            );
+            assert!(
+                line.x >= 71.0,
+                "page {}: text at x {} left of the margin",
+                i + 1,
+                line.x
+            );
+        }
+    }
+    let last = shown_text(&pages.last().unwrap().1);
+    assert!(last.iter().any(|line| line.text.contains("word1499")));
+}
+
+/// `w:sym` characters are shown in their symbol font by their symbol code,
+/// which viewers look up in the (3,0) cmap of the font.
+#[test]
+fn symbol_characters_use_the_symbol_font() {
+    let pdf = convert(&fs::read("tests/fixtures/case12/input.docx").unwrap());
+    let (page, content) = &pages(&pdf)[0];
+    let (name, font) = page_fonts(&pdf, page)
+        .into_iter()
+        .find(|(_, font)| font.contains("+Symbol\n"))
+        .expect("no Symbol font");
+    assert!(!font.contains("/Encoding"), "{font}");
+    let descriptor = object(&pdf, reference(&font, "/FontDescriptor"));
+    assert!(descriptor.contains("/Flags 4\n"), "{descriptor}");
+
+    // Widths start at code 32; the radical sign is 0xD6
+    let widths_at = font.find("/Widths [").unwrap() + "/Widths [".len();
+    let widths: Vec<f32> = font[widths_at..font.rfind(']').unwrap()]
+        .split_whitespace()
+        .map(|w| w.parse().unwrap())
+        .collect();
+    assert!(widths[0xD6 - 32] > 0.0);
+
+    for code in ["<D6>", "<AE>"] {
+        let shown_at = content
+            .find(code)
+            .unwrap_or_else(|| panic!("{code} not shown"));
+        let font_at = content[..shown_at].rfind(" Tf").unwrap();
+        let font_name = content[..font_at].rsplit('/').next().unwrap();
+        assert!(
+            font_name.starts_with(&format!("{name} ")),
+            "{code} shown in {font_name}"
+        );
+    }
+}
+
+/// PAGE and NUMPAGES fields in table cells show the page their row is drawn
+/// on and the page count, not the results cached in the document.
+#[test]
+fn fields_in_table_cells_are_substituted() {
+    let cell = |text: &str| format!(r#"<w:tc><w:p><w:r><w:t>{text}</w:t></w:r></w:p></w:tc>"#);
+    let field_row = r#"<w:tr><w:tc><w:p><w:r><w:t xml:space="preserve">Page </w:t></w:r><w:fldSimple w:instr=" PAGE "><w:r><w:t>97</w:t></w:r></w:fldSimple><w:r><w:t xml:space="preserve"> of </w:t></w:r><w:fldSimple w:instr=" NUMPAGES "><w:r><w:t>98</w:t></w:r></w:fldSimple></w:p></w:tc></w:tr>"#;
+    let rows: String = std::iter::once(field_row.to_string())
+        .chain((0..60).map(|i| format!("<w:tr>{}</w:tr>", cell(&format!("row{i}")))))
+        .chain(std::iter::once(field_row.to_string()))
+        .collect();
+    let body =
+        format!(r#"<w:tbl><w:tblGrid><w:gridCol w:w="9360"/></w:tblGrid>{rows}</w:tbl><w:p/>"#);
+    let pages = pages(&convert(&docx(&body, LETTER, &[])));
+    assert_eq!(pages.len(), 2);
+    for (i, (_, content)) in pages.iter().enumerate() {
+        let lines: Vec<String> = shown_text(content).into_iter().map(|l| l.text).collect();
+        let expected = format!("Page {} of 2", i + 1);
+        assert!(lines.contains(&expected), "page {}: {lines:?}", i + 1);
+        assert!(!lines.iter().any(|l| l.contains("97") || l.contains("98")));
+    }
+}
+
+/// A landscape section written with portrait dimensions gets a landscape page,
+/// and its text fills the landscape width.
+#[test]
+fn landscape_sections_are_laid_out_across_the_wide_page() {
+    let body = format!(r#"<w:p><w:r><w:t>{}</w:t></w:r></w:p>"#, filler(200));
+    let portrait = pages(&convert(&docx(&body, LETTER, &[])));
+    let landscape_sect = LETTER.replace(r#"w:h="15840""#, r#"w:h="15840" w:orient="landscape""#);
+    let landscape = pages(&convert(&docx(&body, &landscape_sect, &[])));
+    assert_eq!((portrait.len(), landscape.len()), (1, 1));
+    let (page, content) = &landscape[0];
+    assert!(page.contains("/MediaBox [0 0 792 612]"), "{page}");
+
+    let portrait_lines = shown_text(&portrait[0].1);
+    let lines = shown_text(content);
+    // 648pt of text width instead of 468pt
+    assert!(
+        (lines.len() as f32) < portrait_lines.len() as f32 * 0.8,
+        "{} landscape lines, {} portrait",
+        lines.len(),
+        portrait_lines.len()
+    );
+    for line in &lines {
+        assert!(
+            line.y < 612.0 - 71.0,
+            "text at y {} above the margin",
+            line.y
+        );
+    }
+}
+
+/// A table as wide as the page that starts in the second of two columns stays
+/// in that column rather than covering the first.
+#[test]
+fn wide_tables_in_the_second_column_stay_in_it() {
+    let sect = format!(r#"{LETTER}<w:cols w:num="2" w:space="720"/>"#);
+    let cell = |text: &str| {
+        format!(
+            r#"<w:tc><w:tcPr><w:tcW w:w="4680" w:type="dxa"/></w:tcPr><w:p><w:r><w:t>{text}</w:t></w:r></w:p></w:tc>"#
+        )
+    };
+    // More one-line paragraphs than the first column holds
+    let paragraphs: String = (0..40)
+        .map(|i| format!("<w:p><w:r><w:t>word{i}</w:t></w:r></w:p>"))
+        .collect();
+    let body = format!(
+        r#"{paragraphs}<w:tbl><w:tblGrid><w:gridCol w:w="4680"/><w:gridCol w:w="4680"/></w:tblGrid><w:tr>{}{}</w:tr></w:tbl><w:p/>"#,
+        cell("left cell"),
+        cell("right cell")
+    );
+    let pages = pages(&convert(&docx(&body, &sect, &[])));
+    assert_eq!(pages.len(), 1);
+    let lines = shown_text(&pages[0].1);
+    let last_paragraph = lines.iter().find(|l| l.text == "word39").unwrap();
+    assert!(last_paragraph.x >= 324.0);
+    // The second column runs from 324pt to the right margin at 540pt
+    for text in ["left cell", "right cell"] {
+        let line = lines.iter().find(|l| l.text == text).unwrap();
+        assert!(line.x > 324.0 && line.x < 540.0, "{text} at x {}", line.x);
+        assert!(line.y < last_paragraph.y, "{text} above the column's text");
+    }
+}
+
+/// A `w:num` with a `w:startOverride` restarts the numbering of the list it
+/// shares with an earlier `w:num`; one without continues it.
+#[test]
+fn numbered_lists_restart_or_continue_per_num() {
+    let numbering = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="{WML_NS}"><w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:lvl></w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num><w:num w:numId="2"><w:abstractNumId w:val="0"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="1"/></w:lvlOverride></w:num><w:num w:numId="3"><w:abstractNumId w:val="0"/></w:num></w:numbering>"#
+    );
+    let item = |num_id: u32, text: &str| {
+        format!(
+            r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="{num_id}"/></w:numPr></w:pPr><w:r><w:t>{text}</w:t></w:r></w:p>"#
+        )
+    };
+    let body = [
+        item(1, "first a"),
+        item(1, "first b"),
+        "<w:p><w:r><w:t>Between</w:t></w:r></w:p>".to_string(),
+        item(2, "second a"),
+        item(2, "second b"),
+        item(3, "third a"),
+    ]
+    .concat();
+    let pages = pages(&convert(&docx(
+        &body,
+        LETTER,
+        &[("word/numbering.xml", numbering.as_bytes())],
+    )));
+    let lines = shown_text(&pages[0].1);
+    let label_of = |text: &str| {
+        let line = lines.iter().find(|l| l.text == text).unwrap();
+        lines
+            .iter()
+            .find(|l| l.y == line.y && l.x < line.x)
+            .map(|l| l.text.as_str())
+            .unwrap()
+    };
+    assert_eq!(label_of("first a"), "1.");
+    assert_eq!(label_of("first b"), "2.");
+    assert_eq!(label_of("second a"), "1.");
+    assert_eq!(label_of("second b"), "2.");
+    assert_eq!(label_of("third a"), "3.");
+}
+
+/// A list label followed by a space instead of a tab starts the first line at
+/// the hanging indent, and the following lines at the left indent.
+#[test]
+fn inline_list_labels_start_at_the_hanging_indent() {
+    let numbering = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="{WML_NS}"><w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/><w:suff w:val="space"/><w:pPr><w:ind w:left="1440" w:hanging="720"/></w:pPr></w:lvl></w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num></w:numbering>"#
+    );
+    let body = format!(
+        r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+        filler(40)
+    );
+    let pages = pages(&convert(&docx(
+        &body,
+        LETTER,
+        &[("word/numbering.xml", numbering.as_bytes())],
+    )));
+    let lines = shown_text(&pages[0].1);
+    // Left indent 72pt and hanging indent 36pt inside the 72pt page margin
+    let first = &lines[0];
+    assert!(first.text.starts_with("1. word0"), "{lines:?}");
+    assert_eq!(first.x, 108.0);
+    let rest: Vec<_> = lines.iter().filter(|l| l.y < first.y).collect();
+    assert!(!rest.is_empty());
+    for line in rest {
+        assert_eq!(line.x, 144.0, "{line:?}");
+    }
+}
+
+/// A cantSplit row that doesn't fit at the bottom of a page moves to the next
+/// one whole, taking a keepNext paragraph before the table with it.
+#[test]
+fn cant_split_rows_move_to_the_next_page_whole() {
+    let body = |cant_split: bool| {
+        let paragraphs: String = (0..27)
+            .map(|i| format!("<w:p><w:r><w:t>word{i}</w:t></w:r></w:p>"))
+            .collect();
+        let tr_pr = if cant_split {
+            "<w:trPr><w:cantSplit/></w:trPr>"
+        } else {
+            ""
+        };
+        let cell_lines: String = (0..5)
+            .map(|i| format!("<w:t>line{i}</w:t><w:br/>"))
+            .collect();
+        format!(
+            r#"{paragraphs}<w:p><w:pPr><w:keepNext/></w:pPr><w:r><w:t>Heading</w:t></w:r></w:p><w:tbl><w:tblGrid><w:gridCol w:w="9360"/></w:tblGrid><w:tr>{tr_pr}<w:tc><w:p><w:r>{cell_lines}</w:r></w:p></w:tc></w:tr></w:tbl><w:p/>"#
+        )
+    };
+    let page_of = |pages: &[(String, String)], text: &str| {
+        pages
+            .iter()
+            .position(|(_, content)| shown_text(content).iter().any(|l| l.text == text))
+            .unwrap_or_else(|| panic!("{text} not shown"))
+    };
+
+    let kept = pages(&convert(&docx(&body(true), LETTER, &[])));
+    assert_eq!(page_of(&kept, "word26"), 0);
+    assert_eq!(page_of(&kept, "Heading"), 1);
+    for i in 0..5 {
+        assert_eq!(page_of(&kept, &format!("line{i}")), 1);
+    }
+
+    // A row that may split only needs its first line to follow the heading
+    let split = pages(&convert(&docx(&body(false), LETTER, &[])));
+    assert_eq!(page_of(&split, "Heading"), 0);
+    assert_eq!(page_of(&split, "line0"), 1);
+}
+
+/// Bold from a table style's first row applies to its runs, but a run that
+/// turns bold off directly stays regular.
+#[test]
+fn direct_formatting_overrides_table_style_bold() {
+    let styles = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{WML_NS}"><w:style w:type="table" w:styleId="Bold"><w:tblStylePr w:type="firstRow"><w:rPr><w:b/><w:i/></w:rPr></w:tblStylePr></w:style></w:styles>"#
+    );
+    let body = r#"<w:tbl><w:tblPr><w:tblStyle w:val="Bold"/><w:tblLook w:firstRow="1"/></w:tblPr><w:tblGrid><w:gridCol w:w="9360"/></w:tblGrid><w:tr><w:tc><w:p><w:r><w:t>Styled</w:t></w:r><w:r><w:rPr><w:b w:val="0"/><w:i w:val="false"/></w:rPr><w:t>Direct</w:t></w:r></w:p></w:tc></w:tr></w:tbl><w:p/>"#;
+    let doc = parse(
+        "table-style-bold",
+        &docx(body, LETTER, &[("word/styles.xml", styles.as_bytes())]),
+    );
+    let Some(Block::Table(table)) = doc.blocks.first() else {
+        panic!("no table");
+    };
+    let runs = &table.rows[0].cells[0].paragraphs[0].runs;
+    let formats: Vec<_> = runs
+        .iter()
+        .map(|r| (r.text.as_str(), r.bold, r.italic))
+        .collect();
+    assert_eq!(formats, [("Styled", true, true), ("Direct", false, false)]);
+}
+
+/// A table style's text color wins over the document default color, which
+/// still applies outside the table.
+#[test]
+fn table_style_color_overrides_the_default_color() {
+    let styles = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{WML_NS}"><w:docDefaults><w:rPrDefault><w:rPr><w:color w:val="0000FF"/></w:rPr></w:rPrDefault></w:docDefaults><w:style w:type="table" w:styleId="Red"><w:rPr><w:color w:val="FF0000"/></w:rPr></w:style></w:styles>"#
+    );
+    let body = r#"<w:tbl><w:tblPr><w:tblStyle w:val="Red"/></w:tblPr><w:tblGrid><w:gridCol w:w="9360"/></w:tblGrid><w:tr><w:tc><w:p><w:r><w:t>Styled</w:t></w:r><w:r><w:rPr><w:color w:val="00FF00"/></w:rPr><w:t>Direct</w:t></w:r></w:p></w:tc></w:tr></w:tbl><w:p><w:r><w:t>Body</w:t></w:r></w:p>"#;
+    let doc = parse(
+        "table-style-color",
+        &docx(body, LETTER, &[("word/styles.xml", styles.as_bytes())]),
+    );
+    let [Block::Table(table), Block::Paragraph(para), ..] = &doc.blocks[..] else {
+        panic!("no table and paragraph");
+    };
+    let colors: Vec<_> = table.rows[0].cells[0].paragraphs[0]
+        .runs
+        .iter()
+        .chain(&para.runs)
+        .map(|r| (r.text.as_str(), r.color))
+        .collect();
+    assert_eq!(
+        colors,
+        [
+            ("Styled", Some([255, 0, 0])),
+            ("Direct", Some([0, 255, 0])),
+            ("Body", Some([0, 0, 255]))
+        ]
+    );
+}
+
+/// The PNG picture cached for a chart is decoded and embedded, with its
+/// transparency as a soft mask.
+#[test]
+fn charts_draw_their_cached_png() {
+    let mut png = Vec::new();
+    image::RgbaImage::from_fn(3, 2, |x, _| {
+        image::Rgba([255, 0, 0, if x == 0 { 0 } else { 255 }])
+    })
+    .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+    .unwrap();
+    let body = r#"<w:p><w:r><w:drawing><wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><wp:extent cx="914400" cy="609600"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/chart"><c:chart xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" r:id="rIdChart"/></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#;
+    let rels = |id: &str, kind: &str, target: &str| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{target}"/></Relationships>"#
+        )
+    };
+    let document_rels = rels("rIdChart", "chart", "charts/chart1.xml");
+    let chart_rels = rels("rIdImage", "image", "../media/image1.png");
+    let pdf = convert(&docx(
+        body,
+        LETTER,
+        &[
+            ("word/_rels/document.xml.rels", document_rels.as_bytes()),
+            ("word/charts/chart1.xml", b"<c:chartSpace/>"),
+            ("word/charts/_rels/chart1.xml.rels", chart_rels.as_bytes()),
+            ("word/media/image1.png", &png),
+        ],
+    ));
+
+    let images = images(&pdf);
+    let (image, samples) = images
+        .iter()
+        .find(|(dict, _)| dict.contains("/SMask"))
+        .expect("no image with a soft mask");
+    assert!(image.contains("/Filter /FlateDecode"), "{image}");
+    assert!(image.contains("/ColorSpace /DeviceRGB"), "{image}");
+    assert_eq!(samples, &[255, 0, 0].repeat(6));
+    let mask = object(&pdf, reference(image, "/SMask"));
+    let (_, alpha) = images.iter().find(|(dict, _)| *dict == mask).unwrap();
+    assert_eq!(alpha, &[0, 255, 255, 0, 255, 255]);
+}
+
+/// Strikethrough rules follow the size and baseline shift of their run.
+#[test]
+fn strikethrough_follows_size_and_baseline_shift() {
+    let run = |rpr: &str, text: &str| {
+        format!(
+            r#"<w:r><w:rPr><w:strike/>{rpr}</w:rPr><w:t xml:space="preserve">{text}</w:t></w:r>"#
+        )
+    };
+    let body = format!(
+        "<w:p>{}{}{}</w:p>",
+        run(r#"<w:sz w:val="40"/>"#, "Big "),
+        run(
+            r#"<w:vertAlign w:val="superscript"/><w:sz w:val="40"/>"#,
+            "Up "
+        ),
+        run(r#"<w:sz w:val="20"/>"#, "Small"),
+    );
+    let pages = pages(&convert(&docx(&body, LETTER, &[])));
+    let content = &pages[0].1;
+    let shown = shown_text(content);
+    let rules: Vec<[f32; 4]> = content
+        .lines()
+        .filter_map(|line| line.strip_suffix(" re"))
+        .map(|rect| {
+            let v: Vec<f32> = rect.split(' ').map(|n| n.parse().unwrap()).collect();
+            [v[0], v[1], v[2], v[3]]
+        })
+        .collect();
+    assert_eq!(rules.len(), 3, "{content}");
+
+    let close = |a: f32, b: f32| (a - b).abs() < 0.01;
+    let baseline = shown[0].y;
+    // Superscripts are set at 58% size, raised by 35% of the run's size. The
+    // Helvetica substitute strikes 0.35em above the baseline, 0.05em thick
+    let expected = [("Big", 20.0, 0.0), ("Up", 11.6, 7.0), ("Small", 10.0, 0.0)];
+    for ((text, size, raise), (line, [x, y, _, h])) in
+        expected.into_iter().zip(shown.iter().zip(&rules))
+    {
+        assert_eq!(line.text.trim(), text);
+        assert!(close(line.y, baseline + raise), "{text} at y {}", line.y);
+        assert!(close(*x, line.x), "{text} struck from x {x}");
+        let thickness = f32::max(size * 0.05, 0.5);
+        assert!(close(*h, thickness), "{text} struck {h} thick");
+        assert!(close(y + h, line.y + size * 0.35), "{text} struck at y {y}");
+    }
+}
+
+/// Pictures with the same bytes share one image XObject; others get their own.
+#[test]
+fn identical_pictures_share_one_image() {
+    let encode = |red: u8| {
+        let mut png = Vec::new();
+        image::RgbImage::from_pixel(2, 2, image::Rgb([red, 0, 0]))
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png
+    };
+    let (logo, other) = (encode(255), encode(0));
+    let picture = |id: &str| {
+        format!(
+            r#"<w:p><w:r><w:drawing><wp:inline xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><wp:extent cx="914400" cy="914400"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData><a:blip xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" r:embed="{id}"/></a:graphicData></a:graphic></wp:inline></w:drawing></w:r></w:p>"#
+        )
+    };
+    let body = format!("{}{}{}", picture("rId1"), picture("rId2"), picture("rId3"));
+    let relationship = |id: &str, target: &str| {
+        format!(
+            r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="{target}"/>"#
+        )
+    };
+    let rels = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}{}{}</Relationships>"#,
+        relationship("rId1", "media/image1.png"),
+        relationship("rId2", "media/image2.png"),
+        relationship("rId3", "media/image3.png"),
+    );
+    let pdf = convert(&docx(
+        &body,
+        LETTER,
+        &[
+            ("word/_rels/document.xml.rels", rels.as_bytes()),
+            ("word/media/image1.png", &logo),
+            ("word/media/image2.png", &logo),
+            ("word/media/image3.png", &other),
+        ],
+    ));
+
+    let images = images(&pdf);
+    assert_eq!(images.len(), 2);
+    let mut samples: Vec<&[u8]> = images.iter().map(|(_, data)| data.as_slice()).collect();
+    samples.sort();
+    assert_eq!(samples, [[0, 0, 0].repeat(4), [255, 0, 0].repeat(4)]);
+}
+
+/// In tagged output a text box beside a list item is a Div outside the list,
+/// and link annotations belong to Link elements of the structure tree.
+#[test]
+fn tagged_text_boxes_and_links_are_placed_in_the_structure() {
+    let numbering = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="{WML_NS}"><w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:start w:val="1"/><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:lvl></w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num></w:numbering>"#
+    );
+    let rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rIdLink" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/></Relationships>"#;
+    let text_box = r#"<w:r><w:drawing><wp:anchor xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><wp:positionH relativeFrom="page"><wp:posOffset>4572000</wp:posOffset></wp:positionH><wp:positionV relativeFrom="page"><wp:posOffset>914400</wp:posOffset></wp:positionV><wp:extent cx="1828800" cy="457200"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData><w:txbxContent><w:p><w:r><w:t>Boxed</w:t></w:r></w:p></w:txbxContent></a:graphicData></a:graphic></wp:anchor></w:drawing></w:r>"#;
+    let body = format!(
+        r#"<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr>{text_box}<w:r><w:t xml:space="preserve">See </w:t></w:r><w:hyperlink xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" r:id="rIdLink"><w:r><w:t>the site</w:t></w:r></w:hyperlink></w:p>"#
+    );
+    let options = ConversionOptions {
+        omit_dates: true,
+        tagged: true,
+        ..Default::default()
+    };
+    let pdf = docxside_pdf::convert_docx_bytes_with_options(
+        &docx(
+            &body,
+            LETTER,
+            &[
+                ("word/numbering.xml", numbering.as_bytes()),
+                ("word/_rels/document.xml.rels", rels.as_bytes()),
+            ],
+        ),
+        &options,
+    )
+    .unwrap();
+    let text = String::from_utf8_lossy(&pdf);
+    // The dictionary of the object holding `needle`, and its number
+    let object_with = |needle: &str| {
+        let at = text.find(needle).unwrap();
+        let start = text[..at].rfind(" 0 obj\n").unwrap();
+        let id = text[..start].rsplit('\n').next().unwrap().to_string();
+        (object(&pdf, &id), id)
+    };
+
+    let (div, _) = object_with("/S /Div");
+    let parent = object(&pdf, reference(&div, "/P"));
+    assert!(
+        !parent.contains("/S /LI") && !parent.contains("/S /L "),
+        "{parent}"
+    );
+
+    let (annot, annot_id) = object_with("/Subtype /Link");
+    assert!(annot.contains("/StructParent "), "{annot}");
+    let (link, _) = object_with("/S /Link");
+    assert!(link.contains("/Type /OBJR"), "{link}");
+    assert_eq!(reference(&link, "/Obj"), annot_id);
+}
+
+/// A section numbered from 0 labels its first page "0" without a /St below 1.
+#[test]
+fn page_labels_from_zero_start_at_one() {
+    let body = r#"<w:p><w:r><w:t>Cover</w:t></w:r></w:p><w:p><w:r><w:br w:type="page"/><w:t>Next</w:t></w:r></w:p>"#;
+    let sect_pr = format!(r#"{LETTER}<w:pgNumType w:start="0"/>"#);
+    let pdf = convert(&docx(body, &sect_pr, &[]));
+    let text = String::from_utf8_lossy(&pdf);
+    let labels: Vec<String> = text
+        .match_indices("/Type /PageLabel")
+        .map(|(at, _)| {
+            let start = text[..at].rfind(" 0 obj\n").unwrap();
+            let id = text[..start].rsplit('\n').next().unwrap();
+            object(&pdf, id)
+        })
+        .collect();
+    assert_eq!(labels.len(), 2, "{labels:?}");
+    assert!(
+        labels[0].contains("/P (0)") && !labels[0].contains("/St"),
+        "{}",
+        labels[0]
+    );
+    assert!(labels[1].contains("/St 1"), "{}", labels[1]);
+}
+
+/// Tab stops at a position that is not a number are left out, both in a
+/// style and in the paragraph that layers its own stops over the style's.
+#[test]
+fn tab_stops_at_nan_positions_are_ignored() {
+    let styles = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="{WML_NS}"><w:style w:type="paragraph" w:styleId="Tabbed"><w:pPr><w:tabs><w:tab w:val="left" w:pos="NaN"/><w:tab w:val="left" w:pos="2880"/></w:tabs></w:pPr></w:style></w:styles>"#
+    );
+    let body = r#"<w:p><w:pPr><w:pStyle w:val="Tabbed"/><w:tabs><w:tab w:val="right" w:pos="NaN"/><w:tab w:val="right" w:pos="1440"/></w:tabs></w:pPr><w:r><w:t>A</w:t><w:tab/><w:t>B</w:t></w:r></w:p>"#;
+    let input = docx(body, LETTER, &[("word/styles.xml", styles.as_bytes())]);
+    let doc = parse("nan-tab-stops", &input);
+    let Some(Block::Paragraph(para)) = doc.blocks.first() else {
+        panic!("no paragraph");
+    };
+    let positions: Vec<f32> = para.tab_stops.iter().map(|s| s.position).collect();
+    assert_eq!(positions, [72.0, 144.0]);
+    convert(&input);
+}
+
+/// A decimal tab places the separator at the stop however the text before it
+/// is drawn: lowercase text in caps is measured as the capitals shown.
+#[test]
+fn decimal_tabs_measure_caps_as_drawn() {
+    let tabs = r#"<w:pPr><w:tabs><w:tab w:val="decimal" w:pos="4320"/></w:tabs></w:pPr>"#;
+    let body = format!(
+        r#"<w:p>{tabs}<w:r><w:tab/></w:r><w:r><w:rPr><w:caps/></w:rPr><w:t>ab</w:t></w:r><w:r><w:t>.5</w:t></w:r></w:p><w:p>{tabs}<w:r><w:tab/></w:r><w:r><w:t>AB</w:t></w:r><w:r><w:t>.5</w:t></w:r></w:p>"#
+    );
+    let pdf = convert(&docx(&body, LETTER, &[]));
+    let shown = shown_text(&pages(&pdf)[0].1);
+    let starts: Vec<f32> = shown
+        .iter()
+        .filter(|s| s.text.starts_with("AB"))
+        .map(|s| s.x)
+        .collect();
+    assert_eq!(starts.len(), 2, "{shown:?}");
+    assert!((starts[0] - starts[1]).abs() < 0.01, "{shown:?}");
+}
+
+/// A header character its font lacks is set in a fallback font when the
+/// document is rendered through the public entry point, which renders a copy
+/// sharing the caller's headers.
+#[test]
+fn header_characters_fall_back_through_render_pdf() {
+    let rels = br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/></Relationships>"#;
+    let header = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr xmlns:w="{WML_NS}"><w:p><w:r><w:rPr><w:rFonts w:ascii="Helvetica" w:hAnsi="Helvetica"/></w:rPr><w:t>Ω</w:t></w:r></w:p></w:hdr>"#
+    );
+    let sect_pr = format!(
+        r#"<w:headerReference xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" w:type="default" r:id="rId1"/>{LETTER}"#
+    );
+    let doc = parse(
+        "header-fallback",
+        &docx(
+            "<w:p><w:r><w:t>Body</w:t></w:r></w:p>",
+            &sect_pr,
+            &[
+                ("word/_rels/document.xml.rels", rels),
+                ("word/header1.xml", header.as_bytes()),
+            ],
+        ),
+    );
+    let options = ConversionOptions {
+        fallback_fonts: vec!["DejaVu Sans".to_string()],
+        omit_dates: true,
+        ..Default::default()
+    };
+    let pdf = docxside_pdf::render_pdf_with_options(&doc, &options).unwrap();
+    let (page, _) = &pages(&pdf)[0];
+    let fonts = page_fonts(&pdf, page);
+    assert!(
+        fonts.iter().any(|(_, dict)| dict.contains("DejaVuSans")),
+        "{fonts:?}"
+    );
+}
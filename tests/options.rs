@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::Path;
+
+use docxside_pdf::{ConversionOptions, Margins};
+
+fn page_count(pdf: &[u8]) -> usize {
+    pdf.windows(b"/Type /Page\n".len())
+        .filter(|w| w == b"/Type /Page\n")
+        .count()
+}
+
+fn convert(fixture: &str, options: &ConversionOptions) -> Result<Vec<u8>, docxside_pdf::Error> {
+    let input = fs::read(Path::new("tests/fixtures").join(fixture).join("input.docx")).unwrap();
+    docxside_pdf::convert_docx_bytes_with_options(&input, options)
+}
+
+#[test]
+fn page_range_keeps_only_the_selected_pages() {
+    let full = page_count(&convert("case6", &ConversionOptions::default()).unwrap());
+    assert!(full >= 3, "case6 should span several pages");
+
+    let options = ConversionOptions {
+        pages: Some(2..=3),
+        ..Default::default()
+    };
+    let pdf = convert("case6", &options).unwrap();
+    assert_eq!(page_count(&pdf), 2);
+    // Viewers show the original page numbers
+    assert!(pdf.windows(b"/St 2".len()).any(|w| w == b"/St 2"));
+
+    let options = ConversionOptions {
+        pages: Some(full + 1..=full + 2),
+        ..Default::default()
+    };
+    assert!(convert("case6", &options).is_err());
+}
+
+#[test]
+fn margin_override_changes_the_layout() {
+    let full = page_count(&convert("case6", &ConversionOptions::default()).unwrap());
+    let options = ConversionOptions {
+        override_margins: Some(Margins::uniform(200.0)),
+        ..Default::default()
+    };
+    let narrow = page_count(&convert("case6", &options).unwrap());
+    assert!(
+        narrow > full,
+        "wide margins should need more pages ({narrow} vs {full})"
+    );
+}
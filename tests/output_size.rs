@@ -0,0 +1,54 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Generated PDFs must not be larger than Word's own export of the same
+/// document. Uncompressed content streams or fonts, or an image embedded once
+/// per use, blow well past this.
+#[test]
+fn output_no_larger_than_reference() {
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let mut oversized = Vec::new();
+
+    println!();
+    for fixture_dir in &fixtures {
+        let name = fixture_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let reference_pdf = fixture_dir.join("reference.pdf");
+        let Ok(reference) = fs::metadata(&reference_pdf) else {
+            continue;
+        };
+        let output_base = PathBuf::from("tests/output").join(&name);
+        fs::create_dir_all(&output_base).ok();
+        let generated_pdf = output_base.join("size.pdf");
+        let _ = fs::remove_file(&generated_pdf);
+
+        if let Err(e) =
+            docxside_pdf::convert_docx_to_pdf(&fixture_dir.join("input.docx"), &generated_pdf)
+        {
+            println!("  [SKIP] {name}: {e}");
+            continue;
+        }
+        let generated = fs::metadata(&generated_pdf).unwrap().len();
+        let ratio = generated as f64 / reference.len() as f64;
+        println!(
+            "  {name:<12} {generated:>9} bytes  reference {:>9}  ({:.0}%)",
+            reference.len(),
+            ratio * 100.0
+        );
+        if generated > reference.len() {
+            oversized.push(name);
+        }
+    }
+
+    assert!(
+        oversized.is_empty(),
+        "output larger than the reference PDF: {}",
+        oversized.join(", ")
+    );
+}
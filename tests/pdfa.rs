@@ -0,0 +1,69 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use docxside_pdf::ConversionOptions;
+
+fn verapdf_available() -> bool {
+    Command::new("verapdf").arg("--version").output().is_ok()
+}
+
+/// Convert every fixture in PDF/A mode and validate it against PDF/A-2b with
+/// veraPDF. Skipped when veraPDF is not installed.
+#[test]
+fn fixtures_validate_as_pdfa_2b() {
+    if !verapdf_available() {
+        println!("  [SKIP] verapdf not found on PATH");
+        return;
+    }
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let options = ConversionOptions {
+        pdfa: true,
+        ..Default::default()
+    };
+    let mut failures = Vec::new();
+
+    for fixture_dir in &fixtures {
+        let name = fixture_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let output_base = PathBuf::from("tests/output").join(&name);
+        fs::create_dir_all(&output_base).ok();
+        let generated_pdf = output_base.join("pdfa.pdf");
+        let _ = fs::remove_file(&generated_pdf);
+
+        if let Err(e) = docxside_pdf::convert_docx_to_pdf_with_options(
+            &fixture_dir.join("input.docx"),
+            &generated_pdf,
+            &options,
+        ) {
+            println!("  [FAIL] {name}: {e}");
+            failures.push(name);
+            continue;
+        }
+
+        let output = Command::new("verapdf")
+            .args(["--flavour", "2b", "--format", "text"])
+            .arg(&generated_pdf)
+            .output()
+            .expect("Failed to run verapdf");
+        let report = String::from_utf8_lossy(&output.stdout);
+        if report.lines().any(|line| line.starts_with("PASS")) {
+            println!("  [PASS] {name}");
+        } else {
+            println!("  [FAIL] {name}\n{report}");
+            failures.push(name);
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "not valid PDF/A-2b: {}",
+        failures.join(", ")
+    );
+}
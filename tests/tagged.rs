@@ -0,0 +1,97 @@
+#[allow(dead_code)]
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use docxside_pdf::ConversionOptions;
+
+/// Decompressed data of every Flate stream in a PDF.
+fn flate_streams(pdf: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams = Vec::new();
+    let mut rest = pdf;
+    while let Some(start) = find(rest, b"stream\n") {
+        let data = &rest[start + b"stream\n".len()..];
+        let Some(end) = find(data, b"\nendstream") else {
+            break;
+        };
+        if let Ok(inflated) = miniz_oxide::inflate::decompress_to_vec_zlib(&data[..end]) {
+            streams.push(inflated);
+        }
+        rest = &data[end + b"\nendstream".len()..];
+    }
+    streams
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Convert every fixture as a tagged PDF and check that it has a structure
+/// tree and that the marked-content sequences of each page are balanced.
+#[test]
+fn tagged_output_has_balanced_marked_content() {
+    let fixtures = common::discover_fixtures().expect("Failed to read tests/fixtures");
+    let options = ConversionOptions {
+        tagged: true,
+        ..Default::default()
+    };
+    let mut failures = Vec::new();
+
+    for fixture_dir in &fixtures {
+        let name = fixture_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        let output_base = PathBuf::from("tests/output").join(&name);
+        fs::create_dir_all(&output_base).ok();
+        let generated_pdf = output_base.join("tagged.pdf");
+        let _ = fs::remove_file(&generated_pdf);
+
+        if let Err(e) = docxside_pdf::convert_docx_to_pdf_with_options(
+            &fixture_dir.join("input.docx"),
+            &generated_pdf,
+            &options,
+        ) {
+            println!("  [SKIP] {name}: {e}");
+            continue;
+        }
+        let pdf = fs::read(&generated_pdf).unwrap();
+        if find(&pdf, b"/StructTreeRoot").is_none() || find(&pdf, b"/MarkInfo").is_none() {
+            println!("  [FAIL] {name}: no structure tree");
+            failures.push(name);
+            continue;
+        }
+
+        let mut mcids = 0;
+        let mut balanced = true;
+        for stream in flate_streams(&pdf) {
+            let text = String::from_utf8_lossy(&stream);
+            let mut depth = 0i32;
+            for line in text.lines() {
+                if line.ends_with(" BDC") || line.ends_with(" BMC") {
+                    depth += 1;
+                } else if line == "EMC" {
+                    depth -= 1;
+                    balanced &= depth >= 0;
+                } else if line.trim_start().starts_with("/MCID ") {
+                    mcids += 1;
+                }
+            }
+            balanced &= depth == 0;
+        }
+        if balanced && mcids > 0 {
+            println!("  [PASS] {name}: {mcids} marked-content sequences");
+        } else {
+            println!("  [FAIL] {name}: balanced={balanced} mcids={mcids}");
+            failures.push(name);
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "bad tagged output: {}",
+        failures.join(", ")
+    );
+}
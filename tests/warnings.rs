@@ -0,0 +1,67 @@
+use std::fs;
+use std::io::{Cursor, Read, Write};
+
+use docxside_pdf::{ConversionOptions, Warning};
+
+/// A copy of case1 with `extra` inserted before the body's section properties.
+fn docx_with_body_xml(extra: &str) -> Vec<u8> {
+    let input = fs::read("tests/fixtures/case1/input.docx").unwrap();
+    let mut source = zip::ZipArchive::new(Cursor::new(input)).unwrap();
+    let mut out = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..source.len() {
+        let mut entry = source.by_index(i).unwrap();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).unwrap();
+        if entry.name() == "word/document.xml" {
+            let xml = String::from_utf8(data).unwrap();
+            let at = xml.rfind("<w:sectPr").unwrap();
+            data = format!("{}{extra}{}", &xml[..at], &xml[at..]).into_bytes();
+        }
+        out.start_file(entry.name(), zip::write::SimpleFileOptions::default())
+            .unwrap();
+        out.write_all(&data).unwrap();
+    }
+    out.finish().unwrap().into_inner()
+}
+
+#[test]
+fn skipped_content_is_reported() {
+    let dir = std::env::temp_dir().join("docxside-pdf-warnings");
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("unsupported.docx");
+    let output = dir.join("unsupported.pdf");
+    fs::write(
+        &input,
+        docx_with_body_xml(r#"<w:altChunk r:id="rIdMissing"/><w:altChunk r:id="rIdMissing"/>"#),
+    )
+    .unwrap();
+
+    let report =
+        docxside_pdf::convert_docx_to_pdf_report(&input, &output, &ConversionOptions::default())
+            .unwrap();
+    assert!(output.exists());
+    assert!(
+        report.warnings.contains(&Warning::UnsupportedElement {
+            tag: "altChunk".into(),
+            count: 2,
+        }),
+        "{:?}",
+        report.warnings
+    );
+}
+
+#[test]
+fn in_memory_conversions_report_warnings() {
+    let input = docx_with_body_xml(r#"<w:altChunk r:id="rIdMissing"/>"#);
+    let (pdf, report) =
+        docxside_pdf::convert_docx_bytes_report(&input, &ConversionOptions::default()).unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    assert!(
+        report.warnings.contains(&Warning::UnsupportedElement {
+            tag: "altChunk".into(),
+            count: 1,
+        }),
+        "{:?}",
+        report.warnings
+    );
+}